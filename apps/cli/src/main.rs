@@ -13,10 +13,7 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use launcher::{fetch, net, utils};
-use url::Url;
-
-const BASE_URL: &str = "https://launchermeta.lambda.prod.andre4ik3.net/";
+use launcher::{fetch, instance, net, utils};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -24,9 +21,10 @@ async fn main() -> anyhow::Result<()> {
     let _guard = utils::log::setup();
     let client = net::Client::new().await;
 
-    let url = Url::parse(BASE_URL)?;
+    let settings = instance::settings::open().await?;
+    let settings = instance::settings::effective(&settings, None).await;
 
-    let index = fetch::meta::index(&client, &url).await?;
+    let index = fetch::meta::index(&client, &settings.meta_url.value).await?;
 
     println!("{:#?}", index);
 
@@ -79,7 +79,11 @@ struct JavaInstallationsView: View {
                     isTaskRunning = true
                     Task {
                         for id in selection {
-                            await bridge.rust.java_update(id)
+                            do {
+                                try await bridge.rust.java_update(id)
+                            } catch {
+                                print("Failed to update \(id): \(error)")
+                            }
                         }
                         await refresh()
                         isTaskRunning = false
@@ -13,14 +13,18 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use launcher::data::models::JavaInfo;
+use launcher::data::core::java::JavaInfo;
 use launcher::data::web::microsoft::AUTH_URL;
 use launcher::auth::AuthenticationService;
 use launcher::net::Client;
-use launcher::fetch::meta::java_build;
-use launcher::repo::{JAVA, Repo};
+use launcher::fetch::java::JavaManager;
+use launcher::instance::InstanceManager;
+use launcher::instance::settings::{self, SettingsRegistry};
 use launcher::store::{CONFIG, CREDENTIALS, ConfigHolder, CredentialsHolder, StoreHolder};
 
+use tokio::sync::Mutex;
+use url::Url;
+
 use crate::ffi::FFIJava;
 
 // Rust extension freaks out here, "extern types is experimental"
@@ -51,7 +55,7 @@ mod ffi {
         async fn java_len(&self) -> usize;
         async fn java_at(&self, index: usize) -> FFIJava;
         async fn java_install(&self, version: u8);
-        async fn java_update(&self, id: String);
+        async fn java_update(&self, id: String) -> Result<(), String>;
         async fn java_uninstall(&self, id: String);
     }
 }
@@ -60,6 +64,9 @@ pub struct LauncherBridge {
     client: Client,
     config: Option<&'static ConfigHolder>,
     credentials: Option<&'static CredentialsHolder>,
+    java: Option<Mutex<JavaManager>>,
+    instances: Option<InstanceManager>,
+    settings: Option<SettingsRegistry>,
 }
 
 impl LauncherBridge {
@@ -68,12 +75,28 @@ impl LauncherBridge {
             client: Client::new(),
             config: None,
             credentials: None,
+            java: None,
+            instances: None,
+            settings: None,
         }
     }
 
     async fn setup(&mut self) {
         self.config = Some(CONFIG.get().await);
         self.credentials = Some(CREDENTIALS.get().await);
+        self.settings = settings::open().await.ok();
+        self.instances = InstanceManager::new().await.ok();
+
+        if let Ok(mut java) = JavaManager::new().await {
+            java.discover().await;
+            self.java = Some(Mutex::new(java));
+        }
+    }
+
+    /// The base URL of the meta server, from the settings.
+    async fn meta_url(&self) -> Url {
+        let settings = self.settings.as_ref().expect("Settings are not set up");
+        settings::effective(settings, None).await.meta_url.value
     }
 
     fn get_login_url(&self) -> String {
@@ -101,49 +124,52 @@ impl LauncherBridge {
     /// Gets the total amount of Java installations. A hack because `swift-bridge` can't handle
     /// vectors of strings (or structs that have them).
     async fn java_len(&self) -> usize {
-        JAVA.get()
-            .await
-            .list()
-            .await
-            .map(|list| list.len())
-            .unwrap_or(0)
+        match &self.java {
+            Some(java) => java.lock().await.list().len(),
+            None => 0,
+        }
     }
 
     /// Gets a Java installation at an index from `java_len()`. A hack because `swift-bridge` can't
     /// handle vectors of strings (or structs that have them).
     async fn java_at(&self, index: usize) -> FFIJava {
-        JAVA.get()
-            .await
-            .list()
-            .await
-            .ok()
-            .and_then(|java| {
-                let mut values: Vec<(String, JavaInfo)> = java.into_iter().collect();
-                values.sort();
-                values.into_iter().nth(index)
-            })
-            .map(|(id, info)| FFIJava::from_info(id, info))
+        let java = self.java.as_ref().expect("Java manager is not set up").lock().await;
+        java.list()
+            .into_iter()
+            .nth(index)
+            .map(|(id, info)| FFIJava::from_info(id.to_string(), info.clone()))
             .expect("Invalid index (issue on Swift's side)")
     }
 
     /// Installs a major Java version from the UI.
     async fn java_install(&self, version: u8) {
         // TODO: Error handling
-        let client = Client::new().await;
-        let build = java_build(&self.client, version).await.unwrap();
-        let archive = self.client.download(build).await.unwrap();
-        JAVA.get().await.add(archive).await.unwrap();
+        let base = self.meta_url().await;
+        let mut java = self.java.as_ref().expect("Java manager is not set up").lock().await;
+        java.install_version(&self.client, &base, version as u64).await.unwrap();
     }
 
-    /// Updates a Java build from the UI.
-    async fn java_update(&self, id: String) {
-        todo!()
+    /// Updates a Java build from the UI. The old build is kept if any instance is pinned to it.
+    async fn java_update(&self, id: String) -> Result<(), String> {
+        let base = self.meta_url().await;
+        let instances = self.instances.as_ref().ok_or("Instances are not set up")?;
+        let pinned = instances.pinned_java();
+
+        let mut java = self.java.as_ref().ok_or("Java manager is not set up")?.lock().await;
+        let updates = java.check_updates(&self.client, &base).await.map_err(|it| it.to_string())?;
+
+        if let Some(update) = updates.into_iter().find(|it| it.id == id) {
+            java.update(&self.client, &update, &pinned).await.map_err(|it| it.to_string())?;
+        }
+
+        Ok(())
     }
 
     /// Uninstalls a Java build from the UI.
     async fn java_uninstall(&self, id: String) {
         // TODO: Error handling
-        JAVA.get().await.delete(id).await.expect("Gone wrong")
+        let mut java = self.java.as_ref().expect("Java manager is not set up").lock().await;
+        java.uninstall(&id).await.expect("Gone wrong")
     }
 }
 
@@ -86,6 +86,13 @@ async fn main() -> anyhow::Result<()> {
         game_versions.len()
     );
 
+    // Asset indexes
+    let asset_indexes = task::assets::run(&game_versions, args.power_wash).await?;
+    tracing::info!(
+        "Successfully fetched {} asset indexes.",
+        asset_indexes.len()
+    );
+
     // let loader_versions = task::loaders::run(game_versions).await?;
     // tracing::info!("Successfully loaded mod loaders.");
 
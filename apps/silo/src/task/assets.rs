@@ -0,0 +1,68 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use launcher::data::core::assets::AssetIndex;
+use launcher::data::core::game::{GameVersion, GameVersionAssetIndex};
+use launcher::data::silo::game::AssetIndex as ApiAssetIndex;
+use tokio::fs;
+
+use crate::macros::write_to_ron_file;
+use crate::{client, vpath};
+
+pub async fn run(game_versions: &[GameVersion], power_wash: bool) -> anyhow::Result<Vec<String>> {
+    let client = client();
+    let mut output = Vec::new();
+
+    // First, collect every distinct asset index. Versions are ordered newest first, so if an index
+    // has changed over time, the newest reference to it wins.
+    let mut indexes = BTreeMap::<&str, &GameVersionAssetIndex>::new();
+    for version in game_versions {
+        indexes
+            .entry(version.asset_index.id.as_str())
+            .or_insert(&version.asset_index);
+    }
+
+    // Then, for every index...
+    for (id, index) in indexes {
+        let path = vpath!("assets/{id}.ron");
+
+        // ...if we don't already have it (and we're not set to do a power wash)...
+        if fs::try_exists(&path).await.unwrap_or(false) && !power_wash {
+            if ron::from_str::<AssetIndex>(&fs::read_to_string(&path).await?).is_ok() {
+                tracing::info!("Skipping asset index {id} as it appears we already have it.");
+                output.push(id.to_string());
+                continue;
+            } else {
+                tracing::warn!("Removing malformed asset index {id} at {}", path.display());
+                fs::remove_file(&path).await?;
+            }
+        }
+
+        // ...fetch the index...
+        let data: ApiAssetIndex = client.get(index.file.url.clone()).await?.json().await?;
+
+        // ...convert it to a better format...
+        let data = AssetIndex::from(data);
+
+        // ...and save it to disk.
+        tracing::info!("Fetched asset index {id} ({} objects).", data.objects.len());
+        write_to_ron_file(&path, &data).await?;
+        output.push(id.to_string());
+    }
+
+    Ok(output)
+}
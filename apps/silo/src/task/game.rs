@@ -22,7 +22,7 @@ use crate::{client, vpath};
 
 const INDEX_URL: &str = "https://launchermeta.mojang.com/mc/game/version_manifest_v2.json";
 
-pub async fn run(power_wash: bool) -> anyhow::Result<Vec<GameVersionSnippet>> {
+pub async fn run(power_wash: bool) -> anyhow::Result<Vec<GameVersion>> {
     let client = client();
     let mut output = Vec::new();
 
@@ -41,7 +41,7 @@ pub async fn run(power_wash: bool) -> anyhow::Result<Vec<GameVersionSnippet>> {
                     version.id
                 );
                 write_to_ron_file(&path, &data).await?;
-                output.push(GameVersionSnippet::from(data));
+                output.push(data);
                 continue;
             } else {
                 tracing::warn!(
@@ -62,11 +62,16 @@ pub async fn run(power_wash: bool) -> anyhow::Result<Vec<GameVersionSnippet>> {
         // ...and save it to disk.
         tracing::info!("Fetched version {}.", version.id);
         write_to_ron_file(&path, &data).await?;
-        output.push(GameVersionSnippet::from(data));
+        output.push(data);
     }
 
     // Finally, write an index of all available versions.
-    write_to_ron_file(vpath!("game.ron"), &output).await?;
+    let index: Vec<_> = output
+        .iter()
+        .cloned()
+        .map(GameVersionSnippet::from)
+        .collect();
+    write_to_ron_file(vpath!("game.ron"), &index).await?;
     tracing::info!("Loaded {} game versions", output.len());
 
     Ok(output)
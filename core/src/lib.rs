@@ -16,6 +16,8 @@
 pub use auth;
 pub use data;
 pub use fetch;
+pub use instance;
+pub use launch;
 pub use macros;
 pub use net;
 pub use persistence;
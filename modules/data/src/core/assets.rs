@@ -12,3 +12,130 @@
 //
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use serde::de::{Error, Unexpected};
+use serde::{Deserialize, Deserializer};
+
+use macros::data_structure;
+
+/// An index of the assets (textures, sounds, languages, etc.) used by one or more game versions.
+#[data_structure]
+pub struct AssetIndex {
+    /// Whether the assets should also be laid out by their names in `virtual/{id}` of the asset
+    /// store, instead of only by their hashes. Used by old versions (the "legacy" index).
+    pub is_virtual: bool,
+    /// Whether the assets should be copied by their names into the `resources` directory of the
+    /// instance. Used by very old versions (the "pre-1.6" index).
+    pub map_to_resources: bool,
+    /// The assets, by their names (e.g. `minecraft/sounds/ambient/cave/cave1.ogg`).
+    pub objects: BTreeMap<String, Asset>,
+}
+
+/// A single file in an [AssetIndex].
+#[data_structure]
+pub struct Asset {
+    /// The SHA1 checksum of the file, which is also its name in the asset store. Indexes with
+    /// anything other than 40 hexadecimal digits here are rejected, as the hash ends up in a path.
+    #[serde(deserialize_with = "sha1")]
+    pub hash: String,
+    /// The size of the file in bytes.
+    pub size: u64,
+}
+
+// === impl ===
+
+impl Asset {
+    /// The path where the asset is saved, relative to the `objects` directory of the asset store
+    /// (e.g. `b6/b62ca8ec10d07e6bf5ac8dae0c8c1d2e6a1e3356`).
+    pub fn path(&self) -> String {
+        match self.hash.get(..2) {
+            Some(prefix) => format!("{prefix}/{}", self.hash),
+            None => self.hash.clone(),
+        }
+    }
+}
+
+/// Deserializes a SHA1 checksum, failing for anything that isn't one.
+pub(crate) fn sha1<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let hash = String::deserialize(deserializer)?;
+    match hash.len() == 40 && hash.bytes().all(|it| it.is_ascii_hexdigit()) {
+        true => Ok(hash),
+        false => Err(D::Error::invalid_value(
+            Unexpected::Str(&hash),
+            &"a SHA1 checksum",
+        )),
+    }
+}
+
+// === conversion ===
+
+#[cfg(feature = "silo")]
+impl From<crate::silo::game::AssetIndex> for AssetIndex {
+    fn from(value: crate::silo::game::AssetIndex) -> Self {
+        Self {
+            is_virtual: value.is_virtual.unwrap_or(false),
+            map_to_resources: value.map_to_resources.unwrap_or(false),
+            objects: value
+                .objects
+                .into_iter()
+                .map(|(name, object)| {
+                    let asset = Asset {
+                        hash: object.hash,
+                        size: object.size,
+                    };
+                    (name, asset)
+                })
+                .collect(),
+        }
+    }
+}
+
+// === test ===
+
+#[cfg(all(test, feature = "silo"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy() {
+        let json = r#"{
+            "virtual": true,
+            "objects": {
+                "sounds/random/click.ogg": {
+                    "hash": "4e8f5d0b2bb6e0d7dbbd0bf0a4d3b6fb1e7bcd5f",
+                    "size": 5427
+                }
+            }
+        }"#;
+
+        let index: crate::silo::game::AssetIndex = serde_json::from_str(json).unwrap();
+        let index = AssetIndex::from(index);
+
+        assert!(index.is_virtual);
+        assert!(!index.map_to_resources);
+
+        let asset = &index.objects["sounds/random/click.ogg"];
+        assert_eq!(asset.path(), "4e/4e8f5d0b2bb6e0d7dbbd0bf0a4d3b6fb1e7bcd5f");
+        assert_eq!(asset.size, 5427);
+
+        // hashes become paths, so anything but a SHA1 checksum is rejected
+        let split = "4é8f5d0b2bb6e0d7dbbd0bf0a4d3b6fb1e7bcd5";
+        for hash in ["../../../../etc/passwd", split, ""] {
+            let json = serde_json::json!({ "hash": hash, "size": 1 });
+            assert!(serde_json::from_value::<Asset>(json.clone()).is_err());
+            let object = serde_json::from_value::<crate::silo::game::AssetIndexObject>(json);
+            assert!(object.is_err());
+        }
+
+        let asset = Asset {
+            hash: split.to_string(),
+            size: 1,
+        };
+        assert_eq!(asset.path(), split);
+    }
+}
@@ -18,12 +18,39 @@ use std::env::consts;
 use std::str::FromStr;
 
 use platforms::{Arch, OS};
+use serde::Serialize;
 
 use macros::data_structure;
 
+pub use syntax::{ParseError, ParseErrorKind, text};
+
+mod syntax;
+
 /// Condition for inclusion of arguments and libraries.
-#[derive(Hash)]
-#[data_structure(equatable)]
+///
+/// Conditions are serialized as trees, but they can also be written in a compact textual form, e.g.
+/// `os == macos && !feature("is_demo_user")`. The grammar, from lowest to highest precedence:
+///
+/// ```text
+/// expression := and ( "||" and )*
+/// and        := unary ( "&&" unary )*
+/// unary      := "!" unary | primary
+/// primary    := "(" expression ")"
+///             | "always" | "never"
+///             | "feature" "(" string ")"
+///             | ( "os" | "arch" ) ( "==" | "!=" ) identifier
+///             | ( "all" | "any" | "xor" ) "(" [ expression ( "," expression )* ] ")"
+/// ```
+///
+/// Chains of `&&` and `||` become a single [Condition::And] or [Condition::Or], while parentheses
+/// always introduce a new level of nesting. The function forms `all(...)`, `any(...)` and
+/// `xor(...)` are used for lists that can't be expressed with operators (e.g. empty lists), so
+/// that formatting a condition and parsing it back always yields the same condition.
+///
+/// The textual form is parsed with [FromStr] and written with [Display](std::fmt::Display).
+/// Deserializing accepts it in place of a tree in formats that pass strings to enums (e.g. JSON),
+/// and fields that should be written in it can use [text].
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize)]
 pub enum Condition {
     Always,
     Never,
@@ -36,8 +63,21 @@ pub enum Condition {
     Xor(Vec<Condition>),
 }
 
+/// The environment that conditions are evaluated against. Usually this is the current platform
+/// (see [EvalContext::current]), but it can also describe another one, e.g. to prepare a launch
+/// for a different machine.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EvalContext {
+    /// The operating system that [Condition::OS] is checked against.
+    pub os: OS,
+    /// The architecture that [Condition::Arch] is checked against.
+    pub arch: Arch,
+    /// The enabled features that [Condition::Feature] is checked against.
+    pub features: Vec<String>,
+}
+
 /// A helper enum for expressing a value that may or may not have an associated condition.
-#[data_structure]
+#[data_structure(equatable)]
 pub enum MaybeConditional<T> {
     Unconditional(T),
     Conditional { when: Condition, then: T },
@@ -68,21 +108,37 @@ macro_rules! simplify_impl {
     }};
 }
 
+impl EvalContext {
+    /// Creates a context for the platform that the launcher is currently running on.
+    pub fn current(features: Vec<String>) -> Self {
+        Self {
+            os: OS::from_str(consts::OS).unwrap(),
+            arch: Arch::from_str(consts::ARCH).unwrap(),
+            features,
+        }
+    }
+}
+
 impl Condition {
-    /// Evaluates a condition to a boolean.
-    pub fn eval(&self, features: &Vec<String>) -> bool {
+    /// Evaluates a condition to a boolean on the current platform.
+    pub fn eval(&self, features: &[String]) -> bool {
+        self.eval_with(&EvalContext::current(features.to_vec()))
+    }
+
+    /// Evaluates a condition to a boolean in the given context.
+    pub fn eval_with(&self, context: &EvalContext) -> bool {
         match self {
             Self::Always => true,
             Self::Never => false,
-            Self::Feature(feature) => features.contains(feature),
-            Self::OS(os) => os == &OS::from_str(consts::OS).unwrap(),
-            Self::Arch(arch) => arch == &Arch::from_str(consts::ARCH).unwrap(),
-            Self::Not(val) => !val.eval(features),
-            Self::And(vals) => vals.iter().all(|v| v.eval(features)),
-            Self::Or(vals) => vals.iter().any(|v| v.eval(features)),
+            Self::Feature(feature) => context.features.contains(feature),
+            Self::OS(os) => os == &context.os,
+            Self::Arch(arch) => arch == &context.arch,
+            Self::Not(val) => !val.eval_with(context),
+            Self::And(vals) => vals.iter().all(|v| v.eval_with(context)),
+            Self::Or(vals) => vals.iter().any(|v| v.eval_with(context)),
             Self::Xor(vals) => vals
                 .iter()
-                .map(|v| v.eval(features) as usize)
+                .map(|v| v.eval_with(context) as usize)
                 .reduce(|a, b| a + b)
                 .unwrap_or(0)
                 .eq(&1),
@@ -108,7 +164,7 @@ impl Condition {
                 Self::Always => Self::Never,
                 Self::Never => Self::Always,
                 Self::Not(val) => val.simplify(),
-                _ => val.simplify(),
+                other => Self::Not(Box::new(other.simplify())),
             },
             _ => self,
         }
@@ -118,15 +174,28 @@ impl Condition {
 impl<T> MaybeConditional<T> {
     /// Evaluates the inner condition (if one exists) and returns the inner value expressed as an
     /// [Option].
-    pub fn fold(self, features: &Vec<String>) -> Option<T> {
+    pub fn fold(self, features: &[String]) -> Option<T> {
+        self.fold_with(&EvalContext::current(features.to_vec()))
+    }
+
+    /// Same as [MaybeConditional::fold], but evaluates the condition in the given context.
+    pub fn fold_with(self, context: &EvalContext) -> Option<T> {
         match self {
             Self::Unconditional(val) => Some(val),
-            Self::Conditional { when, then } => match when.eval(features) {
+            Self::Conditional { when, then } => match when.eval_with(context) {
                 true => Some(then),
                 false => None,
             },
         }
     }
+
+    /// The inner value, regardless of whether its condition holds.
+    pub fn value(&self) -> &T {
+        match self {
+            Self::Unconditional(val) => val,
+            Self::Conditional { then, .. } => then,
+        }
+    }
 }
 
 // === conversion ===
@@ -257,6 +326,30 @@ mod tests {
         assert!(!Condition::Feature("some-random-feature".to_string()).eval(&features));
     }
 
+    #[test]
+    fn eval_with() {
+        let context = EvalContext {
+            os: OS::Windows,
+            arch: Arch::AArch64,
+            features: vec!["feature-1".to_string()],
+        };
+
+        // Should be evaluated against the context instead of the current platform
+        assert!(Condition::OS(OS::Windows).eval_with(&context));
+        assert!(!Condition::OS(OS::MacOS).eval_with(&context));
+        assert!(Condition::Arch(Arch::AArch64).eval_with(&context));
+        assert!(!Condition::Arch(Arch::X86_64).eval_with(&context));
+
+        let conditional = MaybeConditional::Conditional {
+            when: Condition::And(vec![
+                Condition::OS(OS::Windows),
+                Condition::Feature("feature-1".to_string()),
+            ]),
+            then: 123,
+        };
+        assert_eq!(conditional.fold_with(&context), Some(123));
+    }
+
     #[test]
     fn simplify() {
         // Empty arrays should simplify to be always true
@@ -277,6 +370,10 @@ mod tests {
             Condition::Not(Box::new(Condition::Not(Box::new(feature.clone())))).simplify(),
             feature
         );
+        assert_eq!(
+            Condition::Not(Box::new(feature.clone())).simplify(),
+            Condition::Not(Box::new(feature.clone()))
+        );
 
         // Simplification of And(..., Never, ...) and Or(..., Always, ...) should always be false and true
         assert_eq!(
@@ -0,0 +1,690 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The compact textual syntax for [Condition]s, e.g. `os == macos && !feature("is_demo_user")`.
+//! See [Condition] for the grammar.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use platforms::{Arch, OS};
+use serde::de::value::MapAccessDeserializer;
+use serde::de::{self, EnumAccess, MapAccess, VariantAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use thiserror::Error;
+
+use super::Condition;
+
+/// The kinds of errors that can occur when parsing a condition expression.
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+pub enum ParseErrorKind {
+    #[error("unexpected end of input, expected {0}")]
+    UnexpectedEnd(&'static str),
+    #[error("unexpected `{0}`, expected {1}")]
+    UnexpectedToken(String, &'static str),
+    #[error("unexpected character `{0}`")]
+    UnexpectedCharacter(char),
+    #[error(
+        "unknown keyword `{0}`, expected one of `always`, `never`, `feature`, `os`, `arch`, `all`, `any` or `xor`"
+    )]
+    UnknownKeyword(String),
+    #[error("unknown operating system `{0}`")]
+    UnknownOs(String),
+    #[error("unknown architecture `{0}`")]
+    UnknownArch(String),
+    #[error("unterminated string literal")]
+    UnterminatedString,
+    #[error("invalid escape sequence `\\{0}` in string literal")]
+    InvalidEscape(char),
+}
+
+/// An error that occurred when parsing a condition expression, along with where it occurred.
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+#[error("{kind} (at column {column})")]
+pub struct ParseError {
+    /// What went wrong.
+    pub kind: ParseErrorKind,
+    /// The 1-based column (in characters) of the input where the error occurred.
+    pub column: usize,
+}
+
+// === lexer ===
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Token {
+    LeftParen,
+    RightParen,
+    Comma,
+    Not,
+    And,
+    Or,
+    Equal,
+    NotEqual,
+    Identifier(String),
+    String(String),
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LeftParen => f.write_str("("),
+            Self::RightParen => f.write_str(")"),
+            Self::Comma => f.write_str(","),
+            Self::Not => f.write_str("!"),
+            Self::And => f.write_str("&&"),
+            Self::Or => f.write_str("||"),
+            Self::Equal => f.write_str("=="),
+            Self::NotEqual => f.write_str("!="),
+            Self::Identifier(ident) => f.write_str(ident),
+            Self::String(string) => write_string(f, string),
+        }
+    }
+}
+
+/// Splits the input into tokens, each paired with the column where it starts.
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().zip(1..).peekable();
+
+    let error = |kind, column| ParseError { kind, column };
+
+    while let Some((char, column)) = chars.next() {
+        let token = match char {
+            _ if char.is_whitespace() => continue,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            ',' => Token::Comma,
+            '!' if chars.next_if(|(it, _)| *it == '=').is_some() => Token::NotEqual,
+            '!' => Token::Not,
+            '&' | '|' | '=' => match chars.next_if(|(it, _)| *it == char) {
+                Some(_) if char == '&' => Token::And,
+                Some(_) if char == '|' => Token::Or,
+                Some(_) => Token::Equal,
+                None => return Err(error(ParseErrorKind::UnexpectedCharacter(char), column)),
+            },
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        None => return Err(error(ParseErrorKind::UnterminatedString, column)),
+                        Some(('"', _)) => break,
+                        Some(('\\', escape_column)) => match chars.next() {
+                            Some((escaped @ ('"' | '\\'), _)) => string.push(escaped),
+                            Some((other, _)) => {
+                                let kind = ParseErrorKind::InvalidEscape(other);
+                                return Err(error(kind, escape_column));
+                            }
+                            None => return Err(error(ParseErrorKind::UnterminatedString, column)),
+                        },
+                        Some((other, _)) => string.push(other),
+                    }
+                }
+                Token::String(string)
+            }
+            _ if is_identifier_char(char) => {
+                let mut ident = String::from(char);
+                while let Some((it, _)) = chars.next_if(|(it, _)| is_identifier_char(*it)) {
+                    ident.push(it);
+                }
+                Token::Identifier(ident)
+            }
+            _ => return Err(error(ParseErrorKind::UnexpectedCharacter(char), column)),
+        };
+
+        tokens.push((token, column));
+    }
+
+    Ok(tokens)
+}
+
+fn is_identifier_char(char: char) -> bool {
+    char.is_ascii_alphanumeric() || char == '_'
+}
+
+// === parser ===
+
+/// A simple recursive descent parser over the output of [tokenize].
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    /// The column just past the end of the input, used for "unexpected end" errors.
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn next(&mut self, expected: &'static str) -> Result<(Token, usize), ParseError> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token.ok_or(ParseError {
+            kind: ParseErrorKind::UnexpectedEnd(expected),
+            column: self.end,
+        })
+    }
+
+    fn expect(&mut self, token: Token, expected: &'static str) -> Result<(), ParseError> {
+        match self.next(expected)? {
+            (found, _) if found == token => Ok(()),
+            (found, column) => Err(unexpected(found, column, expected)),
+        }
+    }
+
+    fn expression(&mut self) -> Result<Condition, ParseError> {
+        let mut operands = vec![self.and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            operands.push(self.and()?);
+        }
+
+        Ok(match operands.len() {
+            1 => operands.swap_remove(0),
+            _ => Condition::Or(operands),
+        })
+    }
+
+    fn and(&mut self) -> Result<Condition, ParseError> {
+        let mut operands = vec![self.unary()?];
+        while self.peek() == Some(&Token::And) {
+            self.position += 1;
+            operands.push(self.unary()?);
+        }
+
+        Ok(match operands.len() {
+            1 => operands.swap_remove(0),
+            _ => Condition::And(operands),
+        })
+    }
+
+    fn unary(&mut self) -> Result<Condition, ParseError> {
+        if self.peek() == Some(&Token::Not) {
+            self.position += 1;
+            return Ok(Condition::Not(Box::new(self.unary()?)));
+        }
+
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Condition, ParseError> {
+        const EXPECTED: &str = "a condition";
+
+        let (token, column) = self.next(EXPECTED)?;
+        let keyword = match token {
+            Token::LeftParen => {
+                let condition = self.expression()?;
+                self.expect(Token::RightParen, "`)`")?;
+                return Ok(condition);
+            }
+            Token::Identifier(keyword) => keyword,
+            other => return Err(unexpected(other, column, EXPECTED)),
+        };
+
+        match keyword.as_str() {
+            "always" => Ok(Condition::Always),
+            "never" => Ok(Condition::Never),
+            "feature" => {
+                self.expect(Token::LeftParen, "`(`")?;
+                let feature = match self.next("a string")? {
+                    (Token::String(feature), _) => feature,
+                    (other, column) => return Err(unexpected(other, column, "a string")),
+                };
+                self.expect(Token::RightParen, "`)`")?;
+                Ok(Condition::Feature(feature))
+            }
+            "os" | "arch" => {
+                let negated = match self.next("`==` or `!=`")? {
+                    (Token::Equal, _) => false,
+                    (Token::NotEqual, _) => true,
+                    (other, column) => return Err(unexpected(other, column, "`==` or `!=`")),
+                };
+
+                let expected = if keyword == "os" {
+                    "an operating system"
+                } else {
+                    "an architecture"
+                };
+
+                let (value, column) = match self.next(expected)? {
+                    (Token::Identifier(value), column) => (value, column),
+                    (other, column) => return Err(unexpected(other, column, expected)),
+                };
+
+                let condition = match keyword.as_str() {
+                    "os" => OS::from_str(&value)
+                        .map(Condition::OS)
+                        .map_err(|_| ParseError {
+                            kind: ParseErrorKind::UnknownOs(value),
+                            column,
+                        })?,
+                    _ => Arch::from_str(&value)
+                        .map(Condition::Arch)
+                        .map_err(|_| ParseError {
+                            kind: ParseErrorKind::UnknownArch(value),
+                            column,
+                        })?,
+                };
+
+                Ok(match negated {
+                    true => Condition::Not(Box::new(condition)),
+                    false => condition,
+                })
+            }
+            "all" => Ok(Condition::And(self.arguments()?)),
+            "any" => Ok(Condition::Or(self.arguments()?)),
+            "xor" => Ok(Condition::Xor(self.arguments()?)),
+            _ => Err(ParseError {
+                kind: ParseErrorKind::UnknownKeyword(keyword),
+                column,
+            }),
+        }
+    }
+
+    /// Parses a parenthesized, comma-separated (and possibly empty) list of conditions.
+    fn arguments(&mut self) -> Result<Vec<Condition>, ParseError> {
+        self.expect(Token::LeftParen, "`(`")?;
+
+        let mut arguments = Vec::new();
+        if self.peek() == Some(&Token::RightParen) {
+            self.position += 1;
+            return Ok(arguments);
+        }
+
+        loop {
+            arguments.push(self.expression()?);
+            match self.next("`,` or `)`")? {
+                (Token::Comma, _) => continue,
+                (Token::RightParen, _) => return Ok(arguments),
+                (other, column) => return Err(unexpected(other, column, "`,` or `)`")),
+            }
+        }
+    }
+}
+
+fn unexpected(token: Token, column: usize, expected: &'static str) -> ParseError {
+    ParseError {
+        kind: ParseErrorKind::UnexpectedToken(token.to_string(), expected),
+        column,
+    }
+}
+
+impl FromStr for Condition {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            position: 0,
+            end: s.chars().count() + 1,
+        };
+
+        let condition = parser.expression()?;
+        match parser.tokens.get(parser.position).cloned() {
+            None => Ok(condition),
+            Some((token, column)) => Err(unexpected(token, column, "`&&`, `||` or end of input")),
+        }
+    }
+}
+
+// === formatter ===
+
+fn write_string(f: &mut Formatter<'_>, string: &str) -> std::fmt::Result {
+    f.write_str("\"")?;
+    for char in string.chars() {
+        if char == '"' || char == '\\' {
+            f.write_str("\\")?;
+        }
+        write!(f, "{char}")?;
+    }
+    f.write_str("\"")
+}
+
+/// Writes a list of conditions joined by an infix operator, or in function form if the list is
+/// too short for the operator to be used.
+fn write_list(
+    f: &mut Formatter<'_>,
+    conditions: &[Condition],
+    operator: &str,
+    function: &str,
+) -> std::fmt::Result {
+    if conditions.len() < 2 {
+        return write_function(f, conditions, function);
+    }
+
+    for (index, condition) in conditions.iter().enumerate() {
+        if index > 0 {
+            write!(f, " {operator} ")?;
+        }
+
+        // Nested lists are always parenthesized, otherwise they would be merged into this one.
+        match condition {
+            Condition::And(it) | Condition::Or(it) if it.len() >= 2 => write!(f, "({condition})")?,
+            _ => write!(f, "{condition}")?,
+        }
+    }
+
+    Ok(())
+}
+
+fn write_function(f: &mut Formatter<'_>, conditions: &[Condition], name: &str) -> std::fmt::Result {
+    write!(f, "{name}(")?;
+    for (index, condition) in conditions.iter().enumerate() {
+        if index > 0 {
+            f.write_str(", ")?;
+        }
+        write!(f, "{condition}")?;
+    }
+    f.write_str(")")
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Always => f.write_str("always"),
+            Self::Never => f.write_str("never"),
+            Self::Feature(feature) => {
+                f.write_str("feature(")?;
+                write_string(f, feature)?;
+                f.write_str(")")
+            }
+            Self::OS(os) => write!(f, "os == {os}"),
+            Self::Arch(arch) => write!(f, "arch == {arch}"),
+            Self::Not(condition) => match condition.as_ref() {
+                Self::OS(os) => write!(f, "os != {os}"),
+                Self::Arch(arch) => write!(f, "arch != {arch}"),
+                Self::Always | Self::Never | Self::Feature(_) | Self::Not(_) | Self::Xor(_) => {
+                    write!(f, "!{condition}")
+                }
+                Self::And(it) | Self::Or(it) if it.len() < 2 => write!(f, "!{condition}"),
+                Self::And(_) | Self::Or(_) => write!(f, "!({condition})"),
+            },
+            Self::And(conditions) => write_list(f, conditions, "&&", "all"),
+            Self::Or(conditions) => write_list(f, conditions, "||", "any"),
+            Self::Xor(conditions) => write_function(f, conditions, "xor"),
+        }
+    }
+}
+
+// === serde ===
+
+const VARIANTS: &[&str] = &[
+    "Always", "Never", "Feature", "OS", "Arch", "Not", "And", "Or", "Xor",
+];
+
+/// The name of a variant in the tree form. Formats that pass strings to enums as unit variants
+/// (e.g. JSON) also hand over whole conditions in the textual form this way.
+struct Variant(String);
+
+impl<'de> Deserialize<'de> for Variant {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct VariantVisitor;
+
+        impl Visitor<'_> for VariantVisitor {
+            type Value = Variant;
+
+            fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+                f.write_str("a condition variant or expression")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Variant, E> {
+                Ok(Variant(value.to_string()))
+            }
+        }
+
+        deserializer.deserialize_identifier(VariantVisitor)
+    }
+}
+
+/// Accepts a condition in either the tree or the textual form, as far as the format allows it.
+struct ConditionVisitor;
+
+impl<'de> Visitor<'de> for ConditionVisitor {
+    type Value = Condition;
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.write_str("a condition tree or expression")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Condition, E> {
+        match value {
+            "Always" => Ok(Condition::Always),
+            "Never" => Ok(Condition::Never),
+            _ => Condition::from_str(value).map_err(E::custom),
+        }
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Condition, A::Error> {
+        self.visit_enum(MapAccessDeserializer::new(map))
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<Condition, A::Error> {
+        let (Variant(name), variant) = data.variant()?;
+        match name.as_str() {
+            "Always" => variant.unit_variant().map(|_| Condition::Always),
+            "Never" => variant.unit_variant().map(|_| Condition::Never),
+            "Feature" => variant.newtype_variant().map(Condition::Feature),
+            "OS" => variant.newtype_variant().map(Condition::OS),
+            "Arch" => variant.newtype_variant().map(Condition::Arch),
+            "Not" => variant.newtype_variant().map(Condition::Not),
+            "And" => variant.newtype_variant().map(Condition::And),
+            "Or" => variant.newtype_variant().map(Condition::Or),
+            "Xor" => variant.newtype_variant().map(Condition::Xor),
+            expression => match Condition::from_str(expression) {
+                Ok(condition) => variant.unit_variant().map(|_| condition),
+                // an identifier is more likely a misspelled variant than an expression
+                Err(_) if expression.chars().all(char::is_alphanumeric) => {
+                    Err(de::Error::unknown_variant(expression, VARIANTS))
+                }
+                Err(error) => Err(de::Error::custom(error)),
+            },
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Condition {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_enum("Condition", VARIANTS, ConditionVisitor)
+    }
+}
+
+/// Serializes a [Condition] in its textual form instead of the default tree form, and accepts
+/// either form when deserializing. Use it as `#[serde(with = "crate::core::conditional::text")]`.
+///
+/// Formats that drop variant names when they don't know the type (e.g. RON) only read the
+/// textual form back.
+pub mod text {
+    use serde::{Deserializer, Serializer};
+
+    use super::{Condition, ConditionVisitor};
+
+    pub fn serialize<S>(condition: &Condition, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(condition)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Condition, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ConditionVisitor)
+    }
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feature(name: &str) -> Condition {
+        Condition::Feature(name.to_string())
+    }
+
+    fn not(condition: Condition) -> Condition {
+        Condition::Not(Box::new(condition))
+    }
+
+    fn parse(input: &str) -> Condition {
+        Condition::from_str(input).expect("failed to parse condition")
+    }
+
+    #[test]
+    fn parsing() {
+        assert_eq!(
+            parse(r#"os == macos && !feature("is_demo_user")"#),
+            Condition::And(vec![Condition::OS(OS::MacOS), not(feature("is_demo_user"))])
+        );
+
+        // `&&` binds tighter than `||`, and chains are flattened into a single list
+        assert_eq!(
+            parse("os == linux || arch == aarch64 && always || never"),
+            Condition::Or(vec![
+                Condition::OS(OS::Linux),
+                Condition::And(vec![Condition::Arch(Arch::AArch64), Condition::Always]),
+                Condition::Never,
+            ])
+        );
+
+        // ...but parentheses are not
+        assert_eq!(
+            parse("(always && never) && always"),
+            Condition::And(vec![
+                Condition::And(vec![Condition::Always, Condition::Never]),
+                Condition::Always,
+            ])
+        );
+
+        assert_eq!(parse("os != windows"), not(Condition::OS(OS::Windows)));
+        assert_eq!(parse("all()"), Condition::And(vec![]));
+        assert_eq!(
+            parse(r#"xor(feature("a"), feature("b\"\\"))"#),
+            Condition::Xor(vec![feature("a"), feature("b\"\\")])
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let conditions = [
+            Condition::Always,
+            not(Condition::Never),
+            not(not(Condition::OS(OS::Linux))),
+            not(Condition::Arch(Arch::X86_64)),
+            Condition::And(vec![]),
+            Condition::Or(vec![feature("single")]),
+            not(Condition::And(vec![feature("a"), feature("b")])),
+            Condition::Or(vec![
+                Condition::And(vec![
+                    Condition::OS(OS::MacOS),
+                    feature("has_custom_resolution"),
+                ]),
+                Condition::Or(vec![Condition::Never, Condition::Always]),
+                Condition::Xor(vec![
+                    feature("x \"quoted\""),
+                    Condition::Arch(Arch::AArch64),
+                ]),
+            ]),
+        ];
+
+        for condition in conditions {
+            let text = condition.to_string();
+            assert_eq!(parse(&text), condition, "round trip failed for `{text}`");
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let error = |input: &str| Condition::from_str(input).expect_err("parsing should fail");
+
+        assert_eq!(
+            error("os == beos").kind,
+            ParseErrorKind::UnknownOs("beos".into())
+        );
+        assert_eq!(error("os == beos").column, 7);
+        assert_eq!(
+            error("always &&").kind,
+            ParseErrorKind::UnexpectedEnd("a condition")
+        );
+        assert_eq!(error("always &&").column, 10);
+        assert_eq!(
+            error("always & never").kind,
+            ParseErrorKind::UnexpectedCharacter('&')
+        );
+        assert_eq!(
+            error("feature(\"abc").kind,
+            ParseErrorKind::UnterminatedString
+        );
+        assert_eq!(
+            error("sometimes").kind,
+            ParseErrorKind::UnknownKeyword("sometimes".into())
+        );
+        assert_eq!(
+            error("always never").to_string(),
+            "unexpected `never`, expected `&&`, `||` or end of input (at column 8)"
+        );
+    }
+
+    #[test]
+    fn serde() {
+        #[derive(Debug, Deserialize, PartialEq, serde::Serialize)]
+        struct Announcement {
+            #[serde(with = "text")]
+            condition: Condition,
+        }
+
+        let text = r#"os == macos && !feature("is_demo_user")"#;
+        let condition = parse(text);
+
+        // Trees are the default, but formats that pass strings to enums also take the textual form
+        let ron = ron::to_string(&condition).unwrap();
+        assert_eq!(ron, r#"And([OS("macos"),Not(Feature("is_demo_user"))])"#);
+        assert_eq!(ron::from_str::<Condition>(&ron).unwrap(), condition);
+        let json = serde_json::to_value(&condition).unwrap();
+        assert_eq!(
+            serde_json::from_value::<Condition>(json).unwrap(),
+            condition
+        );
+        assert_eq!(
+            serde_json::from_value::<Condition>(text.into()).unwrap(),
+            condition
+        );
+        assert_eq!(
+            serde_json::from_str::<Condition>(r#""always""#).unwrap(),
+            Condition::Always
+        );
+        assert!(serde_json::from_str::<Condition>(r#""Sometimes""#).is_err());
+
+        // Fields using `text` are written in the textual form, and still take trees from JSON
+        let announcement = Announcement { condition };
+        let ron = ron::to_string(&announcement).unwrap();
+        assert_eq!(ron::from_str::<Announcement>(&ron).unwrap(), announcement);
+        let json = serde_json::to_value(&announcement).unwrap();
+        assert_eq!(json["condition"], text);
+        let tree = serde_json::to_value(&announcement.condition).unwrap();
+        let tree = serde_json::json!({ "condition": tree });
+        assert_eq!(
+            serde_json::from_value::<Announcement>(tree).unwrap(),
+            announcement
+        );
+    }
+}
@@ -13,13 +13,16 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use std::collections::HashSet;
+
 use chrono::{DateTime, Utc};
 use macros::data_structure;
 use platforms::OS;
 use semver::{Comparator, Op, Prerelease, VersionReq};
 
-use super::conditional::{Condition, MaybeConditional};
-use super::library::Library;
+use super::conditional::{Condition, EvalContext, MaybeConditional};
+use super::library::{Library, LibraryDownloadable};
+use super::loader::ModLoaderVersion;
 
 /// The different classes of game versions (e.g. release vs snapshot).
 #[data_structure]
@@ -56,6 +59,62 @@ pub struct GameVersion {
     /// A list of arguments that should be passed after `main_class`. The arguments can contain
     /// variables enclosed in `${}`, which should be replaced.
     pub game_arguments: Vec<MaybeConditional<String>>,
+    /// The logging configuration of this version, if it has one.
+    pub logging: Option<GameVersionLogging>,
+    /// The game's own JAR file, which goes last on the class path.
+    pub client: LibraryDownloadable,
+    /// The JAR file of the dedicated server, if this version has one.
+    pub server: Option<LibraryDownloadable>,
+    /// The asset index that lists the assets (textures, sounds, etc.) used by this version.
+    pub asset_index: GameVersionAssetIndex,
+}
+
+/// A reference to the asset index used by a game version.
+#[data_structure]
+pub struct GameVersionAssetIndex {
+    /// The ID of the asset index (e.g. "1.12" or "legacy"). Many versions share the same index.
+    pub id: String,
+    /// The index file, saved relative to the asset store (e.g. `indexes/1.12.json`).
+    pub file: LibraryDownloadable,
+    /// The combined size of all assets in the index, in bytes.
+    pub total_size: u64,
+}
+
+/// The logging configuration of a game version. When used, the game writes its log as log4j XML
+/// events to the standard output, instead of plain text.
+#[data_structure]
+pub struct GameVersionLogging {
+    /// The argument that should be passed before `main_class` to use the configuration. It
+    /// contains a `${path}` variable, which should be replaced with the path to the config file.
+    pub argument: String,
+    /// The config file, saved relative to the asset store (e.g. `log_configs/client-1.12.xml`).
+    pub file: LibraryDownloadable,
+}
+
+/// A [GameVersion] with all of its conditions evaluated for a specific [EvalContext]. Everything in
+/// it applies as-is, so it is ready to be used for launching the game.
+#[data_structure]
+pub struct ResolvedGameVersion {
+    /// The ID or "name" of this version (e.g. "1.12.2" or "23w32a").
+    pub id: String,
+    /// The version of Java required to launch this version.
+    pub java_version: VersionReq,
+    /// The main Java class that contains the game's `main()` function.
+    pub main_class: String,
+    /// The libraries that apply in the context, without duplicates.
+    pub libraries: Vec<Library>,
+    /// The arguments that should be passed before `main_class`. The arguments can contain
+    /// variables enclosed in `${}`, which should be replaced.
+    pub java_arguments: Vec<String>,
+    /// The arguments that should be passed after `main_class`. The arguments can contain
+    /// variables enclosed in `${}`, which should be replaced.
+    pub game_arguments: Vec<String>,
+    /// The logging configuration of this version, if it has one.
+    pub logging: Option<GameVersionLogging>,
+    /// The game's own JAR file, which goes last on the class path.
+    pub client: LibraryDownloadable,
+    /// The asset index that lists the assets used by this version.
+    pub asset_index: GameVersionAssetIndex,
 }
 
 /// A small snippet of game version information that is used in the [GameVersionIndex].
@@ -67,6 +126,60 @@ pub struct GameVersionSnippet {
     pub stability: GameVersionStability,
 }
 
+// === impl ===
+
+impl GameVersion {
+    /// Evaluates all libraries and arguments of this version in the given context. Libraries that
+    /// end up pointing to the same file are only included once.
+    pub fn resolve(self, context: &EvalContext) -> ResolvedGameVersion {
+        let mut paths = HashSet::new();
+        let libraries = self
+            .libraries
+            .into_iter()
+            .filter_map(|it| it.fold_with(context))
+            .filter(|it| paths.insert(it.file.path.clone()))
+            .collect();
+
+        let fold = |args: Vec<MaybeConditional<String>>| -> Vec<String> {
+            args.into_iter()
+                .filter_map(|it| it.fold_with(context))
+                .collect()
+        };
+
+        ResolvedGameVersion {
+            id: self.id,
+            java_version: self.java_version,
+            main_class: self.main_class,
+            libraries,
+            java_arguments: fold(self.java_arguments),
+            game_arguments: fold(self.game_arguments),
+            logging: self.logging,
+            client: self.client,
+            asset_index: self.asset_index,
+        }
+    }
+
+    /// Combines this version with a mod loader. The loader's main class is used instead, its
+    /// arguments are appended, and its libraries come first on the class path. Libraries of the
+    /// game that the loader ships its own version of (same group, artifact and classifier) are
+    /// dropped.
+    pub fn with_loader(mut self, loader: ModLoaderVersion) -> Self {
+        let key = |it: &Library| {
+            let name = &it.name;
+            (name.group.clone(), name.artifact.clone(), name.classifier.clone())
+        };
+
+        let replaced: HashSet<_> = loader.libraries.iter().map(|it| key(it.value())).collect();
+        self.libraries.retain(|it| !replaced.contains(&key(it.value())));
+
+        self.libraries.splice(0..0, loader.libraries);
+        self.main_class = loader.main_class;
+        self.java_arguments.extend(loader.java_arguments);
+        self.game_arguments.extend(loader.game_arguments);
+        self
+    }
+}
+
 // === conversion ===
 
 impl From<GameVersion> for GameVersionSnippet {
@@ -90,53 +203,119 @@ impl From<crate::silo::game::GameManifestStability> for GameVersionStability {
     }
 }
 
+/// The Java requirement of a version that declares the given major version. Java 8 is required
+/// exactly, as older versions break on newer Java, while newer requirements are minimums.
+pub(crate) fn java_requirement(major: u64) -> VersionReq {
+    VersionReq {
+        comparators: vec![Comparator {
+            op: if major == 8 { Op::Exact } else { Op::GreaterEq },
+            major,
+            minor: None,
+            patch: None,
+            pre: Prerelease::EMPTY,
+        }],
+    }
+}
+
+/// The Java arguments of versions from before 17w43a, which don't list their own and left it to
+/// the launcher instead.
+pub(crate) fn legacy_java_arguments() -> Vec<MaybeConditional<String>> {
+    vec![
+        MaybeConditional::Conditional {
+            when: Condition::OS(OS::MacOS),
+            then: "-XstartOnFirstThread".to_string(),
+        },
+        MaybeConditional::Conditional {
+            when: Condition::OS(OS::Windows),
+            then: "-XX:HeapDumpPath=MojangTricksIntelDriversForPerformance_javaw.exe_minecraft.exe.heapdump".to_string(),
+        },
+        MaybeConditional::Conditional {
+            when: Condition::OS(OS::Windows),
+            then: "-Dos.name=Windows 10".to_string(),
+        },
+        MaybeConditional::Conditional {
+            when: Condition::OS(OS::Windows),
+            then: "-Dos.version=10.0".to_string(),
+        },
+        MaybeConditional::Unconditional("-Djava.library.path=${natives_directory}".to_string()),
+        MaybeConditional::Unconditional("-Dminecraft.launcher.brand=${launcher_name}".to_string()),
+        MaybeConditional::Unconditional("-Dminecraft.launcher.version=${launcher_version}".to_string()),
+        MaybeConditional::Unconditional("-cp".to_string()),
+        MaybeConditional::Unconditional("${classpath}".to_string()),
+    ]
+}
+
+/// Converts the download of one of the game's own JAR files (`kind` being client or server).
+#[cfg(feature = "silo")]
+fn jar(
+    id: &str,
+    kind: &str,
+    value: crate::silo::game::ApiGameVersionDownloadable,
+) -> LibraryDownloadable {
+    LibraryDownloadable {
+        path: format!("com/mojang/minecraft/{id}/minecraft-{id}-{kind}.jar"),
+        checksum: value.sha1,
+        size: value.size,
+        url: value.url,
+        source: crate::core::library::LibrarySource::Verified,
+    }
+}
+
+#[cfg(feature = "silo")]
+impl From<crate::silo::game::ApiGameVersionAssetIndex> for GameVersionAssetIndex {
+    fn from(value: crate::silo::game::ApiGameVersionAssetIndex) -> Self {
+        Self {
+            file: LibraryDownloadable {
+                path: format!("indexes/{}.json", value.id),
+                checksum: value.sha1,
+                size: value.size,
+                url: value.url,
+                source: crate::core::library::LibrarySource::Verified,
+            },
+            id: value.id,
+            total_size: value.total_size,
+        }
+    }
+}
+
+#[cfg(feature = "silo")]
+impl From<crate::silo::game::ApiGameVersionLogging> for GameVersionLogging {
+    fn from(value: crate::silo::game::ApiGameVersionLogging) -> Self {
+        let file = value.client.file;
+        Self {
+            argument: value.client.argument,
+            file: LibraryDownloadable {
+                path: format!("log_configs/{}", file.id),
+                checksum: file.sha1,
+                size: file.size,
+                url: file.url,
+                source: crate::core::library::LibrarySource::Verified,
+            },
+        }
+    }
+}
+
 #[cfg(feature = "silo")]
 impl From<crate::silo::game::ApiGameVersionLegacy> for GameVersion {
     fn from(value: crate::silo::game::ApiGameVersionLegacy) -> Self {
         let java_version = value.java_version.map(|it| it.major_version).unwrap_or(8);
         Self {
+            client: jar(&value.id, "client", value.downloads.client),
+            server: value.downloads.server.map(|it| jar(&value.id, "server", it)),
+            asset_index: GameVersionAssetIndex::from(value.asset_index),
             id: value.id,
             release_date: value.release_time,
             stability: GameVersionStability::from(value.stability),
-            java_version: VersionReq {
-                comparators: vec![Comparator {
-                    op: if java_version == 8 { Op::Exact } else { Op::GreaterEq },
-                    major: java_version,
-                    minor: None,
-                    patch: None,
-                    pre: Prerelease::EMPTY,
-                }],
-            },
+            java_version: java_requirement(java_version),
             main_class: value.main_class,
             libraries: value.libraries.into_iter().flat_map(Vec::<MaybeConditional<Library>>::from).collect(),
-            java_arguments: vec![
-                MaybeConditional::Conditional {
-                    when: Condition::OS(OS::MacOS),
-                    then: "-XstartOnFirstThread".to_string(),
-                },
-                MaybeConditional::Conditional {
-                    when: Condition::OS(OS::Windows),
-                    then: "-XX:HeapDumpPath=MojangTricksIntelDriversForPerformance_javaw.exe_minecraft.exe.heapdump".to_string(),
-                },
-                MaybeConditional::Conditional {
-                    when: Condition::OS(OS::Windows),
-                    then: "-Dos.name=Windows 10".to_string(),
-                },
-                MaybeConditional::Conditional {
-                    when: Condition::OS(OS::Windows),
-                    then: "-Dos.version=10.0".to_string(),
-                },
-                MaybeConditional::Unconditional("-Djava.library.path=${natives_directory}".to_string()),
-                MaybeConditional::Unconditional("-Dminecraft.launcher.brand=${launcher_name}".to_string()),
-                MaybeConditional::Unconditional("-Dminecraft.launcher.version=${launcher_version}".to_string()),
-                MaybeConditional::Unconditional("-cp".to_string()),
-                MaybeConditional::Unconditional("${classpath}".to_string()),
-            ],
+            java_arguments: legacy_java_arguments(),
             game_arguments: value
                 .minecraft_arguments
                 .split(' ')
                 .map(|it| MaybeConditional::Unconditional(it.to_string()))
                 .collect(),
+            logging: value.logging.map(GameVersionLogging::from),
         }
     }
 }
@@ -145,22 +324,13 @@ impl From<crate::silo::game::ApiGameVersionLegacy> for GameVersion {
 impl From<crate::silo::game::ApiGameVersion17w43a> for GameVersion {
     fn from(value: crate::silo::game::ApiGameVersion17w43a) -> Self {
         Self {
+            client: jar(&value.id, "client", value.downloads.client),
+            server: value.downloads.server.map(|it| jar(&value.id, "server", it)),
+            asset_index: GameVersionAssetIndex::from(value.asset_index),
             id: value.id,
             release_date: value.release_time,
             stability: GameVersionStability::from(value.stability),
-            java_version: VersionReq {
-                comparators: vec![Comparator {
-                    op: if value.java_version.major_version == 8 {
-                        Op::Exact
-                    } else {
-                        Op::GreaterEq
-                    },
-                    major: value.java_version.major_version,
-                    minor: None,
-                    patch: None,
-                    pre: Prerelease::EMPTY,
-                }],
-            },
+            java_version: java_requirement(value.java_version.major_version),
             main_class: value.main_class,
             libraries: value
                 .libraries
@@ -179,6 +349,7 @@ impl From<crate::silo::game::ApiGameVersion17w43a> for GameVersion {
                 .into_iter()
                 .flat_map(Vec::<MaybeConditional<String>>::from)
                 .collect(),
+            logging: Some(GameVersionLogging::from(value.logging)),
         }
     }
 }
@@ -192,3 +363,155 @@ impl From<crate::silo::game::ApiGameVersion> for GameVersion {
         }
     }
 }
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use platforms::Arch;
+    use url::Url;
+
+    use crate::core::library::LibrarySource;
+
+    use super::*;
+
+    fn file(path: &str) -> LibraryDownloadable {
+        LibraryDownloadable {
+            path: path.to_string(),
+            checksum: "0".repeat(40),
+            size: 0,
+            url: Url::parse("https://libraries.minecraft.net/").unwrap(),
+            source: LibrarySource::Verified,
+        }
+    }
+
+    fn library(name: &str, path: &str) -> Library {
+        Library {
+            name: name.parse().unwrap(),
+            file: file(path),
+            extract: None,
+        }
+    }
+
+    #[test]
+    fn resolve() {
+        let version = GameVersion {
+            id: "1.12.2".to_string(),
+            release_date: DateTime::default(),
+            stability: GameVersionStability::Release,
+            java_version: VersionReq::parse("=8").unwrap(),
+            main_class: "net.minecraft.client.main.Main".to_string(),
+            libraries: vec![
+                MaybeConditional::Unconditional(library("a:b:1", "a/b/1/b-1.jar")),
+                MaybeConditional::Conditional {
+                    when: Condition::OS(OS::MacOS),
+                    then: library("a:c:1", "a/c/1/c-1-natives-osx.jar"),
+                },
+                MaybeConditional::Conditional {
+                    when: Condition::OS(OS::Linux),
+                    then: library("a:c:1", "a/c/1/c-1-natives-linux.jar"),
+                },
+                MaybeConditional::Unconditional(library("a:b:1", "a/b/1/b-1.jar")),
+            ],
+            java_arguments: vec![
+                MaybeConditional::Conditional {
+                    when: Condition::OS(OS::MacOS),
+                    then: "-XstartOnFirstThread".to_string(),
+                },
+                MaybeConditional::Unconditional("-cp".to_string()),
+            ],
+            game_arguments: vec![
+                MaybeConditional::Unconditional("--demo".to_string()),
+                MaybeConditional::Conditional {
+                    when: Condition::Feature("is_demo_user".to_string()),
+                    then: "--demo".to_string(),
+                },
+            ],
+            logging: None,
+            client: file("com/mojang/minecraft/1.12.2/minecraft-1.12.2-client.jar"),
+            server: None,
+            asset_index: GameVersionAssetIndex {
+                id: "1.12".to_string(),
+                file: file("indexes/1.12.json"),
+                total_size: 0,
+            },
+        };
+
+        let context = EvalContext {
+            os: OS::Linux,
+            arch: Arch::X86_64,
+            features: vec![],
+        };
+
+        let resolved = version.resolve(&context);
+        let paths: Vec<_> = resolved
+            .libraries
+            .iter()
+            .map(|it| it.file.path.as_str())
+            .collect();
+
+        assert_eq!(paths, ["a/b/1/b-1.jar", "a/c/1/c-1-natives-linux.jar"]);
+        assert_eq!(resolved.java_arguments, ["-cp"]);
+        assert_eq!(resolved.game_arguments, ["--demo"]);
+        assert_eq!(resolved.asset_index.id, "1.12");
+    }
+
+    #[test]
+    fn with_loader() {
+        let version = GameVersion {
+            id: "1.20.4".to_string(),
+            release_date: DateTime::default(),
+            stability: GameVersionStability::Release,
+            java_version: VersionReq::parse(">=17").unwrap(),
+            main_class: "net.minecraft.client.main.Main".to_string(),
+            libraries: vec![
+                MaybeConditional::Unconditional(library("org.ow2.asm:asm:9.3", "asm-9.3.jar")),
+                MaybeConditional::Unconditional(library("com.mojang:brigadier:1", "b-1.jar")),
+            ],
+            java_arguments: vec![],
+            game_arguments: vec![MaybeConditional::Unconditional("--demo".to_string())],
+            logging: None,
+            client: file("com/mojang/minecraft/1.20.4/minecraft-1.20.4-client.jar"),
+            server: None,
+            asset_index: GameVersionAssetIndex {
+                id: "12".to_string(),
+                file: file("indexes/12.json"),
+                total_size: 0,
+            },
+        };
+
+        let loader = ModLoaderVersion {
+            loader_version: "0.15.0".to_string(),
+            game_version: "1.20.4".to_string(),
+            libraries: vec![
+                MaybeConditional::Unconditional(library("org.ow2.asm:asm:9.6", "asm-9.6.jar")),
+                MaybeConditional::Unconditional(library("net.fabricmc:loader:0.15.0", "l.jar")),
+            ],
+            main_class: "net.fabricmc.loader.impl.launch.knot.KnotClient".to_string(),
+            game_arguments: vec![],
+            java_arguments: vec![MaybeConditional::Unconditional("-Dfabric=1".to_string())],
+        };
+
+        let version = version.with_loader(loader);
+        let names: Vec<_> = version
+            .libraries
+            .iter()
+            .map(|it| it.value().name.to_string())
+            .collect();
+
+        assert_eq!(
+            names,
+            [
+                "org.ow2.asm:asm:9.6",
+                "net.fabricmc:loader:0.15.0",
+                "com.mojang:brigadier:1"
+            ]
+        );
+        assert_eq!(
+            version.main_class,
+            "net.fabricmc.loader.impl.launch.knot.KnotClient"
+        );
+        assert_eq!(version.java_arguments.len(), 1);
+        assert_eq!(version.game_arguments.len(), 1);
+    }
+}
@@ -0,0 +1,98 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use macros::data_structure;
+
+/// Which Java runtime an instance is launched with.
+#[data_structure(equatable)]
+#[derive(Default)]
+pub enum InstanceJava {
+    /// The best installed runtime for the game version is picked on every launch.
+    #[default]
+    Automatic,
+    /// A specific runtime, by its ID. It is kept even when a newer release is installed.
+    Pinned(String),
+}
+
+/// The mod loader of an instance.
+#[data_structure(equatable)]
+pub struct InstanceLoader {
+    /// The ID of the mod loader (e.g. `fabric`).
+    pub id: String,
+    /// The version of the mod loader (e.g. `0.15.7`).
+    pub version: String,
+}
+
+/// A game instance: a game directory (with its own saves, options, mods, etc.) along with the
+/// settings for launching it.
+#[data_structure(equatable)]
+pub struct Instance {
+    /// User-visible name of the instance.
+    pub name: String,
+    /// The ID of the game version (e.g. `1.20.4`).
+    pub game_version: String,
+    /// The mod loader, if the instance is modded.
+    pub loader: Option<InstanceLoader>,
+    /// The Java runtime to launch with.
+    pub java: InstanceJava,
+    /// The maximum size of the heap in MiB, or None to size it from the system memory.
+    pub memory: Option<u64>,
+    /// Extra arguments for the JVM.
+    pub jvm_arguments: Vec<String>,
+    /// Extra arguments for the game.
+    pub game_arguments: Vec<String>,
+    /// Extra environment variables for the game process.
+    pub environment: BTreeMap<String, String>,
+    /// A command (and its arguments) that the game is launched through, e.g. `gamemoderun`.
+    pub wrapper: Vec<String>,
+    /// The file name of the icon, relative to the directory of the instance.
+    pub icon: Option<String>,
+    /// When the instance was created.
+    pub created: DateTime<Utc>,
+    /// When the settings of the instance were last changed.
+    pub modified: DateTime<Utc>,
+    /// When the instance was last launched.
+    pub last_played: Option<DateTime<Utc>>,
+}
+
+// === impl ===
+
+impl Instance {
+    /// Creates a vanilla instance with default settings.
+    pub fn new(
+        name: impl Into<String>,
+        game_version: impl Into<String>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            game_version: game_version.into(),
+            loader: None,
+            java: InstanceJava::Automatic,
+            memory: None,
+            jvm_arguments: Vec::new(),
+            game_arguments: Vec::new(),
+            environment: BTreeMap::new(),
+            wrapper: Vec::new(),
+            icon: None,
+            created: now,
+            modified: now,
+            last_played: None,
+        }
+    }
+}
@@ -13,6 +13,9 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+
 use platforms::{Arch, OS};
 use semver::Version;
 use serde::{Deserialize, Serialize};
@@ -31,6 +34,9 @@ pub struct Environment {
 pub enum JavaProvider {
     /// Azul Zulu -- https://www.azul.com/downloads/#zulu
     Zulu,
+    /// A runtime that was already installed on the system, with the name of its implementor (e.g.
+    /// `Eclipse Adoptium`), if known. These are never managed by the launcher.
+    External(Option<String>),
 }
 
 /// The type of build (JDK or JRE).
@@ -78,6 +84,30 @@ pub struct JavaInfo {
     pub environment: Environment,
     /// Relative path of the executable file (`java` or `javaw`) to the root of the installation.
     pub executable: String,
-    /// Size of this installation on disk.
+    /// Size of this installation on disk (0 for external runtimes, which aren't measured).
     pub size: u64,
+    /// Root of the installation, for runtimes that weren't installed by the launcher. Managed
+    /// runtimes are always in their own directory, so this is None for them.
+    pub home: Option<PathBuf>,
+}
+
+// === impl ===
+
+impl Display for JavaProvider {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Zulu => f.write_str("Zulu"),
+            Self::External(Some(implementor)) => f.write_str(implementor),
+            Self::External(None) => f.write_str("External"),
+        }
+    }
+}
+
+impl Display for JavaEdition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::JDK => f.write_str("JDK"),
+            Self::JRE => f.write_str("JRE"),
+        }
+    }
 }
@@ -26,12 +26,30 @@ use crate::core::maven::MavenIdentifier;
 pub struct LibraryDownloadable {
     /// The relative path where the file will be saved.
     pub path: String,
-    /// The SHA1 checksum of the file.
+    /// The SHA1 checksum of the file. Empty if the file is not [LibrarySource::Verified].
     pub checksum: String,
-    /// The size of the file in bytes.
+    /// The size of the file in bytes, or 0 if it is not known.
     pub size: u64,
-    /// The URL of the file.
+    /// The URL of the file. Not used for [LibrarySource::Local] files.
     pub url: Url,
+    /// How the file is obtained.
+    #[serde(default)]
+    pub source: LibrarySource,
+}
+
+/// How a [LibraryDownloadable] is obtained.
+#[data_structure(equatable)]
+#[derive(Copy, Default)]
+pub enum LibrarySource {
+    /// Downloaded from its URL, and verified by its checksum and size.
+    #[default]
+    Verified,
+    /// Downloaded from its URL, but not verified, as its checksum is not known. Version JSONs of
+    /// mod loaders often only give the Maven repository of their libraries.
+    Unverified,
+    /// Generated locally by an installer (e.g. the patched game JAR of Forge), so it can't be
+    /// downloaded, and has to be put into the store by other means.
+    Local,
 }
 
 /// A library is a JAR file that is downloaded and put into the `classpath` to be loaded by the JVM.
@@ -41,6 +59,25 @@ pub struct Library {
     pub name: MavenIdentifier,
     /// The library file itself.
     pub file: LibraryDownloadable,
+    /// If present, this is a native library, and its contents should be extracted into the natives
+    /// directory before launching, following these rules.
+    pub extract: Option<LibraryExtract>,
+}
+
+/// Rules for extracting the contents of a native library.
+#[data_structure]
+pub struct LibraryExtract {
+    /// Prefixes of paths inside the library that should not be extracted (e.g. `META-INF/`).
+    pub exclude: Vec<String>,
+}
+
+// === impl ===
+
+impl LibraryExtract {
+    /// Whether a path inside the library should be extracted.
+    pub fn includes(&self, path: &str) -> bool {
+        !self.exclude.iter().any(|it| path.starts_with(it.as_str()))
+    }
 }
 
 // === conversion ===
@@ -53,6 +90,7 @@ impl From<crate::silo::game::ApiLibraryArtifact> for LibraryDownloadable {
             checksum: value.sha1,
             size: value.size,
             url: value.url,
+            source: LibrarySource::Verified,
         }
     }
 }
@@ -63,6 +101,7 @@ impl From<crate::silo::game::ApiCommonLibrary> for MaybeConditional<Library> {
         let library = Library {
             name: value.name,
             file: value.downloads.artifact.into(),
+            extract: None,
         };
 
         match value.rules {
@@ -97,6 +136,8 @@ impl From<crate::silo::game::ApiNativeLibrary> for Vec<MaybeConditional<Library>
             .rules
             .map(|s| Condition::And(s.into_iter().map(Condition::from).collect()).simplify());
 
+        let exclude = value.extract.and_then(|it| it.exclude).unwrap_or_default();
+
         for (key, os) in keys {
             // Mojang classic. In some libraries, there are native keys, but no corresponding
             // artifact in downloads. See, for example, second library in rd-132211.
@@ -118,6 +159,9 @@ impl From<crate::silo::game::ApiNativeLibrary> for Vec<MaybeConditional<Library>
                 then: Library {
                     name: value.name.clone(),
                     file: artifact.into(),
+                    extract: Some(LibraryExtract {
+                        exclude: exclude.clone(),
+                    }),
                 },
             });
         }
@@ -126,6 +170,7 @@ impl From<crate::silo::game::ApiNativeLibrary> for Vec<MaybeConditional<Library>
             libraries.push(MaybeConditional::Unconditional(Library {
                 name: value.name.clone(),
                 file: artifact.into(),
+                extract: None,
             }));
         }
 
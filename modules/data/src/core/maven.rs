@@ -41,6 +41,18 @@ impl MavenIdentifier {
             classifier: classifier.map(|it| it.as_ref().to_string()),
         }
     }
+
+    /// The path of the artifact in a Maven repository, e.g. `com/example/hello/1.0/hello-1.0.jar`.
+    pub fn path(&self) -> String {
+        let group = self.group.replace('.', "/");
+        let (artifact, version) = (&self.artifact, &self.version);
+        match &self.classifier {
+            Some(classifier) => {
+                format!("{group}/{artifact}/{version}/{artifact}-{version}-{classifier}.jar")
+            }
+            None => format!("{group}/{artifact}/{version}/{artifact}-{version}.jar"),
+        }
+    }
 }
 
 impl Display for MavenIdentifier {
@@ -17,7 +17,11 @@ pub mod assets;
 pub mod auth;
 pub mod conditional;
 pub mod game;
+pub mod instance;
 pub mod java;
 pub mod library;
 pub mod loader;
 pub mod maven;
+pub mod mods;
+pub mod settings;
+pub mod template;
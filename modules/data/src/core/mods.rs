@@ -0,0 +1,583 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+
+use macros::data_structure;
+
+use crate::web::mods::{
+    FabricMod, FabricVersions, ForgeMods, JAR_VERSION, QuiltDependency, QuiltMod, QuiltProvides,
+    QuiltVersions,
+};
+
+/// The mod loader that a mod is made for, by the metadata that it includes.
+#[data_structure(equatable)]
+#[derive(Copy)]
+pub enum ModPlatform {
+    Fabric,
+    Quilt,
+    Forge,
+    NeoForge,
+}
+
+/// The versions of a dependency that a mod works with.
+#[data_structure(equatable)]
+pub enum ModVersionRange {
+    /// Any version.
+    Any,
+    /// Predicates in the format of Fabric and Quilt (e.g. `>=0.15 <0.16`, `1.20.x` or `~1.20.1`),
+    /// of which any has to match. Terms separated by spaces all have to match.
+    Predicates(Vec<String>),
+    /// A Maven version range (e.g. `[47,)` or `[1.20.1,1.20.2)`), used by Forge and NeoForge.
+    Maven(String),
+}
+
+/// How a mod relates to a dependency.
+#[data_structure(equatable)]
+#[derive(Copy)]
+pub enum ModDependencyKind {
+    /// The mod doesn't load without the dependency.
+    Required,
+    /// The mod works without the dependency, but needs a matching version if it is installed.
+    Optional,
+    /// The mod doesn't work together with a matching version of the dependency.
+    Incompatible,
+}
+
+/// A dependency of a mod, on another mod, the game (`minecraft`) or a mod loader.
+#[data_structure(equatable)]
+pub struct ModDependency {
+    pub id: String,
+    pub versions: ModVersionRange,
+    pub kind: ModDependencyKind,
+}
+
+/// The metadata of a mod, read from its JAR.
+#[data_structure(equatable)]
+pub struct ModInfo {
+    /// The ID of the mod (e.g. `sodium`).
+    pub id: String,
+    /// User-visible name of the mod.
+    pub name: Option<String>,
+    pub version: String,
+    pub platform: ModPlatform,
+    /// Other IDs that the mod can be depended on by.
+    pub provides: Vec<String>,
+    pub dependencies: Vec<ModDependency>,
+}
+
+/// A JAR in the mods directory of an instance.
+#[data_structure(equatable)]
+pub struct InstalledMod {
+    /// The file name of the JAR, including the `.disabled` suffix of disabled mods.
+    pub file: String,
+    /// Whether the mod gets loaded, which is when it has no `.disabled` suffix.
+    pub enabled: bool,
+    /// The mods in the JAR. Empty if it has no metadata that could be read.
+    pub mods: Vec<ModInfo>,
+    /// The mods in JARs nested in the JAR, which get loaded along with it.
+    pub nested: Vec<ModInfo>,
+}
+
+/// A problem with the mods of an instance, which would make the game fail to launch or crash.
+#[data_structure(equatable)]
+pub enum ModProblem {
+    /// A required dependency isn't installed.
+    Missing {
+        id: String,
+        dependency: String,
+        versions: ModVersionRange,
+    },
+    /// A dependency is installed, but not in a version that works with the mod.
+    Mismatch {
+        id: String,
+        dependency: String,
+        versions: ModVersionRange,
+        found: String,
+    },
+    /// A mod is installed that the mod doesn't work together with.
+    Incompatible {
+        id: String,
+        other: String,
+        version: String,
+    },
+    /// A mod is installed more than once.
+    Duplicate { id: String, files: Vec<String> },
+    /// A JAR has no mods for the mod loader of the instance.
+    WrongLoader { file: String, platform: ModPlatform },
+}
+
+// === impl ===
+
+impl ModPlatform {
+    /// Whether mods for this platform get loaded by a mod loader (by its ID, e.g. `quilt`). Quilt
+    /// loads Fabric mods as well.
+    pub fn is_loaded_by(&self, loader: &str) -> bool {
+        matches!(
+            (self, loader),
+            (Self::Fabric, "fabric" | "quilt")
+                | (Self::Quilt, "quilt")
+                | (Self::Forge, "forge")
+                | (Self::NeoForge, "neoforge")
+        )
+    }
+}
+
+impl ModVersionRange {
+    /// Whether a version is in the range.
+    pub fn matches(&self, version: &str) -> bool {
+        match self {
+            Self::Any => true,
+            Self::Predicates(predicates) => predicates.iter().any(|it| {
+                it.split_whitespace()
+                    .all(|term| matches_predicate(term, version))
+            }),
+            Self::Maven(range) => matches_maven(range, version),
+        }
+    }
+}
+
+impl ModInfo {
+    /// Reads the mods of a `mods.toml`. The version of mods that refer to the version in the JAR
+    /// manifest is taken from `jar_version`. Mods that depend on NeoForge are taken to be for it,
+    /// as NeoForge used `mods.toml` as well before it got its own file.
+    pub fn forge(value: ForgeMods, neoforge: bool, jar_version: Option<&str>) -> Vec<Self> {
+        let mut dependencies = value.dependencies;
+
+        value
+            .mods
+            .into_iter()
+            .map(|entry| {
+                let declared = dependencies.remove(&entry.mod_id).unwrap_or_default();
+                let neoforge = neoforge || declared.iter().any(|it| it.mod_id == "neoforge");
+
+                let dependencies = declared
+                    .into_iter()
+                    .filter(|it| {
+                        !it.side
+                            .as_deref()
+                            .is_some_and(|it| it.eq_ignore_ascii_case("server"))
+                    })
+                    .filter_map(|it| {
+                        // NeoForge reads the type case-insensitively
+                        let declared = it.kind.as_deref().map(str::to_ascii_lowercase);
+                        let kind = match (declared.as_deref(), it.mandatory) {
+                            (Some("required"), _) | (None, Some(true)) => {
+                                ModDependencyKind::Required
+                            }
+                            (Some("optional"), _) | (None, Some(false) | None) => {
+                                ModDependencyKind::Optional
+                            }
+                            (Some("incompatible"), _) => ModDependencyKind::Incompatible,
+                            // Discouraged dependencies only cause a warning in NeoForge.
+                            _ => return None,
+                        };
+
+                        Some(ModDependency {
+                            id: it.mod_id,
+                            versions: it
+                                .version_range
+                                .map_or(ModVersionRange::Any, ModVersionRange::Maven),
+                            kind,
+                        })
+                    })
+                    .collect();
+
+                let version = match entry.version.as_deref() {
+                    Some(JAR_VERSION) | None => jar_version.unwrap_or("0"),
+                    Some(version) => version,
+                };
+
+                Self {
+                    id: entry.mod_id,
+                    name: entry.display_name,
+                    version: version.to_string(),
+                    platform: match neoforge {
+                        true => ModPlatform::NeoForge,
+                        false => ModPlatform::Forge,
+                    },
+                    provides: vec![],
+                    dependencies,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Splits a version into its parts, without build metadata: the release (e.g. `1.20.1`) and the
+/// pre-release (e.g. `beta.2`).
+fn version_parts(version: &str) -> (Vec<&str>, Vec<&str>) {
+    let version = version.split('+').next().unwrap_or_default();
+    let (release, pre) = version.split_once('-').unwrap_or((version, ""));
+    fn split(it: &str) -> Vec<&str> {
+        match it.is_empty() {
+            true => vec![],
+            false => it.split('.').collect(),
+        }
+    }
+    (split(release), split(pre))
+}
+
+fn compare_parts(a: &[&str], b: &[&str]) -> Ordering {
+    for i in 0..a.len().max(b.len()) {
+        let (a, b) = (
+            a.get(i).copied().unwrap_or("0"),
+            b.get(i).copied().unwrap_or("0"),
+        );
+        let ordering = match (a.parse::<u64>(), b.parse::<u64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => a.cmp(b),
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    Ordering::Equal
+}
+
+/// Compares two versions leniently, as mods don't always use semantic versioning. Missing parts
+/// count as zero (so `1.20` equals `1.20.0`), and pre-releases come before their release.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (a_release, a_pre) = version_parts(a);
+    let (b_release, b_pre) = version_parts(b);
+
+    compare_parts(&a_release, &b_release).then_with(|| match (a_pre.is_empty(), b_pre.is_empty()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => compare_parts(&a_pre, &b_pre),
+    })
+}
+
+/// Whether a version matches a single predicate term in the format of Fabric.
+fn matches_predicate(term: &str, version: &str) -> bool {
+    if term.is_empty() || term == "*" {
+        return true;
+    }
+
+    let operators = [">=", "<=", ">", "<", "=", "~", "^"];
+    let (operator, expected) = operators
+        .iter()
+        .find_map(|op| term.strip_prefix(op).map(|rest| (*op, rest)))
+        .unwrap_or(("=", term));
+
+    // X-ranges (e.g. `1.20.x`) match every version that starts with the parts before the `x`.
+    let (release, _) = version_parts(expected);
+    if let Some(wildcard) = release.iter().position(|it| matches!(*it, "x" | "X" | "*")) {
+        let (actual, _) = version_parts(version);
+        let prefix = &release[..wildcard];
+        let actual = &actual[..prefix.len().min(actual.len())];
+        return compare_parts(actual, prefix) == Ordering::Equal;
+    }
+
+    let ordering = compare_versions(version, expected);
+    let below = |parts: usize| {
+        // The next version after the first `parts` parts of the expected one.
+        let mut next: Vec<u64> = release
+            .iter()
+            .take(parts)
+            .map(|it| it.parse().unwrap_or(0))
+            .collect();
+        next.resize(parts, 0);
+        if let Some(last) = next.last_mut() {
+            *last += 1;
+        }
+        let next = next
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(".");
+        compare_versions(version, &next) == Ordering::Less
+    };
+
+    match operator {
+        ">=" => ordering != Ordering::Less,
+        "<=" => ordering != Ordering::Greater,
+        ">" => ordering == Ordering::Greater,
+        "<" => ordering == Ordering::Less,
+        "~" => ordering != Ordering::Less && below(2),
+        "^" => ordering != Ordering::Less && below(1),
+        _ => ordering == Ordering::Equal,
+    }
+}
+
+/// Whether a version is in a Maven version range, which is a list of intervals (e.g.
+/// `[1.0,2.0),[3.0,)`). A bare version is only a recommendation, so it matches any version.
+fn matches_maven(range: &str, version: &str) -> bool {
+    let range: String = range.chars().filter(|it| !it.is_whitespace()).collect();
+    if !range.starts_with(['[', '(']) {
+        return true;
+    }
+
+    let mut rest = range.as_str();
+    while let Some(start) = rest.find(['[', '(']) {
+        let Some(length) = rest[start..].find([']', ')']) else {
+            return false;
+        };
+
+        let interval = &rest[start..start + length + 1];
+        rest = &rest[start + length + 1..];
+
+        let (open, close) = (interval.starts_with('['), interval.ends_with(']'));
+        let inner = &interval[1..interval.len() - 1];
+
+        let matched = match inner.split_once(',') {
+            None => compare_versions(version, inner) == Ordering::Equal,
+            Some((low, high)) => {
+                let above = low.is_empty()
+                    || match compare_versions(version, low) {
+                        Ordering::Greater => true,
+                        Ordering::Equal => open,
+                        Ordering::Less => false,
+                    };
+                let below = high.is_empty()
+                    || match compare_versions(version, high) {
+                        Ordering::Less => true,
+                        Ordering::Equal => close,
+                        Ordering::Greater => false,
+                    };
+                above && below
+            }
+        };
+
+        if matched {
+            return true;
+        }
+    }
+
+    false
+}
+
+impl Display for ModPlatform {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Fabric => f.write_str("Fabric"),
+            Self::Quilt => f.write_str("Quilt"),
+            Self::Forge => f.write_str("Forge"),
+            Self::NeoForge => f.write_str("NeoForge"),
+        }
+    }
+}
+
+impl Display for ModVersionRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Any => f.write_str("*"),
+            Self::Predicates(predicates) => f.write_str(&predicates.join(" || ")),
+            Self::Maven(range) => f.write_str(range),
+        }
+    }
+}
+
+impl Display for ModProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing {
+                id,
+                dependency,
+                versions,
+            } => write!(
+                f,
+                "{id} requires {dependency} {versions}, which is not installed"
+            ),
+            Self::Mismatch {
+                id,
+                dependency,
+                versions,
+                found,
+            } => write!(
+                f,
+                "{id} requires {dependency} {versions}, but {found} is installed"
+            ),
+            Self::Incompatible { id, other, version } => {
+                write!(f, "{id} is incompatible with {other} {version}")
+            }
+            Self::Duplicate { id, files } => {
+                write!(f, "{id} is installed more than once: {}", files.join(", "))
+            }
+            Self::WrongLoader { file, platform } => {
+                write!(
+                    f,
+                    "{file} is a {platform} mod, which the instance doesn't load"
+                )
+            }
+        }
+    }
+}
+
+// === conversion ===
+
+impl From<FabricVersions> for ModVersionRange {
+    fn from(value: FabricVersions) -> Self {
+        match value {
+            FabricVersions::One(predicate) => Self::Predicates(vec![predicate]),
+            FabricVersions::Many(predicates) => Self::Predicates(predicates),
+        }
+    }
+}
+
+impl From<QuiltVersions> for ModVersionRange {
+    fn from(value: QuiltVersions) -> Self {
+        match value {
+            QuiltVersions::One(predicate) => Self::Predicates(vec![predicate]),
+            QuiltVersions::Many(predicates) => Self::Predicates(predicates),
+            QuiltVersions::Constraint { any, all } if all.is_empty() => Self::Predicates(any),
+            QuiltVersions::Constraint { all, .. } => Self::Predicates(vec![all.join(" ")]),
+        }
+    }
+}
+
+impl From<FabricMod> for ModInfo {
+    fn from(value: FabricMod) -> Self {
+        let depends = value
+            .depends
+            .into_iter()
+            .map(|(id, versions)| ModDependency {
+                id,
+                versions: versions.into(),
+                kind: ModDependencyKind::Required,
+            });
+        let breaks = value
+            .breaks
+            .into_iter()
+            .map(|(id, versions)| ModDependency {
+                id,
+                versions: versions.into(),
+                kind: ModDependencyKind::Incompatible,
+            });
+
+        Self {
+            id: value.id,
+            name: value.name,
+            version: value.version,
+            platform: ModPlatform::Fabric,
+            provides: value.provides,
+            dependencies: depends.chain(breaks).collect(),
+        }
+    }
+}
+
+fn quilt_dependency(value: QuiltDependency, incompatible: bool) -> ModDependency {
+    let (id, versions, optional) = match value {
+        QuiltDependency::Id(id) => (id, ModVersionRange::Any, false),
+        QuiltDependency::Detailed {
+            id,
+            versions,
+            optional,
+        } => (
+            id,
+            versions.map_or(ModVersionRange::Any, Into::into),
+            optional,
+        ),
+    };
+
+    ModDependency {
+        id,
+        versions,
+        kind: match (incompatible, optional) {
+            (true, _) => ModDependencyKind::Incompatible,
+            (false, true) => ModDependencyKind::Optional,
+            (false, false) => ModDependencyKind::Required,
+        },
+    }
+}
+
+impl From<QuiltMod> for ModInfo {
+    fn from(value: QuiltMod) -> Self {
+        let loader = value.quilt_loader;
+        let depends = loader
+            .depends
+            .into_iter()
+            .map(|it| quilt_dependency(it, false));
+        let breaks = loader
+            .breaks
+            .into_iter()
+            .map(|it| quilt_dependency(it, true));
+
+        Self {
+            id: loader.id,
+            name: loader.metadata.and_then(|it| it.name),
+            version: loader.version,
+            platform: ModPlatform::Quilt,
+            provides: loader
+                .provides
+                .into_iter()
+                .map(|it| match it {
+                    QuiltProvides::Id(id) | QuiltProvides::Detailed { id } => id,
+                })
+                .collect(),
+            dependencies: depends.chain(breaks).collect(),
+        }
+    }
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions() {
+        assert_eq!(compare_versions("1.20", "1.20.0"), Ordering::Equal);
+        assert_eq!(compare_versions("1.20.1", "1.20"), Ordering::Greater);
+        assert_eq!(compare_versions("0.15.0-beta.2", "0.15.0"), Ordering::Less);
+        assert_eq!(compare_versions("0.5.8+mc1.20.4", "0.5.8"), Ordering::Equal);
+        assert_eq!(compare_versions("47.10.0", "47.2.0"), Ordering::Greater);
+    }
+
+    #[test]
+    fn predicates() {
+        let range = |it: &str| ModVersionRange::Predicates(vec![it.to_string()]);
+
+        assert!(range("*").matches("1.20.4"));
+        assert!(range(">=0.15.0").matches("0.15.7"));
+        assert!(!range(">=0.15.0").matches("0.14.24"));
+        assert!(range(">=1.20 <1.21").matches("1.20.4"));
+        assert!(!range(">=1.20 <1.21").matches("1.21"));
+        assert!(range("1.20.x").matches("1.20.4"));
+        assert!(!range("1.20.x").matches("1.21"));
+        assert!(range("~1.20.1").matches("1.20.4"));
+        assert!(!range("~1.20.1").matches("1.21.0"));
+        assert!(range("^0.5.0").matches("0.9.1"));
+        assert!(!range("^0.5.0").matches("1.0.0"));
+        assert!(range("1.20.4").matches("1.20.4"));
+        assert!(!range("1.20.4").matches("1.20.3"));
+
+        let either = ModVersionRange::Predicates(vec!["1.20.1".to_string(), "1.20.2".to_string()]);
+        assert!(either.matches("1.20.2"));
+        assert!(!either.matches("1.20.3"));
+    }
+
+    #[test]
+    fn maven() {
+        let range = |it: &str| ModVersionRange::Maven(it.to_string());
+
+        assert!(range("[47,)").matches("47.2.0"));
+        assert!(!range("[47,)").matches("46.0.14"));
+        assert!(range("[1.20.1,1.20.2)").matches("1.20.1"));
+        assert!(!range("[1.20.1,1.20.2)").matches("1.20.2"));
+        assert!(range("(,1.0]").matches("1.0"));
+        assert!(!range("(1.0,2.0)").matches("1.0"));
+        assert!(range("[1.0],[2.0,)").matches("2.5"));
+        assert!(range("[1.0]").matches("1.0.0"));
+        assert!(range("1.0").matches("0.1"));
+    }
+}
@@ -0,0 +1,243 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Launcher settings, which are resolved in layers: the built-in defaults, then the global
+//! settings, and then the overrides of an instance. Each effective value remembers which layer it
+//! came from, so that the UI can show where it is set.
+
+use macros::data_structure;
+use url::Url;
+
+use crate::core::instance::Instance;
+
+/// The meta server that is used unless the settings say otherwise.
+pub const DEFAULT_META_URL: &str = "https://launchermeta.lambda.prod.andre4ik3.net/";
+
+/// The language that is used unless the settings say otherwise.
+pub const DEFAULT_LANGUAGE: &str = "en-US";
+
+/// How many files are downloaded at once unless the settings say otherwise.
+pub const DEFAULT_DOWNLOAD_CONCURRENCY: usize = 8;
+
+/// How Java runtimes are picked for launching the game.
+#[data_structure(equatable)]
+#[derive(Copy, Default)]
+pub enum JavaPolicy {
+    /// Runtimes are installed from the meta server when none of the installed ones fit.
+    #[default]
+    Automatic,
+    /// Only installed runtimes (including the ones found on the system) are used.
+    InstalledOnly,
+}
+
+/// Redirects downloads from one location to another, e.g. to a mirror that is faster or isn't
+/// blocked in some region.
+#[data_structure(equatable)]
+pub struct MirrorRule {
+    /// The prefix of the URLs that are redirected, e.g. `https://libraries.minecraft.net/`.
+    pub from: String,
+    /// What the prefix is replaced with.
+    pub to: String,
+}
+
+/// The global settings, as stored on disk. Unset values fall back to the built-in defaults.
+#[data_structure(equatable)]
+#[derive(Default)]
+pub struct Settings {
+    /// The base URL of the meta server.
+    pub meta_url: Option<Url>,
+    /// The maximum size of the heap in MiB for instances that don't set one.
+    pub memory: Option<u64>,
+    /// Extra arguments for the JVM for instances that don't set any.
+    pub jvm_arguments: Option<Vec<String>>,
+    /// The language of the user interface (e.g. `en-US`), which new instances start the game in.
+    pub language: Option<String>,
+    /// How many files are downloaded at once.
+    pub download_concurrency: Option<usize>,
+    /// Rules for redirecting downloads, the first matching one is used.
+    pub mirrors: Option<Vec<MirrorRule>>,
+    /// How Java runtimes are picked.
+    pub java_policy: Option<JavaPolicy>,
+}
+
+/// The layer that an effective setting came from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SettingSource {
+    /// The built-in default.
+    Default,
+    /// The global settings.
+    Global,
+    /// The settings of the instance.
+    Instance,
+}
+
+/// The effective value of a setting, and the layer that it came from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Effective<T> {
+    pub value: T,
+    pub source: SettingSource,
+}
+
+/// The settings after all layers have been resolved (see [Settings::resolve]).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EffectiveSettings {
+    pub meta_url: Effective<Url>,
+    /// None means that the heap is sized from the system memory.
+    pub memory: Effective<Option<u64>>,
+    pub jvm_arguments: Effective<Vec<String>>,
+    pub language: Effective<String>,
+    pub download_concurrency: Effective<usize>,
+    pub mirrors: Effective<Vec<MirrorRule>>,
+    pub java_policy: Effective<JavaPolicy>,
+}
+
+// === impl ===
+
+/// Picks the value of the topmost layer that sets it.
+fn layer<T>(default: T, global: Option<T>, instance: Option<T>) -> Effective<T> {
+    match (instance, global) {
+        (Some(value), _) => Effective {
+            value,
+            source: SettingSource::Instance,
+        },
+        (None, Some(value)) => Effective {
+            value,
+            source: SettingSource::Global,
+        },
+        (None, None) => Effective {
+            value: default,
+            source: SettingSource::Default,
+        },
+    }
+}
+
+impl MirrorRule {
+    /// Redirects a URL if it starts with the prefix of this rule.
+    pub fn apply(&self, url: &Url) -> Option<Url> {
+        let rest = url.as_str().strip_prefix(&self.from)?;
+        Url::parse(&format!("{}{rest}", self.to)).ok()
+    }
+}
+
+impl Settings {
+    /// Resolves the settings on top of the built-in defaults, and optionally under the overrides
+    /// of an instance. Instances can override the memory and the JVM arguments (which replace the
+    /// global ones, if the instance has any).
+    pub fn resolve(&self, instance: Option<&Instance>) -> EffectiveSettings {
+        let default_url = Url::parse(DEFAULT_META_URL).expect("default meta url is invalid");
+        let jvm_arguments = instance
+            .map(|it| &it.jvm_arguments)
+            .filter(|it| !it.is_empty())
+            .cloned();
+
+        EffectiveSettings {
+            meta_url: layer(default_url, self.meta_url.clone(), None),
+            memory: layer(
+                None,
+                self.memory.map(Some),
+                instance.and_then(|it| it.memory.map(Some)),
+            ),
+            jvm_arguments: layer(Vec::new(), self.jvm_arguments.clone(), jvm_arguments),
+            language: layer(DEFAULT_LANGUAGE.to_string(), self.language.clone(), None),
+            download_concurrency: layer(
+                DEFAULT_DOWNLOAD_CONCURRENCY,
+                self.download_concurrency,
+                None,
+            ),
+            mirrors: layer(Vec::new(), self.mirrors.clone(), None),
+            java_policy: layer(JavaPolicy::default(), self.java_policy, None),
+        }
+    }
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use super::*;
+
+    #[test]
+    fn layers() {
+        let settings = Settings {
+            memory: Some(4096),
+            jvm_arguments: Some(vec!["-Dfile.encoding=UTF-8".to_string()]),
+            language: Some("de-DE".to_string()),
+            ..Default::default()
+        };
+
+        let effective = Settings::default().resolve(None);
+        assert_eq!(effective.meta_url.value.as_str(), DEFAULT_META_URL);
+        assert_eq!(effective.memory.source, SettingSource::Default);
+        assert_eq!(
+            effective.download_concurrency.value,
+            DEFAULT_DOWNLOAD_CONCURRENCY
+        );
+
+        let effective = settings.resolve(None);
+        assert_eq!(
+            effective.memory,
+            Effective {
+                value: Some(4096),
+                source: SettingSource::Global
+            }
+        );
+        assert_eq!(effective.language.value, "de-DE");
+        assert_eq!(effective.java_policy.source, SettingSource::Default);
+
+        let mut instance = Instance::new("Modpack", "1.20.4", DateTime::UNIX_EPOCH);
+        let effective = settings.resolve(Some(&instance));
+        assert_eq!(effective.memory.source, SettingSource::Global);
+        assert_eq!(effective.jvm_arguments.source, SettingSource::Global);
+
+        instance.memory = Some(8192);
+        instance.jvm_arguments = vec!["-XX:+UseZGC".to_string()];
+        let effective = settings.resolve(Some(&instance));
+        assert_eq!(
+            effective.memory,
+            Effective {
+                value: Some(8192),
+                source: SettingSource::Instance
+            }
+        );
+        assert_eq!(effective.jvm_arguments.value, ["-XX:+UseZGC"]);
+        assert_eq!(effective.jvm_arguments.source, SettingSource::Instance);
+    }
+
+    #[test]
+    fn mirrors() {
+        let settings = Settings {
+            mirrors: Some(vec![MirrorRule {
+                from: "https://libraries.minecraft.net/".to_string(),
+                to: "https://mirror.example.com/libraries/".to_string(),
+            }]),
+            ..Default::default()
+        };
+
+        let effective = settings.resolve(None);
+        assert_eq!(effective.mirrors.source, SettingSource::Global);
+
+        let rule = &effective.mirrors.value[0];
+        let url = Url::parse("https://libraries.minecraft.net/com/mojang/brigadier.jar").unwrap();
+        assert_eq!(
+            rule.apply(&url).unwrap().as_str(),
+            "https://mirror.example.com/libraries/com/mojang/brigadier.jar"
+        );
+
+        let url = Url::parse("https://maven.fabricmc.net/net/fabricmc/fabric-loader.jar").unwrap();
+        assert_eq!(rule.apply(&url), None);
+    }
+}
@@ -0,0 +1,349 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Substitution of the `${}` placeholders found in game and JVM arguments.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::core::auth::{Account, AccountCredentials};
+
+/// The text that secret values are replaced with by [Variables::redact].
+pub const REDACTED: &str = "<redacted>";
+
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+pub enum TemplateError {
+    #[error("unknown variable `${{{0}}}`")]
+    UnknownVariable(String),
+    #[error("variable `${{{0}}}` has no value")]
+    UnsetVariable(Variable),
+    #[error("unterminated variable starting at byte {0}")]
+    Unterminated(usize),
+}
+
+/// How placeholders that can't be substituted (unknown or unset variables) are handled.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SubstitutionMode {
+    /// Placeholders that can't be substituted cause an error.
+    Strict,
+    /// Placeholders that can't be substituted are left as-is.
+    Lenient,
+}
+
+macro_rules! variables {
+    ( $( $(#[$meta:meta])* $variant:ident => $name:literal, )* ) => {
+        /// A variable that can appear in game and JVM arguments as a `${name}` placeholder.
+        #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+        pub enum Variable {
+            $( $(#[$meta])* $variant, )*
+        }
+
+        impl Variable {
+            /// All known variables.
+            pub const ALL: &'static [Variable] = &[ $( Self::$variant, )* ];
+
+            /// The name of the variable, as written inside of the placeholder.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $( Self::$variant => $name, )*
+                }
+            }
+        }
+
+        impl FromStr for Variable {
+            type Err = TemplateError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $( $name => Ok(Self::$variant), )*
+                    _ => Err(TemplateError::UnknownVariable(s.to_string())),
+                }
+            }
+        }
+    };
+}
+
+variables! {
+    /// The game token of the account (secret).
+    AuthAccessToken => "auth_access_token",
+    /// The in-game username of the account.
+    AuthPlayerName => "auth_player_name",
+    /// Legacy session identifier, in the form of `token:{access token}:{uuid}` (secret).
+    AuthSession => "auth_session",
+    /// The UUID of the account.
+    AuthUuid => "auth_uuid",
+    /// The Xbox user ID of the account (secret).
+    AuthXuid => "auth_xuid",
+    /// The client ID used for telemetry.
+    ClientId => "clientid",
+    /// The type of the account (`msa` or `legacy`).
+    UserType => "user_type",
+    /// JSON object with user properties, used by old versions.
+    UserProperties => "user_properties",
+    /// The ID of the game version being launched.
+    VersionName => "version_name",
+    /// The type of the game version being launched, shown in the main menu.
+    VersionType => "version_type",
+    /// The directory that the game will run in.
+    GameDirectory => "game_directory",
+    /// The root directory of the asset store.
+    AssetsRoot => "assets_root",
+    /// The ID of the asset index used by the game version.
+    AssetsIndexName => "assets_index_name",
+    /// The directory with the assets for legacy versions that don't use the object store.
+    GameAssets => "game_assets",
+    /// The class path, joined by the platform's separator.
+    Classpath => "classpath",
+    /// The platform's class path separator (`:` or `;`).
+    ClasspathSeparator => "classpath_separator",
+    /// The root directory of the library store.
+    LibraryDirectory => "library_directory",
+    /// The directory where native libraries have been extracted to.
+    NativesDirectory => "natives_directory",
+    /// The name of the launcher.
+    LauncherName => "launcher_name",
+    /// The version of the launcher.
+    LauncherVersion => "launcher_version",
+    /// The width of the game window.
+    ResolutionWidth => "resolution_width",
+    /// The height of the game window.
+    ResolutionHeight => "resolution_height",
+    /// The file that the game writes quick play logs to.
+    QuickPlayPath => "quickPlayPath",
+    /// The singleplayer world to join on launch.
+    QuickPlaySingleplayer => "quickPlaySingleplayer",
+    /// The multiplayer server to join on launch.
+    QuickPlayMultiplayer => "quickPlayMultiplayer",
+    /// The realm to join on launch.
+    QuickPlayRealms => "quickPlayRealms",
+}
+
+impl Variable {
+    /// Whether the value of this variable should never end up in logs.
+    pub fn is_secret(&self) -> bool {
+        matches!(
+            self,
+            Self::AuthAccessToken | Self::AuthSession | Self::AuthXuid
+        )
+    }
+}
+
+impl Display for Variable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A set of values for [Variable]s, used to substitute placeholders in arguments.
+#[derive(Clone, Debug, Default)]
+pub struct Variables {
+    values: HashMap<Variable, String>,
+}
+
+impl Variables {
+    /// Creates an empty set of variables.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the value of a variable, if it is set.
+    pub fn get(&self, variable: Variable) -> Option<&str> {
+        self.values.get(&variable).map(String::as_str)
+    }
+
+    /// Sets the value of a variable, replacing any previous value.
+    pub fn set(&mut self, variable: Variable, value: impl Into<String>) -> &mut Self {
+        self.values.insert(variable, value.into());
+        self
+    }
+
+    /// Sets all variables that are derived from an [Account].
+    pub fn set_account(&mut self, account: &Account) -> &mut Self {
+        let user_type = match account.credentials {
+            AccountCredentials::Microsoft { .. } => "msa",
+            AccountCredentials::Offline => "legacy",
+        };
+
+        self.set(Variable::AuthPlayerName, &account.username)
+            .set(Variable::AuthUuid, &account.id)
+            .set(Variable::AuthAccessToken, &account.token)
+            .set(
+                Variable::AuthSession,
+                format!("token:{}:{}", account.token, account.id),
+            )
+            .set(Variable::UserType, user_type)
+            .set(Variable::UserProperties, "{}")
+    }
+
+    /// Replaces every `${name}` placeholder in the template with the value of the variable.
+    pub fn substitute(
+        &self,
+        template: &str,
+        mode: SubstitutionMode,
+    ) -> Result<String, TemplateError> {
+        let mut output = String::with_capacity(template.len());
+        let mut rest = template;
+
+        while let Some(start) = rest.find("${") {
+            output.push_str(&rest[..start]);
+
+            let offset = template.len() - rest.len() + start;
+            let Some(length) = rest[start..].find('}') else {
+                match mode {
+                    SubstitutionMode::Strict => return Err(TemplateError::Unterminated(offset)),
+                    SubstitutionMode::Lenient => {
+                        rest = &rest[start..];
+                        break;
+                    }
+                }
+            };
+
+            let placeholder = &rest[start..start + length + 1];
+            let value = Variable::from_str(&placeholder[2..placeholder.len() - 1])
+                .and_then(|it| self.get(it).ok_or(TemplateError::UnsetVariable(it)));
+
+            match (value, mode) {
+                (Ok(value), _) => output.push_str(value),
+                (Err(_), SubstitutionMode::Lenient) => output.push_str(placeholder),
+                (Err(err), SubstitutionMode::Strict) => return Err(err),
+            }
+
+            rest = &rest[start + length + 1..];
+        }
+
+        output.push_str(rest);
+        Ok(output)
+    }
+
+    /// Substitutes every template in a list (see [Variables::substitute]).
+    pub fn substitute_all(
+        &self,
+        templates: &[String],
+        mode: SubstitutionMode,
+    ) -> Result<Vec<String>, TemplateError> {
+        templates
+            .iter()
+            .map(|it| self.substitute(it, mode))
+            .collect()
+    }
+
+    /// Replaces the values of all secret variables in already substituted text with [REDACTED],
+    /// so that it can be safely logged.
+    pub fn redact(&self, text: &str) -> String {
+        self.values
+            .iter()
+            .filter(|(variable, value)| variable.is_secret() && !value.is_empty())
+            .fold(text.to_string(), |text, (_, value)| {
+                text.replace(value.as_str(), REDACTED)
+            })
+    }
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account() -> Account {
+        Account {
+            id: "069a79f444e94726a5befca90e38aaf5".to_string(),
+            username: "Notch".to_string(),
+            has_profile: true,
+            token: "eyJhbGciOiJIUzI1NiJ9.secret".to_string(),
+            expires: None,
+            credentials: AccountCredentials::Microsoft {
+                access: "access".to_string(),
+                refresh: "refresh".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn substitute() {
+        let mut variables = Variables::new();
+        variables
+            .set_account(&account())
+            .set(Variable::NativesDirectory, "/tmp/natives");
+
+        assert_eq!(
+            variables.substitute(
+                "-Djava.library.path=${natives_directory}",
+                SubstitutionMode::Strict
+            ),
+            Ok("-Djava.library.path=/tmp/natives".to_string())
+        );
+        assert_eq!(
+            variables.substitute(
+                "${auth_player_name} (${user_type})",
+                SubstitutionMode::Strict
+            ),
+            Ok("Notch (msa)".to_string())
+        );
+        assert_eq!(
+            variables.substitute("no placeholders, just $ and {}", SubstitutionMode::Strict),
+            Ok("no placeholders, just $ and {}".to_string())
+        );
+    }
+
+    #[test]
+    fn modes() {
+        let variables = Variables::new();
+
+        // Unknown and unset variables are only errors in strict mode...
+        assert_eq!(
+            variables.substitute("${something}", SubstitutionMode::Strict),
+            Err(TemplateError::UnknownVariable("something".to_string()))
+        );
+        assert_eq!(
+            variables.substitute("${classpath}", SubstitutionMode::Strict),
+            Err(TemplateError::UnsetVariable(Variable::Classpath))
+        );
+        assert_eq!(
+            variables.substitute("a ${b", SubstitutionMode::Strict),
+            Err(TemplateError::Unterminated(2))
+        );
+
+        // ...otherwise they are left as-is
+        assert_eq!(
+            variables.substitute("${something} ${classpath} ${b", SubstitutionMode::Lenient),
+            Ok("${something} ${classpath} ${b".to_string())
+        );
+    }
+
+    #[test]
+    fn redact() {
+        let mut variables = Variables::new();
+        variables.set_account(&account());
+
+        let args = [
+            "--accessToken",
+            "${auth_access_token}",
+            "--username",
+            "${auth_player_name}",
+        ];
+        let command = args
+            .map(|it| variables.substitute(it, SubstitutionMode::Strict).unwrap())
+            .join(" ");
+
+        assert_eq!(
+            variables.redact(&command),
+            "--accessToken <redacted> --username Notch"
+        );
+    }
+}
@@ -20,8 +20,13 @@
 //! into many submodules that categorize the data models based on where they are used:
 //!
 //! - [core] - Useful structures and APIs used throughout the code.
+//!     - [core::assets] - Asset indexes and the objects they list.
 //!     - [core::conditional] - Data-driven condition API.
+//!     - [core::instance] - Game instances and their configuration.
 //!     - [core::maven] - Wrapper around Maven artifact identifiers.
+//!     - [core::mods] - Metadata of mods and their dependencies.
+//!     - [core::settings] - Layered launcher settings with per-instance overrides.
+//!     - [core::template] - Substitution of placeholders in game and JVM arguments.
 //!
 //! - [game] - Models used in core launcher functions (i.e. accounts, Java builds, launching, etc.).
 //!     - [game::assets]
@@ -36,8 +41,13 @@
 //!     - [silo::loader] - Loader Meta APIs.
 //!
 //! - [web] - Models for web APIs used either partially or wholly on the client side.
+//!     - [web::curseforge] - CurseForge modpack manifests.
 //!     - [web::microsoft] - Microsoft's Authentication and Xbox APIs.
+//!     - [web::modrinth] - Modrinth modpacks (`.mrpack`).
+//!     - [web::mods] - Metadata files of the different mod loaders.
 //!     - [web::mojang] - Mojang's API.
+//!     - [web::prism] - Prism Launcher and MultiMC instances.
+//!     - [web::vanilla] - Version JSONs and profiles of the official launcher.
 
 pub mod core;
 pub mod silo;
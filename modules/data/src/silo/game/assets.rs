@@ -18,11 +18,15 @@ use std::collections::HashMap;
 
 #[api_response]
 pub struct AssetIndex {
+    #[serde(rename = "virtual")]
+    pub is_virtual: Option<bool>,
+    pub map_to_resources: Option<bool>,
     pub objects: HashMap<String, AssetIndexObject>,
 }
 
 #[api_response]
 pub struct AssetIndexObject {
+    #[serde(deserialize_with = "crate::core::assets::sha1")]
     pub hash: String,
     pub size: u64,
 }
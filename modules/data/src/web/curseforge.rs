@@ -0,0 +1,121 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The format of CurseForge modpacks, and the parts of the CurseForge API that are needed to
+//! download their files. A modpack is a zip archive with a manifest (`manifest.json`) that lists
+//! the files by their project and file IDs, and a directory with files that are copied into the
+//! game directory as-is.
+
+use url::Url;
+
+use macros::api_response;
+
+/// The name of the manifest in the archive.
+pub const MANIFEST: &str = "manifest.json";
+
+/// The type of manifests of modpacks.
+pub const MANIFEST_TYPE: &str = "minecraftModpack";
+
+/// The base URL of the CurseForge API. Requests need an API key (`x-api-key` header).
+pub const API_URL: &str = "https://api.curseforge.com/v1";
+
+/// The hash algorithm ID of SHA1 in [CurseForgeHash].
+pub const SHA1: u64 = 1;
+
+fn default_overrides() -> String {
+    "overrides".to_string()
+}
+
+fn default_required() -> bool {
+    true
+}
+
+/// A mod loader of a modpack, by its ID and version (e.g. `forge-47.2.0` or `fabric-0.15.7`).
+#[api_response(strict = false)]
+pub struct CurseForgeModLoader {
+    pub id: String,
+    /// Whether this is the loader that the pack is launched with.
+    #[serde(default)]
+    pub primary: bool,
+}
+
+#[api_response(strict = false, rename = "camelCase")]
+pub struct CurseForgeMinecraft {
+    pub version: String,
+    #[serde(default)]
+    pub mod_loaders: Vec<CurseForgeModLoader>,
+}
+
+/// A file of a modpack, by its project and file IDs.
+#[api_response(strict = false)]
+pub struct CurseForgeFile {
+    #[serde(rename = "projectID")]
+    pub project_id: u64,
+    #[serde(rename = "fileID")]
+    pub file_id: u64,
+    /// Whether the file is installed. Optional files that the author left disabled aren't.
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+/// The contents of `manifest.json`.
+#[api_response(strict = false, rename = "camelCase")]
+pub struct CurseForgeManifest {
+    pub minecraft: CurseForgeMinecraft,
+    pub manifest_type: String,
+    pub manifest_version: u64,
+    pub name: String,
+    pub version: Option<String>,
+    pub author: Option<String>,
+    #[serde(default)]
+    pub files: Vec<CurseForgeFile>,
+    /// The directory in the archive with the files that are copied into the game directory.
+    #[serde(default = "default_overrides")]
+    pub overrides: String,
+}
+
+/// The envelope around every response of the API.
+#[api_response(strict = false)]
+pub struct CurseForgeResponse<T> {
+    pub data: T,
+}
+
+#[api_response(strict = false)]
+pub struct CurseForgeHash {
+    pub value: String,
+    /// The hash algorithm (see [SHA1]).
+    pub algo: u64,
+}
+
+/// A file of a project, from `/mods/{projectID}/files/{fileID}`.
+#[api_response(strict = false, rename = "camelCase")]
+pub struct CurseForgeApiFile {
+    pub id: u64,
+    pub file_name: String,
+    /// Missing for files of projects whose authors don't allow downloads from other launchers.
+    pub download_url: Option<Url>,
+    pub file_length: u64,
+    #[serde(default)]
+    pub hashes: Vec<CurseForgeHash>,
+}
+
+/// A project, from `/mods/{projectID}`.
+#[api_response(strict = false, rename = "camelCase")]
+pub struct CurseForgeProject {
+    pub id: u64,
+    pub name: String,
+    /// The kind of project (mod, resource pack, shader pack, etc.).
+    pub class_id: Option<u64>,
+}
@@ -13,6 +13,11 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+pub mod curseforge;
 pub mod meta;
 pub mod microsoft;
+pub mod modrinth;
+pub mod mods;
 pub mod mojang;
+pub mod prism;
+pub mod vanilla;
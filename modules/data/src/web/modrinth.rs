@@ -0,0 +1,104 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The format of Modrinth modpacks (`.mrpack`). A modpack is a zip archive with an index
+//! (`modrinth.index.json`) that lists the files to download, and directories with files that are
+//! copied into the game directory as-is (`overrides`, and `client-overrides` on top of it).
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// The name of the index in the archive.
+pub const INDEX: &str = "modrinth.index.json";
+
+/// The directory with files for both the client and the server.
+pub const OVERRIDES: &str = "overrides";
+
+/// The directory with files only for the client, applied after [OVERRIDES].
+pub const CLIENT_OVERRIDES: &str = "client-overrides";
+
+/// The dependency that holds the game version.
+pub const GAME_DEPENDENCY: &str = "minecraft";
+
+/// The only version of the format that exists so far.
+pub const FORMAT_VERSION: u64 = 1;
+
+/// Whether a file is needed on one side (client or server).
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ModrinthSupport {
+    Required,
+    Optional,
+    Unsupported,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ModrinthEnv {
+    pub client: ModrinthSupport,
+    pub server: ModrinthSupport,
+}
+
+/// The checksums of a file. Both are required by the format.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ModrinthHashes {
+    pub sha1: String,
+    pub sha512: String,
+}
+
+/// A file that is downloaded into the game directory.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModrinthFile {
+    /// The path of the file, relative to the game directory.
+    pub path: String,
+    pub hashes: ModrinthHashes,
+    /// The sides the file is needed on. Files without one are needed on both.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<ModrinthEnv>,
+    /// Mirrors of the file, tried in order.
+    pub downloads: Vec<Url>,
+    pub file_size: u64,
+}
+
+/// The contents of `modrinth.index.json`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModrinthIndex {
+    pub format_version: u64,
+    /// The game the pack is for, which is always `minecraft`.
+    pub game: String,
+    /// The version of the pack.
+    pub version_id: String,
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    pub files: Vec<ModrinthFile>,
+    /// The versions of the game (see [GAME_DEPENDENCY]) and mod loader, by their names (e.g.
+    /// `fabric-loader`).
+    pub dependencies: BTreeMap<String, String>,
+}
+
+// === impl ===
+
+impl ModrinthFile {
+    /// Whether the file is needed on the client. Optional files are included.
+    pub fn is_client(&self) -> bool {
+        self.env
+            .as_ref()
+            .is_none_or(|it| it.client != ModrinthSupport::Unsupported)
+    }
+}
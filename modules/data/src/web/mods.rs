@@ -0,0 +1,173 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The metadata files inside of mod JARs: `fabric.mod.json` for Fabric, `quilt.mod.json` for
+//! Quilt, and `META-INF/mods.toml` or `META-INF/neoforge.mods.toml` for Forge and NeoForge.
+
+use std::collections::BTreeMap;
+
+use macros::api_response;
+
+pub const FABRIC_METADATA: &str = "fabric.mod.json";
+pub const QUILT_METADATA: &str = "quilt.mod.json";
+pub const FORGE_METADATA: &str = "META-INF/mods.toml";
+pub const NEOFORGE_METADATA: &str = "META-INF/neoforge.mods.toml";
+
+/// The list of JARs nested in a Forge or NeoForge mod.
+pub const FORGE_NESTED: &str = "META-INF/jarjar/metadata.json";
+
+/// The manifest of a JAR, which has the version that `mods.toml` can refer to.
+pub const JAR_MANIFEST: &str = "META-INF/MANIFEST.MF";
+
+/// The placeholder that `mods.toml` uses for the version from the JAR manifest.
+pub const JAR_VERSION: &str = "${file.jarVersion}";
+
+/// Version predicates of a Fabric dependency. A list matches if any of them does.
+#[api_response(strict = false)]
+pub enum FabricVersions {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// The contents of `fabric.mod.json`.
+#[api_response(strict = false, rename = "camelCase")]
+pub struct FabricMod {
+    pub id: String,
+    pub version: String,
+    pub name: Option<String>,
+    #[serde(default)]
+    pub depends: BTreeMap<String, FabricVersions>,
+    #[serde(default)]
+    pub breaks: BTreeMap<String, FabricVersions>,
+    /// Other IDs that this mod can be depended on by.
+    #[serde(default)]
+    pub provides: Vec<String>,
+    /// Other mods that are nested in this mod's JAR.
+    #[serde(default)]
+    pub jars: Vec<FabricJar>,
+}
+
+#[api_response(strict = false)]
+pub struct FabricJar {
+    /// The path of the nested JAR inside of the mod's JAR.
+    pub file: String,
+}
+
+/// Version predicates of a Quilt dependency: one, a list of which any has to match, or an object
+/// with a list of which any or all have to match.
+#[api_response(strict = false)]
+pub enum QuiltVersions {
+    One(String),
+    Many(Vec<String>),
+    Constraint {
+        #[serde(default)]
+        any: Vec<String>,
+        #[serde(default)]
+        all: Vec<String>,
+    },
+}
+
+/// A Quilt dependency, either by its ID alone or with more details.
+#[api_response(strict = false)]
+pub enum QuiltDependency {
+    Id(String),
+    Detailed {
+        id: String,
+        versions: Option<QuiltVersions>,
+        #[serde(default)]
+        optional: bool,
+    },
+}
+
+#[api_response(strict = false)]
+pub enum QuiltProvides {
+    Id(String),
+    Detailed { id: String },
+}
+
+#[api_response(strict = false)]
+pub struct QuiltMetadata {
+    pub name: Option<String>,
+}
+
+#[api_response(strict = false)]
+pub struct QuiltLoader {
+    pub id: String,
+    pub version: String,
+    pub metadata: Option<QuiltMetadata>,
+    #[serde(default)]
+    pub depends: Vec<QuiltDependency>,
+    #[serde(default)]
+    pub breaks: Vec<QuiltDependency>,
+    #[serde(default)]
+    pub provides: Vec<QuiltProvides>,
+    /// The paths of other mods that are nested in this mod's JAR.
+    #[serde(default)]
+    pub jars: Vec<String>,
+}
+
+/// The contents of `quilt.mod.json`.
+#[api_response(strict = false)]
+pub struct QuiltMod {
+    pub quilt_loader: QuiltLoader,
+}
+
+/// A mod in `mods.toml`. One JAR can contain several.
+#[api_response(strict = false, rename = "camelCase")]
+pub struct ForgeModEntry {
+    pub mod_id: String,
+    /// The version of the mod, or [JAR_VERSION].
+    pub version: Option<String>,
+    pub display_name: Option<String>,
+}
+
+/// A dependency in `mods.toml`. Forge marks them as `mandatory`, while NeoForge gives their `type`
+/// (`required`, `optional`, `incompatible` or `discouraged`).
+#[api_response(strict = false, rename = "camelCase")]
+pub struct ForgeDependency {
+    pub mod_id: String,
+    pub mandatory: Option<bool>,
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    /// A Maven version range, e.g. `[47,)`.
+    pub version_range: Option<String>,
+    /// The side the dependency is needed on (`BOTH`, `CLIENT` or `SERVER`).
+    pub side: Option<String>,
+}
+
+/// The contents of `mods.toml` or `neoforge.mods.toml`.
+#[api_response(strict = false, rename = "camelCase")]
+pub struct ForgeMods {
+    pub mod_loader: Option<String>,
+    pub loader_version: Option<String>,
+    #[serde(default)]
+    pub mods: Vec<ForgeModEntry>,
+    /// The dependencies of each mod, by its ID.
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, Vec<ForgeDependency>>,
+}
+
+#[api_response(strict = false)]
+pub struct ForgeNestedJar {
+    /// The path of the nested JAR inside of the mod's JAR.
+    pub path: String,
+}
+
+/// The contents of `META-INF/jarjar/metadata.json`.
+#[api_response(strict = false)]
+pub struct ForgeNestedJars {
+    #[serde(default)]
+    pub jars: Vec<ForgeNestedJar>,
+}
@@ -0,0 +1,42 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The format of instances of Prism Launcher and MultiMC (which Prism is a fork of).
+
+use macros::api_response;
+
+/// The component that holds the game version.
+pub const GAME_UID: &str = "net.minecraft";
+
+/// A component of an instance: the game, a mod loader, a library like LWJGL, etc.
+#[api_response(strict = false, rename = "camelCase")]
+pub struct PrismComponent {
+    /// The ID of the component, e.g. `net.fabricmc.fabric-loader`.
+    pub uid: String,
+    /// The version of the component. Missing for some components that are added by others.
+    pub version: Option<String>,
+    /// The name of the component, as shown in the UI.
+    pub cached_name: Option<String>,
+    /// Whether the component was only added because another one depends on it.
+    #[serde(default)]
+    pub dependency_only: bool,
+}
+
+/// The contents of `mmc-pack.json`, which lists the components of an instance.
+#[api_response(strict = false, rename = "camelCase")]
+pub struct PrismPack {
+    pub components: Vec<PrismComponent>,
+    pub format_version: u64,
+}
@@ -0,0 +1,1242 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The version format of the official launcher, which keeps each version in
+//! `versions/<id>/<id>.json`. Besides Mojang, these files are written by mod loader installers,
+//! which often only list what they change and point to the version they build on with
+//! `inheritsFrom`. Unlike the models in `silo`, these are lenient and can be written back, so that
+//! versions can be exchanged with other tools.
+
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+
+use chrono::{DateTime, Utc};
+use platforms::{Arch, OS};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use url::Url;
+
+use crate::core::conditional::{Condition, MaybeConditional};
+use crate::core::game::{
+    GameVersion, GameVersionAssetIndex, GameVersionLogging, GameVersionStability, java_requirement,
+    legacy_java_arguments,
+};
+use crate::core::library::{Library, LibraryDownloadable, LibraryExtract, LibrarySource};
+use crate::core::maven::MavenIdentifier;
+
+/// The repository that libraries without a URL of their own are downloaded from.
+pub const LIBRARIES_URL: &str = "https://libraries.minecraft.net/";
+
+#[derive(Debug, Error)]
+pub enum VanillaError {
+    #[error("version {0} has no {1}")]
+    Missing(String, &'static str),
+    #[error("condition `{0}` can't be expressed as rules")]
+    Inexpressible(Condition),
+    #[error("invalid url: {0}")]
+    Url(#[from] url::ParseError),
+    #[error("unsafe library path: {0}")]
+    UnsafePath(String),
+}
+
+pub type Result<T> = core::result::Result<T, VanillaError>;
+
+/// A version JSON. Nearly everything is optional, as versions that inherit from another one only
+/// contain what they add or change.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VanillaVersion {
+    pub id: String,
+    /// The ID of the version this one builds on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inherits_from: Option<String>,
+    /// The stability of the version (`release`, `snapshot`, `old_beta` or `old_alpha`).
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub stability: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_time: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub main_class: Option<String>,
+    /// The game arguments of versions from before 17w43a, separated by spaces.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minecraft_arguments: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<VanillaArguments>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub libraries: Vec<VanillaLibrary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub java_version: Option<VanillaJavaVersion>,
+    /// The ID of the asset index.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assets: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_index: Option<VanillaAssetIndex>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub downloads: Option<VanillaDownloads>,
+    /// The ID of the version whose JAR file is used, if not this one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jar: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logging: Option<VanillaLogging>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct VanillaArguments {
+    #[serde(default)]
+    pub game: Vec<VanillaArgument>,
+    #[serde(default)]
+    pub jvm: Vec<VanillaArgument>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum VanillaArgument {
+    Plain(String),
+    Conditional {
+        rules: Vec<VanillaRule>,
+        value: VanillaArgumentValue,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum VanillaArgumentValue {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// A rule of a library or argument. A list of rules applies if the last rule that matches allows
+/// it, and a rule matches if all of its criteria do (a rule without criteria always matches).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct VanillaRule {
+    pub action: VanillaRuleAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub os: Option<VanillaRuleOs>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub features: Option<BTreeMap<String, bool>>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum VanillaRuleAction {
+    Allow,
+    Disallow,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct VanillaRuleOs {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arch: Option<String>,
+    /// A regular expression that the OS version should match.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+/// A library. Mojang lists its downloads, while mod loaders often only give the repository it is in
+/// (`url`), and sometimes its checksum and size next to it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct VanillaLibrary {
+    pub name: MavenIdentifier,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha1: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub downloads: Option<VanillaLibraryDownloads>,
+    /// The classifier of the native library for each OS (e.g. `linux` to `natives-linux`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub natives: Option<BTreeMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extract: Option<VanillaExtract>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rules: Option<Vec<VanillaRule>>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct VanillaLibraryDownloads {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artifact: Option<VanillaArtifact>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub classifiers: Option<BTreeMap<String, VanillaArtifact>>,
+}
+
+/// A file of a library. Installers leave the URL empty for files that they generate themselves.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct VanillaArtifact {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha1: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct VanillaExtract {
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VanillaJavaVersion {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+    pub major_version: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VanillaAssetIndex {
+    pub id: String,
+    pub sha1: String,
+    pub size: u64,
+    #[serde(default)]
+    pub total_size: u64,
+    pub url: Url,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct VanillaDownloads {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client: Option<VanillaDownload>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server: Option<VanillaDownload>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct VanillaDownload {
+    pub sha1: String,
+    pub size: u64,
+    pub url: Url,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct VanillaLogging {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client: Option<VanillaLoggingConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct VanillaLoggingConfig {
+    pub argument: String,
+    pub file: VanillaLoggingFile,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct VanillaLoggingFile {
+    pub id: String,
+    pub sha1: String,
+    pub size: u64,
+    pub url: Url,
+}
+
+/// A set of criteria that all have to hold, which is what a list of rules can express: an allowing
+/// rule with the positive criteria, followed by a disallowing rule for each excluded case.
+#[derive(Clone, Default)]
+struct Conjunction {
+    os: Option<OS>,
+    arch: Option<Arch>,
+    features: BTreeMap<String, bool>,
+    excluded: Vec<VanillaRule>,
+}
+
+// === impl ===
+
+impl VanillaVersion {
+    /// Fills in this version with what it inherits from its parent. Libraries of this version come
+    /// first and replace those of the parent with the same name, while arguments are appended.
+    pub fn inherit(mut self, parent: VanillaVersion) -> Self {
+        let key = |it: &VanillaLibrary| {
+            let name = &it.name;
+            (
+                name.group.clone(),
+                name.artifact.clone(),
+                name.classifier.clone(),
+            )
+        };
+
+        let own: Vec<_> = self.libraries.iter().map(key).collect();
+        let inherited = parent
+            .libraries
+            .into_iter()
+            .filter(|it| !own.contains(&key(it)));
+        self.libraries.extend(inherited);
+
+        self.arguments = match (parent.arguments, self.arguments) {
+            (Some(mut parent), Some(child)) => {
+                parent.game.extend(child.game);
+                parent.jvm.extend(child.jvm);
+                Some(parent)
+            }
+            (parent, child) => child.or(parent),
+        };
+
+        if self.jar.is_none() && self.downloads.is_none() {
+            self.jar = parent.jar.or(Some(parent.id));
+        }
+
+        self.inherits_from = parent.inherits_from;
+        self.stability = self.stability.or(parent.stability);
+        self.release_time = self.release_time.or(parent.release_time);
+        self.time = self.time.or(parent.time);
+        self.main_class = self.main_class.or(parent.main_class);
+        self.minecraft_arguments = self.minecraft_arguments.or(parent.minecraft_arguments);
+        self.java_version = self.java_version.or(parent.java_version);
+        self.assets = self.assets.or(parent.assets);
+        self.asset_index = self.asset_index.or(parent.asset_index);
+        self.downloads = self.downloads.or(parent.downloads);
+        self.logging = self.logging.or(parent.logging);
+        self
+    }
+}
+
+impl VanillaRule {
+    fn allow() -> Self {
+        Self {
+            action: VanillaRuleAction::Allow,
+            os: None,
+            features: None,
+        }
+    }
+
+    /// The condition under which this rule matches.
+    fn criteria(&self) -> Condition {
+        let mut conditions = vec![];
+
+        if let Some(os) = &self.os {
+            if let Some(name) = &os.name {
+                conditions.push(os_from_name(name).map_or(Condition::Never, Condition::OS));
+            }
+
+            if let Some(arch) = &os.arch {
+                conditions.push(arch_from_name(arch).map_or(Condition::Never, Condition::Arch));
+            }
+
+            // Like in the conversion of Mojang's rules, versions of macOS and Windows that the
+            // launcher doesn't run on are never matched. Other version requirements are ignored.
+            if let Some(version) = &os.version {
+                match (version.as_str(), os.name.as_deref()) {
+                    ("^10\\.5\\.\\d$", Some("osx")) => conditions.push(Condition::Never),
+                    ("^10\\.", Some("windows")) => conditions.push(Condition::Never),
+                    ("^11\\.", Some("windows")) => conditions.push(Condition::Never),
+                    _ => {}
+                }
+            }
+        }
+
+        for (feature, value) in self.features.iter().flatten() {
+            let condition = Condition::Feature(feature.clone());
+            conditions.push(match value {
+                true => condition,
+                false => Condition::Not(Box::new(condition)),
+            });
+        }
+
+        Condition::And(conditions).simplify()
+    }
+}
+
+impl Conjunction {
+    /// Combines two conjunctions, or returns [None] if they contradict each other.
+    fn merge(&self, other: &Self) -> Option<Self> {
+        fn pick<T: Clone + PartialEq>(a: &Option<T>, b: &Option<T>) -> Option<Option<T>> {
+            match (a, b) {
+                (Some(a), Some(b)) if a != b => None,
+                _ => Some(a.clone().or_else(|| b.clone())),
+            }
+        }
+
+        let mut features = self.features.clone();
+        for (feature, value) in &other.features {
+            if *features.entry(feature.clone()).or_insert(*value) != *value {
+                return None;
+            }
+        }
+
+        Some(Self {
+            os: pick(&self.os, &other.os)?,
+            arch: pick(&self.arch, &other.arch)?,
+            features,
+            excluded: [self.excluded.clone(), other.excluded.clone()].concat(),
+        })
+    }
+
+    /// The rule that matches when all positive criteria hold, if there are any.
+    fn positive(&self, action: VanillaRuleAction) -> Option<VanillaRule> {
+        if self.os.is_none() && self.arch.is_none() && self.features.is_empty() {
+            return None;
+        }
+
+        let os = (self.os.is_some() || self.arch.is_some()).then(|| VanillaRuleOs {
+            name: self.os.map(os_name),
+            arch: self.arch.map(arch_name),
+            version: None,
+        });
+
+        Some(VanillaRule {
+            action,
+            os,
+            features: (!self.features.is_empty()).then(|| self.features.clone()),
+        })
+    }
+
+    /// The rules for this conjunction. No rules means that it always holds.
+    fn rules(&self) -> Vec<VanillaRule> {
+        if self.excluded.is_empty() {
+            return self
+                .positive(VanillaRuleAction::Allow)
+                .into_iter()
+                .collect();
+        }
+
+        let allow = self
+            .positive(VanillaRuleAction::Allow)
+            .unwrap_or_else(VanillaRule::allow);
+        [vec![allow], self.excluded.clone()].concat()
+    }
+}
+
+/// Converts a condition to alternatives that each can be written as a list of rules. Nested
+/// negations and exclusive ors can't be expressed this way.
+fn alternatives(condition: &Condition) -> Result<Vec<Conjunction>> {
+    Ok(match condition {
+        Condition::Always => vec![Conjunction::default()],
+        Condition::Never => vec![],
+        Condition::Feature(feature) => vec![Conjunction {
+            features: BTreeMap::from([(feature.clone(), true)]),
+            ..Default::default()
+        }],
+        Condition::OS(os) => vec![Conjunction {
+            os: Some(*os),
+            ..Default::default()
+        }],
+        Condition::Arch(arch) => vec![Conjunction {
+            arch: Some(*arch),
+            ..Default::default()
+        }],
+        Condition::Not(inner) => match alternatives(inner)?.as_slice() {
+            [] => vec![Conjunction::default()],
+            [it] if it.excluded.is_empty() => match it.positive(VanillaRuleAction::Disallow) {
+                Some(rule) => vec![Conjunction {
+                    excluded: vec![rule],
+                    ..Default::default()
+                }],
+                None => vec![],
+            },
+            _ => return Err(VanillaError::Inexpressible(condition.clone())),
+        },
+        Condition::And(conditions) => {
+            let mut result = vec![Conjunction::default()];
+            for condition in conditions {
+                let options = alternatives(condition)?;
+                result = result
+                    .iter()
+                    .flat_map(|a| options.iter().filter_map(|b| a.merge(b)))
+                    .collect();
+            }
+            result
+        }
+        Condition::Or(conditions) => {
+            let mut result = vec![];
+            for condition in conditions {
+                result.extend(alternatives(condition)?);
+            }
+            result
+        }
+        Condition::Xor(_) => return Err(VanillaError::Inexpressible(condition.clone())),
+    })
+}
+
+/// Converts a list of rules to the condition under which it applies.
+fn condition(rules: &[VanillaRule]) -> Condition {
+    let condition = rules.iter().fold(Condition::Never, |rest, rule| {
+        let criteria = rule.criteria();
+        match (rule.action, rest) {
+            (VanillaRuleAction::Allow, Condition::Never) => criteria,
+            (VanillaRuleAction::Allow, rest) => Condition::Or(vec![criteria, rest]),
+            (VanillaRuleAction::Disallow, Condition::Never) => Condition::Never,
+            (VanillaRuleAction::Disallow, Condition::Always) => Condition::Not(Box::new(criteria)),
+            (VanillaRuleAction::Disallow, rest) => {
+                Condition::And(vec![Condition::Not(Box::new(criteria)), rest])
+            }
+        }
+    });
+
+    condition.simplify()
+}
+
+fn conditional<T>(when: Condition, then: T) -> MaybeConditional<T> {
+    match when {
+        Condition::Always => MaybeConditional::Unconditional(then),
+        when => MaybeConditional::Conditional { when, then },
+    }
+}
+
+fn os_from_name(name: &str) -> Option<OS> {
+    match name {
+        "osx" | "macos" => Some(OS::MacOS),
+        "linux" => Some(OS::Linux),
+        "windows" => Some(OS::Windows),
+        _ => None,
+    }
+}
+
+fn os_name(os: OS) -> String {
+    match os {
+        OS::MacOS => "osx".to_string(),
+        other => other.as_str().to_string(),
+    }
+}
+
+// Mojang's `x86` is taken to mean any Intel CPU, the same as in the conversion of Mojang's rules.
+fn arch_from_name(name: &str) -> Option<Arch> {
+    match name {
+        "x86" | "x86_64" => Some(Arch::X86_64),
+        "arm64" | "aarch64" => Some(Arch::AArch64),
+        _ => None,
+    }
+}
+
+fn arch_name(arch: Arch) -> String {
+    match arch {
+        Arch::X86_64 => "x86".to_string(),
+        Arch::AArch64 => "arm64".to_string(),
+        other => other.as_str().to_string(),
+    }
+}
+
+/// The name of the runtime that the official launcher uses for a major Java version.
+fn java_component(major: u64) -> Option<String> {
+    let component = match major {
+        8 => "jre-legacy",
+        16 => "java-runtime-alpha",
+        17 => "java-runtime-gamma",
+        21 => "java-runtime-delta",
+        _ => return None,
+    };
+    Some(component.to_string())
+}
+
+fn stability_name(stability: &GameVersionStability) -> &'static str {
+    match stability {
+        GameVersionStability::Release => "release",
+        GameVersionStability::Snapshot => "snapshot",
+        GameVersionStability::OldBeta => "old_beta",
+        GameVersionStability::OldAlpha => "old_alpha",
+    }
+}
+
+/// Resolves a file of a library, falling back to its Maven path in `repository`. Files with an
+/// empty URL are generated by an installer, and files without a checksum can't be verified. Paths
+/// that would end up outside of the library directory are rejected.
+fn file(
+    name: &MavenIdentifier,
+    artifact: VanillaArtifact,
+    repository: &Url,
+) -> Result<LibraryDownloadable> {
+    let path = artifact.path.unwrap_or_else(|| name.path());
+    let safe = !path.is_empty()
+        && Path::new(&path)
+            .components()
+            .all(|it| matches!(it, Component::Normal(_)));
+
+    if !safe {
+        return Err(VanillaError::UnsafePath(path));
+    }
+
+    let (url, source) = match (artifact.url.as_deref(), &artifact.sha1) {
+        (Some(""), _) => (repository.join(&path)?, LibrarySource::Local),
+        (Some(url), Some(_)) => (Url::parse(url)?, LibrarySource::Verified),
+        (Some(url), None) => (Url::parse(url)?, LibrarySource::Unverified),
+        (None, Some(_)) => (repository.join(&path)?, LibrarySource::Verified),
+        (None, None) => (repository.join(&path)?, LibrarySource::Unverified),
+    };
+
+    Ok(LibraryDownloadable {
+        path,
+        checksum: artifact.sha1.unwrap_or_default(),
+        size: artifact.size.unwrap_or_default(),
+        url,
+        source,
+    })
+}
+
+fn artifact(file: &LibraryDownloadable) -> VanillaArtifact {
+    let verified = file.source == LibrarySource::Verified;
+    VanillaArtifact {
+        path: Some(file.path.clone()),
+        sha1: verified.then(|| file.checksum.clone()),
+        size: verified.then_some(file.size),
+        url: Some(match file.source {
+            LibrarySource::Local => String::new(),
+            _ => file.url.to_string(),
+        }),
+    }
+}
+
+fn rules(conjunction: &Conjunction) -> Option<Vec<VanillaRule>> {
+    Some(conjunction.rules()).filter(|it| !it.is_empty())
+}
+
+/// Converts arguments, putting consecutive ones with the same condition (e.g. `--width` and its
+/// value) in one entry.
+fn arguments(values: &[MaybeConditional<String>]) -> Result<Vec<VanillaArgument>> {
+    let mut result = vec![];
+    let mut previous: Option<&Condition> = None;
+
+    for value in values {
+        let (when, then) = match value {
+            MaybeConditional::Unconditional(then) => {
+                previous = None;
+                result.push(VanillaArgument::Plain(then.clone()));
+                continue;
+            }
+            MaybeConditional::Conditional { when, then } => (when, then),
+        };
+
+        if previous == Some(when)
+            && let Some(VanillaArgument::Conditional { value, .. }) = result.last_mut()
+        {
+            *value = match std::mem::replace(value, VanillaArgumentValue::Many(vec![])) {
+                VanillaArgumentValue::One(first) => {
+                    VanillaArgumentValue::Many(vec![first, then.clone()])
+                }
+                VanillaArgumentValue::Many(mut values) => {
+                    values.push(then.clone());
+                    VanillaArgumentValue::Many(values)
+                }
+            };
+            continue;
+        }
+
+        let options = alternatives(when)?;
+        previous = (options.len() == 1).then_some(when);
+        for option in options {
+            result.push(match rules(&option) {
+                None => VanillaArgument::Plain(then.clone()),
+                Some(rules) => VanillaArgument::Conditional {
+                    rules,
+                    value: VanillaArgumentValue::One(then.clone()),
+                },
+            });
+        }
+    }
+
+    Ok(result)
+}
+
+// === conversion ===
+
+impl From<VanillaArgument> for Vec<MaybeConditional<String>> {
+    fn from(value: VanillaArgument) -> Self {
+        match value {
+            VanillaArgument::Plain(value) => vec![MaybeConditional::Unconditional(value)],
+            VanillaArgument::Conditional { rules, value } => {
+                let when = condition(&rules);
+                let values = match value {
+                    VanillaArgumentValue::One(value) => vec![value],
+                    VanillaArgumentValue::Many(values) => values,
+                };
+                values
+                    .into_iter()
+                    .map(|it| conditional(when.clone(), it))
+                    .collect()
+            }
+        }
+    }
+}
+
+impl TryFrom<VanillaLibrary> for Vec<MaybeConditional<Library>> {
+    type Error = VanillaError;
+
+    fn try_from(value: VanillaLibrary) -> Result<Self> {
+        let repository = Url::parse(value.url.as_deref().unwrap_or(LIBRARIES_URL))?;
+        let base = value.rules.as_deref().map_or(Condition::Always, condition);
+        let mut downloads = value.downloads.unwrap_or_default();
+        let mut libraries = vec![];
+
+        let exclude = value.extract.map(|it| it.exclude).unwrap_or_default();
+        for (os, classifier) in value.natives.into_iter().flatten() {
+            let Some(os) = os_from_name(&os) else {
+                continue;
+            };
+            let classifier = classifier.replace("${arch}", "64");
+
+            // Libraries without downloads are in a repository, with the natives next to them.
+            let artifact = match downloads.classifiers.as_mut() {
+                Some(classifiers) => match classifiers.remove(&classifier) {
+                    Some(artifact) => artifact,
+                    None => continue,
+                },
+                None => VanillaArtifact {
+                    path: Some(
+                        MavenIdentifier {
+                            classifier: Some(classifier),
+                            ..value.name.clone()
+                        }
+                        .path(),
+                    ),
+                    ..Default::default()
+                },
+            };
+
+            let when = Condition::And(vec![base.clone(), Condition::OS(os)]).simplify();
+            libraries.push(conditional(
+                when,
+                Library {
+                    name: value.name.clone(),
+                    file: file(&value.name, artifact, &repository)?,
+                    extract: Some(LibraryExtract {
+                        exclude: exclude.clone(),
+                    }),
+                },
+            ));
+        }
+
+        let artifact = match downloads.artifact {
+            Some(artifact) => Some(artifact),
+            None if libraries.is_empty() && downloads.classifiers.is_none() => {
+                Some(VanillaArtifact {
+                    sha1: value.sha1,
+                    size: value.size,
+                    ..Default::default()
+                })
+            }
+            None => None,
+        };
+
+        if let Some(artifact) = artifact {
+            libraries.push(conditional(
+                base,
+                Library {
+                    file: file(&value.name, artifact, &repository)?,
+                    name: value.name,
+                    extract: None,
+                },
+            ));
+        }
+
+        Ok(libraries)
+    }
+}
+
+impl TryFrom<VanillaVersion> for GameVersion {
+    type Error = VanillaError;
+
+    /// Converts a version that doesn't inherit from another one anymore (see
+    /// [VanillaVersion::inherit]).
+    fn try_from(value: VanillaVersion) -> Result<Self> {
+        let id = value.id;
+        let missing = |field| VanillaError::Missing(id.clone(), field);
+
+        let jar = value.jar.unwrap_or_else(|| id.clone());
+        let game_jar = |kind: &str, download: VanillaDownload| LibraryDownloadable {
+            path: format!("com/mojang/minecraft/{jar}/minecraft-{jar}-{kind}.jar"),
+            checksum: download.sha1,
+            size: download.size,
+            url: download.url,
+            source: LibrarySource::Verified,
+        };
+
+        let downloads = value.downloads.unwrap_or_default();
+        let client = downloads.client.ok_or_else(|| missing("client download"))?;
+        let asset_index = value.asset_index.ok_or_else(|| missing("asset index"))?;
+
+        let mut libraries = vec![];
+        for library in value.libraries {
+            libraries.extend(Vec::<MaybeConditional<Library>>::try_from(library)?);
+        }
+
+        let convert = |args: Vec<VanillaArgument>| -> Vec<MaybeConditional<String>> {
+            args.into_iter()
+                .flat_map(Vec::<MaybeConditional<String>>::from)
+                .collect()
+        };
+
+        let arguments = value.arguments.unwrap_or_default();
+        let (java_arguments, game_arguments) = match value.minecraft_arguments {
+            // Versions from before 17w43a leave the Java arguments to the launcher.
+            Some(legacy) => {
+                let mut java_arguments = legacy_java_arguments();
+                java_arguments.extend(convert(arguments.jvm));
+
+                let mut game_arguments: Vec<_> = legacy
+                    .split_whitespace()
+                    .map(|it| MaybeConditional::Unconditional(it.to_string()))
+                    .collect();
+                game_arguments.extend(convert(arguments.game));
+
+                (java_arguments, game_arguments)
+            }
+            None => (convert(arguments.jvm), convert(arguments.game)),
+        };
+
+        Ok(Self {
+            release_date: value
+                .release_time
+                .or(value.time)
+                .ok_or_else(|| missing("release time"))?,
+            stability: match value.stability.as_deref() {
+                Some("snapshot") => GameVersionStability::Snapshot,
+                Some("old_beta") => GameVersionStability::OldBeta,
+                Some("old_alpha") => GameVersionStability::OldAlpha,
+                _ => GameVersionStability::Release,
+            },
+            java_version: java_requirement(value.java_version.map_or(8, |it| it.major_version)),
+            main_class: value.main_class.ok_or_else(|| missing("main class"))?,
+            libraries,
+            java_arguments,
+            game_arguments,
+            logging: value
+                .logging
+                .and_then(|it| it.client)
+                .map(|it| GameVersionLogging {
+                    argument: it.argument,
+                    file: LibraryDownloadable {
+                        path: format!("log_configs/{}", it.file.id),
+                        checksum: it.file.sha1,
+                        size: it.file.size,
+                        url: it.file.url,
+                        source: LibrarySource::Verified,
+                    },
+                }),
+            client: game_jar("client", client),
+            server: downloads.server.map(|it| game_jar("server", it)),
+            asset_index: GameVersionAssetIndex {
+                file: LibraryDownloadable {
+                    path: format!("indexes/{}.json", asset_index.id),
+                    checksum: asset_index.sha1,
+                    size: asset_index.size,
+                    url: asset_index.url,
+                    source: LibrarySource::Verified,
+                },
+                id: asset_index.id,
+                total_size: asset_index.total_size,
+            },
+            id,
+        })
+    }
+}
+
+impl TryFrom<GameVersion> for VanillaVersion {
+    type Error = VanillaError;
+
+    /// Converts a version to a standalone version JSON. Versions with only plain game arguments
+    /// and the default Java arguments are written in the format from before 17w43a, which older
+    /// installers expect.
+    fn try_from(value: GameVersion) -> Result<Self> {
+        let mut libraries = vec![];
+        for library in &value.libraries {
+            let (when, library) = match library {
+                MaybeConditional::Unconditional(then) => (&Condition::Always, then),
+                MaybeConditional::Conditional { when, then } => (when, then),
+            };
+
+            for option in alternatives(when)? {
+                libraries.push(match &library.extract {
+                    None => VanillaLibrary {
+                        name: library.name.clone(),
+                        url: None,
+                        sha1: None,
+                        size: None,
+                        downloads: Some(VanillaLibraryDownloads {
+                            artifact: Some(artifact(&library.file)),
+                            classifiers: None,
+                        }),
+                        natives: None,
+                        extract: None,
+                        rules: rules(&option),
+                    },
+                    // Only libraries with natives get extracted by the official launcher.
+                    Some(extract) => {
+                        let oses = match option.os {
+                            Some(os) => vec![os],
+                            None => vec![OS::Linux, OS::MacOS, OS::Windows],
+                        };
+                        let classifier = match option.os {
+                            Some(os) => format!("natives-{}", os_name(os)),
+                            None => "natives".to_string(),
+                        };
+
+                        VanillaLibrary {
+                            name: library.name.clone(),
+                            url: None,
+                            sha1: None,
+                            size: None,
+                            downloads: Some(VanillaLibraryDownloads {
+                                artifact: None,
+                                classifiers: Some(BTreeMap::from([(
+                                    classifier.clone(),
+                                    artifact(&library.file),
+                                )])),
+                            }),
+                            natives: Some(
+                                oses.into_iter()
+                                    .map(|os| (os_name(os), classifier.clone()))
+                                    .collect(),
+                            ),
+                            extract: Some(VanillaExtract {
+                                exclude: extract.exclude.clone(),
+                            }),
+                            rules: rules(&option),
+                        }
+                    }
+                });
+            }
+        }
+
+        let legacy = value.java_arguments == legacy_java_arguments()
+            && value
+                .game_arguments
+                .iter()
+                .all(|it| matches!(it, MaybeConditional::Unconditional(_)));
+
+        let (minecraft_arguments, arguments) = match legacy {
+            true => {
+                let values: Vec<_> = value
+                    .game_arguments
+                    .iter()
+                    .map(|it| it.value().as_str())
+                    .collect();
+                (Some(values.join(" ")), None)
+            }
+            false => (
+                None,
+                Some(VanillaArguments {
+                    game: arguments(&value.game_arguments)?,
+                    jvm: arguments(&value.java_arguments)?,
+                }),
+            ),
+        };
+
+        let major = value.java_version.comparators.first().map(|it| it.major);
+        let download = |file: &LibraryDownloadable| VanillaDownload {
+            sha1: file.checksum.clone(),
+            size: file.size,
+            url: file.url.clone(),
+        };
+
+        Ok(Self {
+            inherits_from: None,
+            stability: Some(stability_name(&value.stability).to_string()),
+            release_time: Some(value.release_date),
+            time: Some(value.release_date),
+            main_class: Some(value.main_class),
+            minecraft_arguments,
+            arguments,
+            libraries,
+            java_version: major.map(|major| VanillaJavaVersion {
+                component: java_component(major),
+                major_version: major,
+            }),
+            assets: Some(value.asset_index.id.clone()),
+            asset_index: Some(VanillaAssetIndex {
+                sha1: value.asset_index.file.checksum,
+                size: value.asset_index.file.size,
+                url: value.asset_index.file.url,
+                id: value.asset_index.id,
+                total_size: value.asset_index.total_size,
+            }),
+            downloads: Some(VanillaDownloads {
+                client: Some(download(&value.client)),
+                server: value.server.as_ref().map(download),
+            }),
+            jar: None,
+            logging: value.logging.map(|it| VanillaLogging {
+                client: Some(VanillaLoggingConfig {
+                    argument: it.argument,
+                    file: VanillaLoggingFile {
+                        id: it
+                            .file
+                            .path
+                            .rsplit('/')
+                            .next()
+                            .unwrap_or_default()
+                            .to_string(),
+                        sha1: it.file.checksum,
+                        size: it.file.size,
+                        url: it.file.url,
+                    },
+                    kind: Some("log4j2-xml".to_string()),
+                }),
+            }),
+            id: value.id,
+        })
+    }
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use crate::core::conditional::EvalContext;
+
+    use super::*;
+
+    const PARENT: &str = r#"{
+        "id": "1.20.4",
+        "type": "release",
+        "releaseTime": "2023-12-07T12:56:20+00:00",
+        "mainClass": "net.minecraft.client.main.Main",
+        "arguments": {
+            "game": ["--username", "${auth_player_name}", {
+                "rules": [{"action": "allow", "features": {"has_custom_resolution": true}}],
+                "value": ["--width", "${resolution_width}"]
+            }],
+            "jvm": [{
+                "rules": [{"action": "allow", "os": {"name": "osx"}}],
+                "value": "-XstartOnFirstThread"
+            }, "-cp", "${classpath}"]
+        },
+        "libraries": [
+            {
+                "name": "org.ow2.asm:asm:9.3",
+                "downloads": {"artifact": {
+                    "path": "org/ow2/asm/asm/9.3/asm-9.3.jar",
+                    "sha1": "8e6300ef51c1d801a7ed62d07cd221aca3a90640",
+                    "size": 122004,
+                    "url": "https://libraries.minecraft.net/org/ow2/asm/asm/9.3/asm-9.3.jar"
+                }}
+            },
+            {
+                "name": "org.lwjgl:lwjgl:3.3.2:natives-linux",
+                "downloads": {"artifact": {
+                    "path": "org/lwjgl/lwjgl/3.3.2/lwjgl-3.3.2-natives-linux.jar",
+                    "sha1": "0000000000000000000000000000000000000000",
+                    "size": 1,
+                    "url": "https://libraries.minecraft.net/org/lwjgl/lwjgl/3.3.2/lwjgl-3.3.2-natives-linux.jar"
+                }},
+                "rules": [{"action": "allow", "os": {"name": "linux"}}]
+            }
+        ],
+        "javaVersion": {"component": "java-runtime-gamma", "majorVersion": 17},
+        "assetIndex": {
+            "id": "12",
+            "sha1": "0000000000000000000000000000000000000000",
+            "size": 1,
+            "totalSize": 2,
+            "url": "https://piston-meta.mojang.com/v1/packages/0/12.json"
+        },
+        "downloads": {"client": {
+            "sha1": "0000000000000000000000000000000000000000",
+            "size": 1,
+            "url": "https://piston-data.mojang.com/v1/objects/0/client.jar"
+        }}
+    }"#;
+
+    const CHILD: &str = r#"{
+        "id": "fabric-loader-0.15.3-1.20.4",
+        "inheritsFrom": "1.20.4",
+        "releaseTime": "2023-12-21T20:23:01+0000",
+        "type": "release",
+        "mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient",
+        "arguments": {"game": [], "jvm": ["-DFabricMcEmu= net.minecraft.client.main.Main "]},
+        "libraries": [
+            {
+                "name": "org.ow2.asm:asm:9.6",
+                "url": "https://maven.fabricmc.net/",
+                "sha1": "aa205cf0a06dbd8e04ece91c0b37c3f5d567546a",
+                "size": 123598
+            },
+            {"name": "net.fabricmc:fabric-loader:0.15.3", "url": "https://maven.fabricmc.net/"}
+        ]
+    }"#;
+
+    fn context(os: OS, features: &[&str]) -> EvalContext {
+        EvalContext {
+            os,
+            arch: Arch::X86_64,
+            features: features.iter().map(|it| it.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn inherit() {
+        let parent: VanillaVersion = serde_json::from_str(PARENT).unwrap();
+        let child: VanillaVersion = serde_json::from_str(CHILD).unwrap();
+        let version = GameVersion::try_from(child.inherit(parent)).unwrap();
+
+        assert_eq!(version.id, "fabric-loader-0.15.3-1.20.4");
+        assert_eq!(version.java_version, java_requirement(17));
+        assert_eq!(
+            version.client.path,
+            "com/mojang/minecraft/1.20.4/minecraft-1.20.4-client.jar"
+        );
+
+        let resolved = version.resolve(&context(OS::Linux, &[]));
+        assert_eq!(
+            resolved.main_class,
+            "net.fabricmc.loader.impl.launch.knot.KnotClient"
+        );
+
+        let paths: Vec<_> = resolved
+            .libraries
+            .iter()
+            .map(|it| it.file.path.as_str())
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                "org/ow2/asm/asm/9.6/asm-9.6.jar",
+                "net/fabricmc/fabric-loader/0.15.3/fabric-loader-0.15.3.jar",
+                "org/lwjgl/lwjgl/3.3.2/lwjgl-3.3.2-natives-linux.jar",
+            ]
+        );
+        assert_eq!(
+            resolved.libraries[0].file.url.as_str(),
+            "https://maven.fabricmc.net/org/ow2/asm/asm/9.6/asm-9.6.jar"
+        );
+        assert_eq!(resolved.libraries[0].file.size, 123598);
+        assert_eq!(resolved.libraries[0].file.source, LibrarySource::Verified);
+        assert_eq!(resolved.libraries[1].file.source, LibrarySource::Unverified);
+
+        assert_eq!(
+            resolved.java_arguments,
+            [
+                "-cp",
+                "${classpath}",
+                "-DFabricMcEmu= net.minecraft.client.main.Main "
+            ]
+        );
+        assert_eq!(
+            resolved.game_arguments,
+            ["--username", "${auth_player_name}"]
+        );
+    }
+
+    #[test]
+    fn unsafe_paths() {
+        for path in [
+            "../../escape.jar",
+            "/tmp/escape.jar",
+            "org/../../escape.jar",
+        ] {
+            let json = PARENT.replace("org/ow2/asm/asm/9.3/asm-9.3.jar\",", &format!("{path}\","));
+            let version: VanillaVersion = serde_json::from_str(&json).unwrap();
+            assert!(matches!(
+                GameVersion::try_from(version),
+                Err(VanillaError::UnsafePath(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn rules() {
+        let rule = |action, os: Option<&str>| VanillaRule {
+            action,
+            os: os.map(|it| VanillaRuleOs {
+                name: Some(it.to_string()),
+                ..Default::default()
+            }),
+            features: None,
+        };
+
+        let rules = [
+            rule(VanillaRuleAction::Allow, None),
+            rule(VanillaRuleAction::Disallow, Some("osx")),
+        ];
+        assert_eq!(
+            condition(&rules),
+            Condition::Not(Box::new(Condition::OS(OS::MacOS)))
+        );
+        assert_eq!(condition(&[]), Condition::Never);
+
+        let written = alternatives(&condition(&rules)).unwrap();
+        assert_eq!(written.len(), 1);
+        assert_eq!(condition(&written[0].rules()), condition(&rules));
+
+        let either = Condition::Or(vec![Condition::OS(OS::Linux), Condition::OS(OS::Windows)]);
+        assert_eq!(alternatives(&either).unwrap().len(), 2);
+
+        let xor = Condition::Xor(vec![Condition::OS(OS::Linux), Condition::OS(OS::Windows)]);
+        assert!(alternatives(&xor).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut version =
+            GameVersion::try_from(serde_json::from_str::<VanillaVersion>(PARENT).unwrap()).unwrap();
+        version.libraries.push(MaybeConditional::Conditional {
+            when: Condition::OS(OS::MacOS),
+            then: Library {
+                name: "org.lwjgl.lwjgl:lwjgl-platform:2.9.4".parse().unwrap(),
+                file: LibraryDownloadable {
+                    path:
+                        "org/lwjgl/lwjgl/lwjgl-platform/2.9.4/lwjgl-platform-2.9.4-natives-osx.jar"
+                            .to_string(),
+                    checksum: "0".repeat(40),
+                    size: 1,
+                    url: Url::parse(
+                        "https://libraries.minecraft.net/lwjgl-platform-2.9.4-natives-osx.jar",
+                    )
+                    .unwrap(),
+                    source: LibrarySource::Verified,
+                },
+                extract: Some(LibraryExtract {
+                    exclude: vec!["META-INF/".to_string()],
+                }),
+            },
+        });
+
+        let written =
+            serde_json::to_string(&VanillaVersion::try_from(version.clone()).unwrap()).unwrap();
+        let read = GameVersion::try_from(serde_json::from_str::<VanillaVersion>(&written).unwrap())
+            .unwrap();
+
+        for context in [
+            context(OS::MacOS, &[]),
+            context(OS::Linux, &["has_custom_resolution"]),
+            context(OS::Windows, &[]),
+        ] {
+            let expected = serde_json::to_value(version.clone().resolve(&context)).unwrap();
+            let actual = serde_json::to_value(read.clone().resolve(&context)).unwrap();
+            assert_eq!(expected, actual);
+        }
+
+        assert!(written.contains(r#""value":["--width","${resolution_width}"]"#));
+        assert!(written.contains(r#""natives":{"osx":"natives-osx"}"#));
+    }
+
+    #[test]
+    fn legacy() {
+        let mut version =
+            GameVersion::try_from(serde_json::from_str::<VanillaVersion>(PARENT).unwrap()).unwrap();
+        version.java_arguments = legacy_java_arguments();
+        version.game_arguments = ["--username", "${auth_player_name}"]
+            .map(|it| MaybeConditional::Unconditional(it.to_string()))
+            .to_vec();
+
+        let written = VanillaVersion::try_from(version).unwrap();
+        assert!(written.arguments.is_none());
+        assert_eq!(
+            written.minecraft_arguments.as_deref(),
+            Some("--username ${auth_player_name}")
+        );
+
+        let read = GameVersion::try_from(written).unwrap();
+        assert_eq!(read.java_arguments, legacy_java_arguments());
+    }
+}
@@ -0,0 +1,272 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Installation of game assets into a hash-addressed object store that is shared by all game
+//! versions. The store (`assets` in the data directory) is laid out the same way as Mojang's:
+//!
+//! - `indexes/{id}.json` - The asset indexes, as read by the game.
+//! - `objects/{hash[0..2]}/{hash}` - The assets themselves, named by their SHA1 checksum.
+//! - `virtual/{id}/{name}` - The assets of legacy indexes, named by their original names.
+//! - `log_configs/{id}` - The logging configs of versions, which make the game log XML events.
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use futures_util::{StreamExt, TryStreamExt, stream};
+use tokio::fs;
+use url::Url;
+
+use data::core::assets::{Asset, AssetIndex};
+use data::core::game::{GameVersionAssetIndex, GameVersionLogging, ResolvedGameVersion};
+use net::Client;
+use utils::directories;
+
+use super::download::DownloadOptions;
+use super::{Error, Result, download, meta};
+
+/// Where asset objects are downloaded from.
+const RESOURCES_URL: &str = "https://resources.download.minecraft.net/";
+
+/// A hash-addressed store of game assets.
+#[derive(Clone, Debug)]
+pub struct AssetStore {
+    root: PathBuf,
+    downloads: DownloadOptions,
+}
+
+impl Default for AssetStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AssetStore {
+    /// Opens the default asset store in the data directory.
+    pub fn new() -> Self {
+        Self::at(directories::DATA.join("assets"))
+    }
+
+    /// Opens an asset store at a custom location.
+    pub fn at(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            downloads: DownloadOptions::default(),
+        }
+    }
+
+    /// Downloads files with the given options, e.g. the ones from the settings.
+    pub fn with_downloads(mut self, downloads: DownloadOptions) -> Self {
+        self.downloads = downloads;
+        self
+    }
+
+    /// The root directory of the store.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The path of an asset object in the store.
+    pub fn object(&self, asset: &Asset) -> PathBuf {
+        self.root.join("objects").join(asset.path())
+    }
+
+    /// Installs an asset index along with all of its objects. Objects that are already in the store
+    /// are skipped, and objects of legacy indexes are also laid out by their names in `virtual`.
+    #[tracing::instrument(name = "AssetStore::install", skip_all, fields(id = %reference.id))]
+    pub async fn install(
+        &self,
+        client: &Client,
+        base: &Url,
+        reference: &GameVersionAssetIndex,
+    ) -> Result<AssetIndex> {
+        // The game reads the index itself, so it is kept in Mojang's original format
+        let path = self.root.join(&reference.file.path);
+        let file = &reference.file;
+        let url = self.downloads.mirror(&file.url);
+        download::file(client, &url, &path, &file.checksum, file.size).await?;
+
+        let index = meta::asset_index(client, base, &reference.id).await?;
+        self.install_objects(client, &index).await?;
+
+        if index.is_virtual {
+            let target = self.root.join("virtual").join(&reference.id);
+            self.materialize(&index, &target).await?;
+        }
+
+        Ok(index)
+    }
+
+    /// Installs the logging config of a version, and returns its path.
+    pub async fn install_logging(
+        &self,
+        client: &Client,
+        logging: &GameVersionLogging,
+    ) -> Result<PathBuf> {
+        let file = &logging.file;
+        let path = self.root.join(&file.path);
+        let url = self.downloads.mirror(&file.url);
+        download::file(client, &url, &path, &file.checksum, file.size).await?;
+        Ok(path)
+    }
+
+    /// Installs everything that a version needs from the store to launch in an instance (by its
+    /// game directory): its asset index with all of its objects, and its logging config if it has
+    /// one. Very old versions get their assets copied into the instance as well.
+    #[tracing::instrument(name = "AssetStore::install_version", skip_all, fields(id = %version.id))]
+    pub async fn install_version(
+        &self,
+        client: &Client,
+        base: &Url,
+        version: &ResolvedGameVersion,
+        instance: &Path,
+    ) -> Result<AssetIndex> {
+        let index = self.install(client, base, &version.asset_index).await?;
+        if let Some(logging) = &version.logging {
+            self.install_logging(client, logging).await?;
+        }
+
+        self.map_to_resources(&index, instance).await?;
+        Ok(index)
+    }
+
+    /// Downloads all objects of an index that aren't in the store yet.
+    async fn install_objects(&self, client: &Client, index: &AssetIndex) -> Result<()> {
+        let base = &Url::parse(RESOURCES_URL)?;
+
+        // Many assets share the same contents, but each object only needs to be downloaded once
+        let objects: HashMap<&str, &Asset> = index
+            .objects
+            .values()
+            .map(|it| (it.hash.as_str(), it))
+            .collect();
+
+        let downloaded = stream::iter(objects.into_values())
+            .map(|asset| async move {
+                let url = self.downloads.mirror(&base.join(&asset.path())?);
+                let path = self.object(asset);
+                download::file(client, &url, &path, &asset.hash, asset.size).await
+            })
+            .buffer_unordered(self.downloads.concurrency)
+            .try_fold(0, |count, it| async move { Ok(count + usize::from(it)) })
+            .await?;
+
+        tracing::info!("Downloaded {downloaded} new asset objects");
+        Ok(())
+    }
+
+    /// Lays out the objects of an index by their names in the target directory. Objects are hard
+    /// linked when possible, and copied otherwise.
+    async fn materialize(&self, index: &AssetIndex, target: &Path) -> Result<()> {
+        for (name, asset) in &index.objects {
+            let path = named(target, name).ok_or_else(|| Error::UnsafeAssetName(name.clone()))?;
+            if download::is_installed(&path, asset.size).await {
+                continue;
+            }
+
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+
+            let _ = fs::remove_file(&path).await;
+            let object = self.object(asset);
+            if fs::hard_link(&object, &path).await.is_err() {
+                fs::copy(&object, &path).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copies the assets of a very old index (with `map_to_resources`) into the `resources`
+    /// directory of an instance, where such versions look for them. Does nothing for other indexes.
+    pub async fn map_to_resources(&self, index: &AssetIndex, instance: &Path) -> Result<()> {
+        if index.map_to_resources {
+            self.materialize(index, &instance.join("resources")).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The path of an asset by its name in a directory. Names come from the index, so names that
+/// would end up outside of the directory are rejected.
+fn named(target: &Path, name: &str) -> Option<PathBuf> {
+    let relative = Path::new(name);
+    let safe = !name.is_empty()
+        && relative
+            .components()
+            .all(|it| matches!(it, Component::Normal(_)));
+
+    safe.then(|| target.join(relative))
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn materialize() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let store = AssetStore::at(root.join("assets"));
+
+        let asset = Asset {
+            hash: "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed".to_string(),
+            size: 11,
+        };
+
+        let object = store.object(&asset);
+        fs::create_dir_all(object.parent().unwrap()).await.unwrap();
+        fs::write(&object, "hello world").await.unwrap();
+
+        let index = AssetIndex {
+            is_virtual: false,
+            map_to_resources: true,
+            objects: BTreeMap::from([
+                ("sound/a.ogg".to_string(), asset.clone()),
+                ("sound/b.ogg".to_string(), asset),
+            ]),
+        };
+
+        let instance = root.join("instance");
+        store.map_to_resources(&index, &instance).await.unwrap();
+
+        for name in ["sound/a.ogg", "sound/b.ogg"] {
+            let contents = fs::read_to_string(instance.join("resources").join(name)).await;
+            assert_eq!(contents.unwrap(), "hello world");
+        }
+
+        for name in ["../escaped.ogg", "/etc/escaped.ogg", ""] {
+            assert_eq!(named(&instance.join("resources"), name), None);
+        }
+
+        let index = AssetIndex {
+            objects: BTreeMap::from([(
+                "../escaped.ogg".to_string(),
+                index.objects["sound/a.ogg"].clone(),
+            )]),
+            ..index
+        };
+        let result = store.map_to_resources(&index, &instance).await;
+        assert!(matches!(result, Err(Error::UnsafeAssetName(_))));
+        assert!(!instance.join("escaped.ogg").exists());
+    }
+}
@@ -0,0 +1,313 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Downloading of files with known checksums into content stores.
+
+use std::path::{Path, PathBuf};
+
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use tokio::fs;
+use tokio::io::AsyncReadExt;
+use url::Url;
+
+use data::core::settings::{DEFAULT_DOWNLOAD_CONCURRENCY, EffectiveSettings, MirrorRule};
+use net::Client;
+
+use super::{Error, Result};
+
+/// How files are downloaded, as configured in the settings.
+#[derive(Clone, Debug)]
+pub struct DownloadOptions {
+    /// How many files are downloaded at the same time.
+    pub concurrency: usize,
+    /// Rules that redirect downloads, the first matching one wins.
+    pub mirrors: Vec<MirrorRule>,
+}
+
+/// The algorithm that a checksum was computed with.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Algorithm {
+    /// Used by Mojang for game files, libraries and assets.
+    Sha1,
+    /// Used by Java providers for their builds.
+    Sha256,
+    /// Used by Modrinth for the files of modpacks.
+    Sha512,
+}
+
+// === impl ===
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_DOWNLOAD_CONCURRENCY,
+            mirrors: Vec::new(),
+        }
+    }
+}
+
+impl DownloadOptions {
+    /// Redirects a URL according to the first mirror rule that matches it, if any.
+    pub fn mirror(&self, url: &Url) -> Url {
+        self.mirrors
+            .iter()
+            .find_map(|it| it.apply(url))
+            .unwrap_or_else(|| url.clone())
+    }
+}
+
+/// Computes the checksum of a file, as a lowercase hex string.
+async fn digest<D: Digest>(path: &Path) -> Result<String> {
+    let mut file = fs::File::open(path).await?;
+    let mut hasher = D::new();
+    let mut buffer = vec![0; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hex(&hasher.finalize()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|it| format!("{it:02x}")).collect()
+}
+
+/// Computes the SHA1 checksum of a file, as a lowercase hex string.
+pub async fn sha1(path: &Path) -> Result<String> {
+    digest::<Sha1>(path).await
+}
+
+/// Computes the SHA256 checksum of a file, as a lowercase hex string.
+pub async fn sha256(path: &Path) -> Result<String> {
+    digest::<Sha256>(path).await
+}
+
+/// Computes the SHA512 checksum of a file, as a lowercase hex string.
+pub async fn sha512(path: &Path) -> Result<String> {
+    digest::<Sha512>(path).await
+}
+
+/// Whether a file exists with the expected size. Files in the stores are only ever moved in place
+/// after their checksum has been verified, so this is enough to tell that a file is installed.
+pub async fn is_installed(path: &Path, size: u64) -> bool {
+    fs::metadata(path)
+        .await
+        .is_ok_and(|it| it.is_file() && it.len() == size)
+}
+
+/// Whether a file with the checksum is installed. A size of zero means that the size is unknown
+/// (e.g. for libraries of mod loaders), so the checksum of the existing file is compared instead.
+async fn is_verified(path: &Path, checksum: &str, size: u64) -> bool {
+    match size {
+        0 => sha1(path)
+            .await
+            .is_ok_and(|it| it.eq_ignore_ascii_case(checksum)),
+        size => is_installed(path, size).await,
+    }
+}
+
+/// The path that a file is downloaded to before it is verified and moved in place.
+fn partial(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+/// Downloads a file to the path and verifies its SHA1 checksum. Files that are already installed
+/// are skipped. Returns whether the file was downloaded.
+pub async fn file(
+    client: &Client,
+    url: &Url,
+    path: &Path,
+    checksum: &str,
+    size: u64,
+) -> Result<bool> {
+    if is_verified(path, checksum, size).await {
+        return Ok(false);
+    }
+
+    verified(client, url, path, checksum, Algorithm::Sha1).await?;
+    Ok(true)
+}
+
+/// Downloads a file next to the path, and returns where it was downloaded to.
+async fn download_partial(client: &Client, url: &Url, path: &Path) -> Result<PathBuf> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    let partial = partial(path);
+    let mut file = fs::File::create(&partial).await?;
+    let result = client.download(url.clone(), &mut file).await;
+    drop(file);
+
+    match result {
+        Ok(()) => Ok(partial),
+        Err(err) => {
+            let _ = fs::remove_file(&partial).await;
+            Err(err.into())
+        }
+    }
+}
+
+/// Downloads a file to the path and verifies its checksum, replacing whatever was there before.
+/// Nothing is written to the path if the download fails or the checksum doesn't match.
+pub async fn verified(
+    client: &Client,
+    url: &Url,
+    path: &Path,
+    checksum: &str,
+    algorithm: Algorithm,
+) -> Result<()> {
+    let partial = download_partial(client, url, path).await?;
+    let actual = match algorithm {
+        Algorithm::Sha1 => sha1(&partial).await?,
+        Algorithm::Sha256 => sha256(&partial).await?,
+        Algorithm::Sha512 => sha512(&partial).await?,
+    };
+
+    if !actual.eq_ignore_ascii_case(checksum) {
+        let _ = fs::remove_file(&partial).await;
+        return Err(Error::ChecksumMismatch(
+            url.to_string(),
+            checksum.to_string(),
+            actual,
+        ));
+    }
+
+    fs::rename(&partial, path).await?;
+    tracing::debug!("Downloaded {url} to {}", path.display());
+    Ok(())
+}
+
+/// Downloads a file whose checksum is not known to the path and verifies its size, replacing
+/// whatever was there before. Nothing is written to the path if the download fails or the size
+/// doesn't match.
+pub async fn sized(client: &Client, url: &Url, path: &Path, size: u64) -> Result<()> {
+    let partial = download_partial(client, url, path).await?;
+    let actual = fs::metadata(&partial).await?.len();
+
+    if actual != size {
+        let _ = fs::remove_file(&partial).await;
+        return Err(Error::SizeMismatch(url.clone(), size, actual));
+    }
+
+    fs::rename(&partial, path).await?;
+    tracing::debug!("Downloaded {url} to {}", path.display());
+    Ok(())
+}
+
+/// Downloads a file whose checksum is not known to the path, unless it exists already. Nothing is
+/// written to the path if the download fails. Returns whether the file was downloaded.
+pub async fn unverified(client: &Client, url: &Url, path: &Path) -> Result<bool> {
+    if fs::try_exists(path).await? {
+        return Ok(false);
+    }
+
+    let partial = download_partial(client, url, path).await?;
+    fs::rename(&partial, path).await?;
+    tracing::debug!(
+        "Downloaded {url} to {} without verifying it",
+        path.display()
+    );
+    Ok(true)
+}
+
+// === conversion ===
+
+impl From<&EffectiveSettings> for DownloadOptions {
+    fn from(value: &EffectiveSettings) -> Self {
+        Self {
+            // Nothing would ever be downloaded with a concurrency of zero
+            concurrency: value.download_concurrency.value.max(1),
+            mirrors: value.mirrors.value.clone(),
+        }
+    }
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn checksum() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("hello.txt");
+        fs::write(&path, "hello world").await.unwrap();
+
+        assert_eq!(
+            sha1(&path).await.unwrap(),
+            "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed"
+        );
+        assert_eq!(
+            sha256(&path).await.unwrap(),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+        assert_eq!(
+            sha512(&path).await.unwrap(),
+            "309ecc489c12d6eb4cc40f50c902f2b4d0ed77ee511a7c7a9bcd3ca86d4cd86f989dd35bc5ff499670da34255b45b0cfd830e81f605dcf7dc5542e93ae9cd76f"
+        );
+        assert!(is_installed(&path, 11).await);
+        assert!(!is_installed(&path, 12).await);
+        assert_eq!(partial(&path).extension().unwrap(), "part");
+    }
+
+    #[tokio::test]
+    async fn unknown_size() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("hello.txt");
+        let checksum = "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed";
+
+        // Files of unknown size are installed if their checksum matches
+        assert!(!is_verified(&path, checksum, 0).await);
+        fs::write(&path, "hello world").await.unwrap();
+        assert!(is_verified(&path, checksum, 0).await);
+        assert!(is_verified(&path, checksum, 11).await);
+
+        // ...and downloaded again otherwise, even if they are empty
+        fs::write(&path, "").await.unwrap();
+        assert!(!is_verified(&path, checksum, 0).await);
+    }
+
+    #[test]
+    fn mirrors() {
+        let options = DownloadOptions {
+            mirrors: vec![MirrorRule {
+                from: "https://libraries.minecraft.net/".to_string(),
+                to: "https://mirror.example.com/libraries/".to_string(),
+            }],
+            ..Default::default()
+        };
+
+        let url = Url::parse("https://libraries.minecraft.net/com/mojang/brigadier.jar").unwrap();
+        assert_eq!(
+            options.mirror(&url).as_str(),
+            "https://mirror.example.com/libraries/com/mojang/brigadier.jar"
+        );
+
+        let url = Url::parse("https://maven.fabricmc.net/net/fabricmc/fabric-loader.jar").unwrap();
+        assert_eq!(options.mirror(&url), url);
+    }
+}
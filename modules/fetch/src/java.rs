@@ -0,0 +1,628 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Management of the Java runtimes that the launcher installs by itself.
+
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+use std::path::{Path, PathBuf};
+
+use semver::{Version, VersionReq};
+use tokio::fs;
+use url::Url;
+
+use data::core::java::{Environment, JavaBuild, JavaEdition, JavaInfo, JavaProvider};
+use data::core::settings::JavaPolicy;
+use net::Client;
+use persistence::DirectoryRegistry;
+use utils::archive::{self, ExtractOptions, Format};
+use utils::directories;
+use utils::platforms::{CURRENT_ARCH, CURRENT_OS};
+
+use super::download::{self, Algorithm};
+use super::{Error, Result, meta};
+
+pub mod discovery;
+
+/// The file in each installation's directory that its [JavaInfo] is stored in.
+const INFO_FILE: &str = "Java.toml";
+
+/// The oldest major version of Java that the game runs on.
+const MIN_MAJOR: u64 = 8;
+
+/// The ID that a build is installed under, e.g. `zulu-21.0.5-jre`.
+pub fn id(build: &JavaBuild) -> String {
+    format!("{}-{}-{}", build.provider, build.version, build.edition).to_lowercase()
+}
+
+/// The outcome of [JavaManager::resolve].
+#[derive(Clone, Debug)]
+pub enum JavaSelection {
+    /// An installed runtime satisfies the requirement. Contains its ID.
+    Installed(String),
+    /// No installed runtime satisfies the requirement. Contains the build that should be installed.
+    Missing(Box<JavaBuild>),
+}
+
+/// A newer build of a managed runtime, found by [JavaManager::check_updates].
+#[derive(Clone, Debug)]
+pub struct JavaUpdate {
+    /// The ID of the installed runtime.
+    pub id: String,
+    /// The version of the installed runtime.
+    pub current: Version,
+    /// The newer build that is available.
+    pub build: JavaBuild,
+}
+
+/// Whether a build is an update for an installed runtime: the same major version, edition and
+/// environment, but a newer release.
+pub fn is_update(info: &JavaInfo, build: &JavaBuild) -> bool {
+    info.version.major == build.version.major
+        && info.edition == build.edition
+        && info.environment == build.environment
+        && info.version < build.version
+}
+
+/// The lowest major version of Java that satisfies a requirement, e.g. 8 for `=8` and 17 for
+/// `>=17`. This is the version that is most likely to work, as newer versions can break old mods.
+pub fn required_major(requirement: &VersionReq) -> Option<u64> {
+    // upper bounds (e.g. `<17`) are satisfied by anything below them, so the search starts at the
+    // oldest version that the game runs on
+    let highest = requirement
+        .comparators
+        .iter()
+        .map(|it| it.major)
+        .max()
+        .unwrap_or(MIN_MAJOR)
+        .max(MIN_MAJOR);
+
+    (MIN_MAJOR..highest + 32).find(|major| requirement.matches(&Version::new(*major, 0, 0)))
+}
+
+/// Picks the best runtime for a requirement out of a list of runtimes. Only runtimes for the
+/// current OS that satisfy the requirement are considered. Among those, the required major version
+/// is preferred, then the current architecture (others might run under emulation), then JREs
+/// (which are what the launcher installs), and finally newer versions.
+pub fn select<'a>(
+    runtimes: impl IntoIterator<Item = (&'a str, &'a JavaInfo)>,
+    requirement: &VersionReq,
+) -> Option<&'a str> {
+    let major = required_major(requirement);
+
+    runtimes
+        .into_iter()
+        .filter(|(_, info)| info.environment.os == CURRENT_OS)
+        .filter(|(_, info)| requirement.matches(&info.version))
+        .max_by_key(|(id, info)| {
+            (
+                Some(info.version.major) == major,
+                info.environment.arch == CURRENT_ARCH,
+                info.edition == JavaEdition::JRE,
+                &info.version,
+                // ties are broken by the ID, so that the choice is stable
+                std::cmp::Reverse(*id),
+            )
+        })
+        .map(|(id, _)| id)
+}
+
+/// Computes the size of a directory on disk, without following symbolic links.
+async fn size(directory: &Path) -> Result<u64> {
+    let mut total = 0;
+    let mut pending = vec![directory.to_path_buf()];
+
+    while let Some(directory) = pending.pop() {
+        let mut stream = fs::read_dir(&directory).await?;
+        while let Some(entry) = stream.next_entry().await? {
+            let metadata = fs::symlink_metadata(entry.path()).await?;
+            if metadata.is_dir() {
+                pending.push(entry.path());
+            } else {
+                total += metadata.len();
+            }
+        }
+    }
+
+    Ok(total)
+}
+
+/// The ID that an external runtime is listed under, e.g. `external:/usr/lib/jvm/java-17-openjdk`.
+fn external_id(home: &Path) -> String {
+    format!("external:{}", home.display())
+}
+
+/// Keeps track of the Java runtimes installed by the launcher. Each runtime is extracted into its
+/// own directory, alongside a file with its [JavaInfo].
+///
+/// Runtimes that are already installed on the system can also be listed alongside them (see
+/// [JavaManager::discover]). Those are read-only, and can't be uninstalled.
+pub struct JavaManager {
+    registry: DirectoryRegistry<JavaInfo>,
+    external: BTreeMap<String, JavaInfo>,
+}
+
+impl JavaManager {
+    /// Opens the default Java directory in the data directory.
+    pub async fn new() -> Result<Self> {
+        Self::at(directories::DATA.join("Java")).await
+    }
+
+    /// Opens a Java directory at a custom location.
+    pub async fn at(root: impl Into<PathBuf>) -> Result<Self> {
+        let registry = DirectoryRegistry::at(root.into(), INFO_FILE).await?;
+        Ok(Self {
+            registry,
+            external: BTreeMap::new(),
+        })
+    }
+
+    /// Finds the runtimes that are installed on the system (see [discovery::discover]), and lists
+    /// them alongside the managed ones, replacing the ones found previously.
+    pub async fn discover(&mut self) {
+        let base = self.registry.base().to_path_buf();
+        let base = fs::canonicalize(&base).await.unwrap_or(base);
+
+        self.external = discovery::discover()
+            .await
+            .into_iter()
+            .filter(|(home, _)| !home.starts_with(&base))
+            .map(|(home, info)| (external_id(&home), info))
+            .collect();
+    }
+
+    /// Iterates over all runtimes, both managed and external, in no particular order.
+    fn iter(&self) -> impl Iterator<Item = (&str, &JavaInfo)> {
+        let external = self.external.iter().map(|(id, info)| (id.as_str(), info));
+        self.registry.iter().chain(external)
+    }
+
+    /// Lists the installed runtimes, both managed and external, sorted by their ID.
+    pub fn list(&self) -> Vec<(&str, &JavaInfo)> {
+        let mut list: Vec<_> = self.iter().collect();
+        list.sort_by_key(|(id, _)| *id);
+        list
+    }
+
+    /// Gets an installed runtime.
+    pub fn get(&self, id: &str) -> Option<&JavaInfo> {
+        self.registry.get(id).or_else(|| self.external.get(id))
+    }
+
+    /// Whether a runtime was installed by the launcher (as opposed to being found on the system).
+    pub fn is_managed(&self, id: &str) -> bool {
+        self.registry.get(id).is_some()
+    }
+
+    /// The root directory of an installed runtime (its `JAVA_HOME`).
+    pub fn home(&self, id: &str) -> PathBuf {
+        match self.external.get(id).and_then(|it| it.home.clone()) {
+            Some(home) => home,
+            None => self.registry.path(id),
+        }
+    }
+
+    /// The path to the executable of an installed runtime.
+    pub fn executable(&self, id: &str) -> Option<PathBuf> {
+        let info = self.get(id)?;
+        Some(self.home(id).join(&info.executable))
+    }
+
+    /// Picks the best installed runtime for a requirement (see [select]).
+    pub fn select(&self, requirement: &VersionReq) -> Option<&str> {
+        select(self.iter(), requirement)
+    }
+
+    /// Picks the best installed runtime for a requirement (see [select]). If there is none, gets
+    /// the build that should be installed for it from the meta server, unless the policy only
+    /// allows installed runtimes.
+    pub async fn resolve(
+        &self,
+        client: &Client,
+        base: &Url,
+        requirement: &VersionReq,
+        policy: JavaPolicy,
+    ) -> Result<JavaSelection> {
+        if let Some(id) = self.select(requirement) {
+            return Ok(JavaSelection::Installed(id.to_string()));
+        }
+
+        if policy == JavaPolicy::InstalledOnly {
+            return Err(Error::UnsatisfiableJava(requirement.clone()));
+        }
+
+        let major = required_major(requirement)
+            .ok_or_else(|| Error::UnsatisfiableJava(requirement.clone()))?;
+        let build = meta::java_build(client, base, major).await?;
+
+        // the meta server only has builds for the current environment, but check just in case
+        let environment = Environment {
+            os: CURRENT_OS,
+            arch: CURRENT_ARCH,
+        };
+        if build.environment != environment || !requirement.matches(&build.version) {
+            return Err(Error::UnsatisfiableJava(requirement.clone()));
+        }
+
+        Ok(JavaSelection::Missing(Box::new(build)))
+    }
+
+    /// Downloads, verifies and installs the build for a major version of Java from the meta
+    /// server. Returns the ID that it was installed under.
+    pub async fn install_version(
+        &mut self,
+        client: &Client,
+        base: &Url,
+        major: u64,
+    ) -> Result<String> {
+        let build = meta::java_build(client, base, major).await?;
+        self.install(client, &build).await
+    }
+
+    /// Downloads, verifies and installs a build. Returns the ID that it was installed under.
+    /// Installing a build that is already installed replaces it, which repairs a broken
+    /// installation.
+    #[tracing::instrument(name = "JavaManager::install", skip_all, fields(name = build.name))]
+    pub async fn install(&mut self, client: &Client, build: &JavaBuild) -> Result<String> {
+        let archive = directories::CACHE.join("java").join(&build.name);
+        download::verified(
+            client,
+            &build.download,
+            &archive,
+            &build.checksum,
+            Algorithm::Sha256,
+        )
+        .await?;
+
+        let result = self.install_archive(&archive, build).await;
+        let _ = fs::remove_file(&archive).await;
+        result
+    }
+
+    /// Installs a build from an archive that was already downloaded and verified. Returns the ID
+    /// that it was installed under.
+    pub async fn install_archive(&mut self, archive: &Path, build: &JavaBuild) -> Result<String> {
+        let id = id(build);
+        let home = self.home(&id);
+        let format = Format::detect(Path::new(&build.name))
+            .ok_or_else(|| archive::Error::UnsupportedFormat(build.name.clone().into()))?;
+
+        // all providers put everything in a single top-level directory named after the build
+        let options = ExtractOptions {
+            strip_top_level: match build.provider {
+                JavaProvider::Zulu => true,
+                JavaProvider::External(_) => false,
+            },
+            ..Default::default()
+        };
+
+        // the build is extracted next to its home, so that the runtime that it replaces (if any)
+        // keeps working until it is known to be complete
+        let staging = self.registry.base().join(format!(".{id}.part"));
+        if fs::try_exists(&staging).await? {
+            fs::remove_dir_all(&staging).await?;
+        }
+
+        tracing::info!("Installing {} into {}", build.name, home.display());
+        let result = async {
+            archive::extract_as(archive, &staging, format, options).await?;
+
+            let executable = staging.join(&build.executable);
+            if !fs::try_exists(&executable).await? {
+                return Err(Error::MissingExecutable(home.join(&build.executable)));
+            }
+
+            let info = JavaInfo {
+                provider: build.provider.clone(),
+                version: build.version.clone(),
+                edition: build.edition.clone(),
+                environment: build.environment.clone(),
+                executable: build.executable.clone(),
+                size: size(&staging).await?,
+                home: None,
+            };
+
+            if fs::try_exists(&home).await? {
+                fs::remove_dir_all(&home).await?;
+            }
+            fs::rename(&staging, &home).await?;
+
+            self.registry.insert(&id, info).await?;
+            Ok(())
+        }
+        .await;
+
+        if let Err(err) = result {
+            let _ = fs::remove_dir_all(&staging).await;
+            return Err(err);
+        }
+
+        Ok(id)
+    }
+
+    /// Compares every managed runtime against the build for its major version on the meta server,
+    /// and lists the ones that have a newer release available. Runtimes for other environments
+    /// (e.g. ones running under emulation) are skipped, as the meta server only has builds for the
+    /// current environment.
+    #[tracing::instrument(name = "JavaManager::check_updates", skip_all)]
+    pub async fn check_updates(&self, client: &Client, base: &Url) -> Result<Vec<JavaUpdate>> {
+        let environment = Environment {
+            os: CURRENT_OS,
+            arch: CURRENT_ARCH,
+        };
+
+        let mut builds = BTreeMap::new();
+        let mut updates = Vec::new();
+
+        for (id, info) in self.registry.iter() {
+            if info.environment != environment {
+                continue;
+            }
+
+            let major = info.version.major;
+            if let Entry::Vacant(entry) = builds.entry(major) {
+                // a major version might have been dropped from the meta server, which isn't fatal
+                let build = match meta::java_build(client, base, major).await {
+                    Ok(build) => Some(build),
+                    Err(err) => {
+                        tracing::warn!("Failed to check for updates to Java {major}: {err}");
+                        None
+                    }
+                };
+                entry.insert(build);
+            }
+
+            if let Some(build) = &builds[&major]
+                && is_update(info, build)
+            {
+                updates.push(JavaUpdate {
+                    id: id.to_string(),
+                    current: info.version.clone(),
+                    build: build.clone(),
+                });
+            }
+        }
+
+        updates.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(updates)
+    }
+
+    /// Installs an update, then retires the runtime that it replaces. Runtimes that are pinned
+    /// (e.g. selected explicitly by an instance) are kept, so that nothing that depends on them
+    /// breaks. Returns the ID of the new runtime.
+    #[tracing::instrument(name = "JavaManager::update", skip_all, fields(id = update.id))]
+    pub async fn update(
+        &mut self,
+        client: &Client,
+        update: &JavaUpdate,
+        pinned: &[&str],
+    ) -> Result<String> {
+        let id = self.install(client, &update.build).await?;
+
+        if pinned.contains(&update.id.as_str()) {
+            tracing::info!("Keeping {} as it is pinned", update.id);
+        } else if id != update.id {
+            self.uninstall(&update.id).await?;
+        }
+
+        Ok(id)
+    }
+
+    /// Removes an installed runtime from the disk. External runtimes can't be removed.
+    #[tracing::instrument(name = "JavaManager::uninstall", skip(self))]
+    pub async fn uninstall(&mut self, id: &str) -> Result<()> {
+        if self.external.contains_key(id) {
+            return Err(Error::ExternalJava(id.to_string()));
+        }
+
+        if self.get(id).is_none() {
+            return Err(Error::JavaNotInstalled(id.to_string()));
+        }
+
+        self.registry.delete(id).await?;
+        Ok(())
+    }
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+    use utils::archive::CreateOptions;
+
+    use super::*;
+
+    fn build() -> JavaBuild {
+        JavaBuild {
+            provider: JavaProvider::Zulu,
+            version: Version::new(21, 0, 5),
+            edition: JavaEdition::JRE,
+            environment: Environment {
+                os: CURRENT_OS,
+                arch: CURRENT_ARCH,
+            },
+            executable: "bin/java".to_string(),
+            download: Url::parse("https://cdn.azul.com/zulu/bin/zulu21-jre.zip").unwrap(),
+            name: "zulu21-jre.zip".to_string(),
+            size: 0,
+            checksum: String::new(),
+        }
+    }
+
+    fn info(version: Version, edition: JavaEdition, arch: platforms::Arch) -> JavaInfo {
+        JavaInfo {
+            provider: JavaProvider::Zulu,
+            version,
+            edition,
+            environment: Environment {
+                os: CURRENT_OS,
+                arch,
+            },
+            executable: "bin/java".to_string(),
+            size: 0,
+            home: None,
+        }
+    }
+
+    #[test]
+    fn required_major() {
+        let major = |it| super::required_major(&VersionReq::parse(it).unwrap());
+        assert_eq!(major("=8"), Some(8));
+        assert_eq!(major(">=17"), Some(17));
+        assert_eq!(major(">16"), Some(17));
+        assert_eq!(major("<17"), Some(8));
+        assert_eq!(major(">=11, <17"), Some(11));
+        assert_eq!(major("*"), Some(8));
+        assert_eq!(major(">=17, <17"), None);
+    }
+
+    #[test]
+    fn select() {
+        let other = match CURRENT_ARCH {
+            platforms::Arch::X86_64 => platforms::Arch::AArch64,
+            _ => platforms::Arch::X86_64,
+        };
+
+        let runtimes = [
+            (
+                "8-jre",
+                info(Version::new(8, 0, 432), JavaEdition::JRE, CURRENT_ARCH),
+            ),
+            (
+                "17-jdk",
+                info(Version::new(17, 0, 13), JavaEdition::JDK, CURRENT_ARCH),
+            ),
+            (
+                "17-jre-emulated",
+                info(Version::new(17, 0, 13), JavaEdition::JRE, other),
+            ),
+            (
+                "17-jre-old",
+                info(Version::new(17, 0, 2), JavaEdition::JRE, CURRENT_ARCH),
+            ),
+            (
+                "17-jre",
+                info(Version::new(17, 0, 13), JavaEdition::JRE, CURRENT_ARCH),
+            ),
+            (
+                "21-jre",
+                info(Version::new(21, 0, 5), JavaEdition::JRE, CURRENT_ARCH),
+            ),
+        ];
+        let select = |requirement| {
+            let runtimes = runtimes.iter().map(|(id, info)| (*id, info));
+            super::select(runtimes, &VersionReq::parse(requirement).unwrap())
+        };
+
+        assert_eq!(select("=8"), Some("8-jre"));
+        assert_eq!(select(">=17"), Some("17-jre"));
+        assert_eq!(select(">=21"), Some("21-jre"));
+        assert_eq!(select(">=25"), None);
+    }
+
+    #[test]
+    fn is_update() {
+        let installed = info(Version::new(21, 0, 3), JavaEdition::JRE, CURRENT_ARCH);
+        let mut build = build();
+        assert!(super::is_update(&installed, &build));
+
+        build.version = Version::new(21, 0, 3);
+        assert!(!super::is_update(&installed, &build));
+
+        build.version = Version::new(22, 0, 0);
+        assert!(!super::is_update(&installed, &build));
+
+        build.version = Version::new(21, 0, 5);
+        build.edition = JavaEdition::JDK;
+        assert!(!super::is_update(&installed, &build));
+    }
+
+    #[tokio::test]
+    async fn install() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let source = root.join("source");
+        std::fs::create_dir_all(source.join("zulu21-jre/bin")).unwrap();
+        std::fs::write(source.join("zulu21-jre/bin/java"), "java").unwrap();
+        std::fs::write(source.join("zulu21-jre/release"), "JAVA_VERSION=\"21.0.5\"").unwrap();
+
+        let archive = root.join("zulu21-jre.zip");
+        archive::create(&source, &archive, CreateOptions::default())
+            .await
+            .unwrap();
+
+        let mut manager = JavaManager::at(root.join("Java")).await.unwrap();
+        let id = manager.install_archive(&archive, &build()).await.unwrap();
+
+        assert_eq!(id, "zulu-21.0.5-jre");
+        assert_eq!(manager.list().len(), 1);
+        assert_eq!(manager.get(&id).unwrap().size, 25);
+        assert!(manager.executable(&id).unwrap().is_file());
+
+        // entries are read back from the disk
+        let mut manager = JavaManager::at(root.join("Java")).await.unwrap();
+        assert_eq!(manager.get(&id).unwrap().version, Version::new(21, 0, 5));
+
+        // a broken build doesn't replace the runtime that is already installed
+        let broken = root.join("broken");
+        std::fs::create_dir_all(broken.join("zulu21-jre")).unwrap();
+        std::fs::write(broken.join("zulu21-jre/release"), "JAVA_VERSION=\"21.0.5\"").unwrap();
+        let archive = root.join("broken.zip");
+        archive::create(&broken, &archive, CreateOptions::default())
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            manager.install_archive(&archive, &build()).await,
+            Err(Error::MissingExecutable(_))
+        ));
+        assert!(manager.executable(&id).unwrap().is_file());
+        assert!(!root.join("Java").join(format!(".{id}.part")).exists());
+
+        // only installed runtimes are picked without going to the meta server
+        let client = Client::new().await;
+        let base = Url::parse("http://localhost:0/").unwrap();
+        let selection = manager
+            .resolve(
+                &client,
+                &base,
+                &VersionReq::parse("^21").unwrap(),
+                JavaPolicy::InstalledOnly,
+            )
+            .await
+            .unwrap();
+        assert!(matches!(selection, JavaSelection::Installed(it) if it == id));
+        assert!(matches!(
+            manager
+                .resolve(
+                    &client,
+                    &base,
+                    &VersionReq::parse("^17").unwrap(),
+                    JavaPolicy::InstalledOnly
+                )
+                .await,
+            Err(Error::UnsatisfiableJava(_))
+        ));
+
+        manager.uninstall(&id).await.unwrap();
+        assert!(manager.list().is_empty());
+        assert!(!manager.home(&id).exists());
+        assert!(matches!(
+            manager.uninstall(&id).await,
+            Err(Error::JavaNotInstalled(_))
+        ));
+    }
+}
@@ -0,0 +1,265 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Discovery of Java runtimes that are already installed on the system, e.g. by a package manager
+//! or by SDKMAN. These are inspected through the `release` file found in the root of every
+//! runtime since Java 9 (and in most builds of Java 8).
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use platforms::{Arch, OS};
+use semver::Version;
+use tokio::fs;
+
+use data::core::java::{Environment, JavaEdition, JavaInfo, JavaProvider};
+use utils::platforms::{CURRENT_ARCH, CURRENT_OS};
+
+/// The name of the executable, relative to the root of a runtime.
+#[cfg(windows)]
+const EXECUTABLE: &str = "bin\\javaw.exe";
+#[cfg(not(windows))]
+const EXECUTABLE: &str = "bin/java";
+
+/// The name of the executable of the compiler, which is only in JDKs.
+#[cfg(windows)]
+const COMPILER: &str = "bin\\javac.exe";
+#[cfg(not(windows))]
+const COMPILER: &str = "bin/javac";
+
+/// Parses the contents of a `release` file, which are lines of `KEY="value"`.
+pub fn parse_release(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| {
+            (
+                key.trim().to_string(),
+                value.trim().trim_matches('"').to_string(),
+            )
+        })
+        .collect()
+}
+
+/// Parses a Java version. Versions before Java 9 look like `1.8.0_392`, and newer ones look like
+/// `17.0.9` or just `21`.
+pub fn parse_version(text: &str) -> Option<Version> {
+    let (text, update) = match text.strip_prefix("1.") {
+        Some(legacy) => {
+            let (version, update) = legacy.split_once('_').unwrap_or((legacy, "0"));
+            (version, Some(update))
+        }
+        None => (text, None),
+    };
+
+    let number = |it: &str| {
+        let digits: String = it.chars().take_while(char::is_ascii_digit).collect();
+        digits.parse::<u64>().ok()
+    };
+
+    let mut parts = text.split(['.', '+', '-']);
+    let major = number(parts.next()?)?;
+    let minor = parts.next().and_then(number).unwrap_or(0);
+    let patch = match update {
+        Some(update) => number(update)?,
+        None => parts.next().and_then(number).unwrap_or(0),
+    };
+
+    Some(Version::new(major, minor, patch))
+}
+
+fn parse_os(text: &str) -> Option<OS> {
+    match text.to_lowercase().as_str() {
+        "linux" => Some(OS::Linux),
+        "darwin" | "macos" | "mac os x" => Some(OS::MacOS),
+        "windows" => Some(OS::Windows),
+        _ => None,
+    }
+}
+
+fn parse_arch(text: &str) -> Option<Arch> {
+    match text.to_lowercase().as_str() {
+        "amd64" | "x64" => Some(Arch::X86_64),
+        "arm64" => Some(Arch::AArch64),
+        "i386" | "i586" | "i686" => Some(Arch::X86),
+        other => Arch::from_str(other).ok(),
+    }
+}
+
+/// Inspects a directory that might be the root of a runtime (a `JAVA_HOME`). Returns None if it
+/// isn't one, or if its `release` file can't be understood.
+pub async fn inspect(home: &Path) -> Option<JavaInfo> {
+    if !fs::try_exists(home.join(EXECUTABLE)).await.unwrap_or(false) {
+        return None;
+    }
+
+    let release = fs::read_to_string(home.join("release")).await.ok()?;
+    let release = parse_release(&release);
+    let version = parse_version(release.get("JAVA_VERSION")?)?;
+
+    let environment = Environment {
+        os: release
+            .get("OS_NAME")
+            .and_then(|it| parse_os(it))
+            .unwrap_or(CURRENT_OS),
+        arch: release
+            .get("OS_ARCH")
+            .and_then(|it| parse_arch(it))
+            .unwrap_or(CURRENT_ARCH),
+    };
+
+    let edition = match fs::try_exists(home.join(COMPILER)).await {
+        Ok(true) => JavaEdition::JDK,
+        _ => JavaEdition::JRE,
+    };
+
+    Some(JavaInfo {
+        provider: JavaProvider::External(release.get("IMPLEMENTOR").cloned()),
+        version,
+        edition,
+        environment,
+        executable: EXECUTABLE.to_string(),
+        size: 0,
+        home: Some(home.to_path_buf()),
+    })
+}
+
+/// Lists the subdirectories of a directory, or nothing if it doesn't exist.
+async fn children(directory: &Path) -> Vec<PathBuf> {
+    let mut output = Vec::new();
+    let Ok(mut stream) = fs::read_dir(directory).await else {
+        return output;
+    };
+
+    while let Ok(Some(entry)) = stream.next_entry().await {
+        output.push(entry.path());
+    }
+
+    output.sort();
+    output
+}
+
+/// Lists the directories that might be roots of runtimes, from the well-known locations:
+/// `JAVA_HOME`, every `java` on `PATH`, SDKMAN, and the system directories of Linux and macOS.
+pub async fn candidates() -> Vec<PathBuf> {
+    let mut output = Vec::new();
+
+    if let Some(home) = env::var_os("JAVA_HOME") {
+        output.push(PathBuf::from(home));
+    }
+
+    // `java` on the path is usually a symbolic link (e.g. through `/etc/alternatives`), so it has
+    // to be resolved to find the runtime that it belongs to
+    if let Some(path) = env::var_os("PATH") {
+        for directory in env::split_paths(&path) {
+            let executable = directory.join(Path::new(EXECUTABLE).file_name().unwrap_or_default());
+            if let Ok(executable) = fs::canonicalize(executable).await
+                && let Some(home) = executable.parent().and_then(Path::parent)
+            {
+                output.push(home.to_path_buf());
+            }
+        }
+    }
+
+    if let Some(home) = env::home_dir() {
+        output.extend(children(&home.join(".sdkman/candidates/java")).await);
+    }
+
+    output.extend(children(Path::new("/usr/lib/jvm")).await);
+
+    for bundle in children(Path::new("/Library/Java/JavaVirtualMachines")).await {
+        output.push(bundle.join("Contents/Home"));
+    }
+
+    output
+}
+
+/// Finds the runtimes installed on the system, keyed by their root directory. Runtimes that are
+/// found multiple times (e.g. through both `PATH` and `/usr/lib/jvm`) are only listed once.
+#[tracing::instrument(name = "java::discover")]
+pub async fn discover() -> BTreeMap<PathBuf, JavaInfo> {
+    let mut seen = HashSet::new();
+    let mut output = BTreeMap::new();
+
+    for candidate in candidates().await {
+        let Ok(home) = fs::canonicalize(&candidate).await else {
+            continue;
+        };
+
+        if !seen.insert(home.clone()) {
+            continue;
+        }
+
+        match inspect(&home).await {
+            Some(info) => {
+                tracing::debug!("Found Java {} at {}", info.version, home.display());
+                output.insert(home, info);
+            }
+            None => tracing::trace!("No Java found at {}", home.display()),
+        }
+    }
+
+    output
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn versions() {
+        assert_eq!(parse_version("1.8.0_392"), Some(Version::new(8, 0, 392)));
+        assert_eq!(parse_version("1.8.0"), Some(Version::new(8, 0, 0)));
+        assert_eq!(parse_version("17.0.9"), Some(Version::new(17, 0, 9)));
+        assert_eq!(parse_version("21"), Some(Version::new(21, 0, 0)));
+        assert_eq!(parse_version("21.0.1+12"), Some(Version::new(21, 0, 1)));
+        assert_eq!(parse_version("banana"), None);
+    }
+
+    #[tokio::test]
+    async fn inspect() {
+        let temp = TempDir::new().unwrap();
+        let home = temp.path();
+        std::fs::create_dir_all(home.join("bin")).unwrap();
+        std::fs::write(home.join(EXECUTABLE), "").unwrap();
+        std::fs::write(
+            home.join("release"),
+            "IMPLEMENTOR=\"Eclipse Adoptium\"\nJAVA_VERSION=\"17.0.9\"\nOS_NAME=\"Linux\"\nOS_ARCH=\"amd64\"\n",
+        )
+        .unwrap();
+
+        let info = super::inspect(home).await.unwrap();
+        assert_eq!(
+            info.provider,
+            JavaProvider::External(Some("Eclipse Adoptium".to_string()))
+        );
+        assert_eq!(info.version, Version::new(17, 0, 9));
+        assert_eq!(info.edition, JavaEdition::JRE);
+        assert_eq!(info.environment.os, OS::Linux);
+        assert_eq!(info.environment.arch, Arch::X86_64);
+        assert_eq!(info.home.as_deref(), Some(home));
+
+        std::fs::write(home.join(COMPILER), "").unwrap();
+        assert_eq!(
+            super::inspect(home).await.unwrap().edition,
+            JavaEdition::JDK
+        );
+    }
+}
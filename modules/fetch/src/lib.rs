@@ -21,8 +21,16 @@
 //!
 //! It consists of a few separate parts:
 //!
+//! - [assets] - Installation of game assets.
+//! - [download] - Downloading of files with known checksums.
+//! - [java] - Installation of Java runtimes.
+//! - [libraries] - Installation of libraries.
 //! - [meta]
 
+pub mod assets;
+pub mod download;
+pub mod java;
+pub mod libraries;
 pub mod meta;
 
 use thiserror::Error;
@@ -37,6 +45,30 @@ pub enum Error {
     RonParseError(#[from] ron::de::SpannedError),
     #[error("unsupported api version: we only support {0}, api supports {1}")]
     ApiVersionMismatch(u64, String),
+    #[error("io error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("checksum mismatch for {0}: expected {1}, got {2}")]
+    ChecksumMismatch(String, String, String),
+    #[error("size mismatch for {0}: expected {1} bytes, got {2}")]
+    SizeMismatch(url::Url, u64, u64),
+    #[error("archive error: {0}")]
+    ArchiveError(#[from] utils::archive::Error),
+    #[error("registry error: {0}")]
+    RegistryError(#[from] persistence::Error),
+    #[error("java runtime {0} is not installed")]
+    JavaNotInstalled(String),
+    #[error("java runtime {0} was not installed by the launcher")]
+    ExternalJava(String),
+    #[error("java executable is missing: {0}")]
+    MissingExecutable(std::path::PathBuf),
+    #[error("no java version satisfies the requirement {0}")]
+    UnsatisfiableJava(semver::VersionReq),
+    #[error("file is generated by an installer, but is missing: {0}")]
+    MissingLocalFile(std::path::PathBuf),
+    #[error("unsafe asset name: {0}")]
+    UnsafeAssetName(String),
+    #[error("unsafe library path: {0}")]
+    UnsafeLibraryPath(String),
 }
 
 pub type Result<T> = core::result::Result<T, Error>;
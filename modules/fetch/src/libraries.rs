@@ -0,0 +1,263 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Installation of libraries into a store (`libraries` in the data directory) that is shared by all
+//! game versions and instances. Libraries are laid out in Maven layout, by the path of their file,
+//! so each JAR is only ever downloaded and stored once.
+
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+use futures_util::{StreamExt, TryStreamExt, stream};
+
+use data::core::game::ResolvedGameVersion;
+use data::core::library::{Library, LibraryDownloadable, LibrarySource};
+use net::Client;
+use utils::directories;
+
+use super::download::DownloadOptions;
+use super::{Error, Result, download};
+
+/// A shared store of libraries, in Maven layout.
+#[derive(Clone, Debug)]
+pub struct LibraryStore {
+    root: PathBuf,
+    downloads: DownloadOptions,
+}
+
+impl Default for LibraryStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LibraryStore {
+    /// Opens the default library store in the data directory.
+    pub fn new() -> Self {
+        Self::at(directories::DATA.join("libraries"))
+    }
+
+    /// Opens a library store at a custom location.
+    pub fn at(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            downloads: DownloadOptions::default(),
+        }
+    }
+
+    /// Downloads files with the given options, e.g. the ones from the settings.
+    pub fn with_downloads(mut self, downloads: DownloadOptions) -> Self {
+        self.downloads = downloads;
+        self
+    }
+
+    /// The root directory of the store.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The path of a file in the store. Paths come from version JSONs, which can also be written by
+    /// mod loaders and installers, so paths that would end up outside of the store are rejected.
+    pub fn path(&self, file: &LibraryDownloadable) -> Result<PathBuf> {
+        let relative = Path::new(&file.path);
+        let safe = !file.path.is_empty()
+            && relative
+                .components()
+                .all(|it| matches!(it, Component::Normal(_)));
+
+        match safe {
+            true => Ok(self.root.join(relative)),
+            false => Err(Error::UnsafeLibraryPath(file.path.clone())),
+        }
+    }
+
+    /// Installs files into the store, verifying their checksums. Files that are already installed
+    /// are skipped. Returns the paths of the files, in order and without duplicates.
+    async fn install_files(
+        &self,
+        client: &Client,
+        files: Vec<&LibraryDownloadable>,
+    ) -> Result<Vec<PathBuf>> {
+        let mut seen = HashSet::new();
+        let files: Vec<_> = files
+            .into_iter()
+            .filter(|it| seen.insert(it.path.as_str()))
+            .collect();
+
+        stream::iter(files)
+            .map(|file| async move {
+                let path = self.path(file)?;
+                let url = self.downloads.mirror(&file.url);
+                match file.source {
+                    LibrarySource::Verified => {
+                        download::file(client, &url, &path, &file.checksum, file.size).await?;
+                    }
+                    LibrarySource::Unverified => {
+                        download::unverified(client, &url, &path).await?;
+                    }
+                    LibrarySource::Local => {
+                        if !tokio::fs::try_exists(&path).await? {
+                            return Err(Error::MissingLocalFile(path));
+                        }
+                    }
+                }
+                Ok(path)
+            })
+            .buffered(self.downloads.concurrency)
+            .try_collect()
+            .await
+    }
+
+    /// Installs libraries into the store. Returns their class path entries, in order.
+    #[tracing::instrument(name = "LibraryStore::install", skip_all)]
+    pub async fn install(&self, client: &Client, libraries: &[Library]) -> Result<Vec<PathBuf>> {
+        let files = libraries.iter().map(|it| &it.file).collect();
+        self.install_files(client, files).await
+    }
+
+    /// Installs everything that a version needs on its class path: its libraries, followed by the
+    /// game's own JAR file. Versions combined with a mod loader (see [GameVersion::with_loader])
+    /// get the loader's libraries as well. Returns the class path entries, in order.
+    ///
+    /// [GameVersion::with_loader]: data::core::game::GameVersion::with_loader
+    #[tracing::instrument(name = "LibraryStore::install_version", skip_all, fields(id = %version.id))]
+    pub async fn install_version(
+        &self,
+        client: &Client,
+        version: &ResolvedGameVersion,
+    ) -> Result<Vec<PathBuf>> {
+        let files = version
+            .libraries
+            .iter()
+            .map(|it| &it.file)
+            .chain([&version.client])
+            .collect();
+
+        self.install_files(client, files).await
+    }
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use data::core::conditional::MaybeConditional;
+    use data::web::vanilla::VanillaLibrary;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn library(name: &str, path: &str) -> Library {
+        Library {
+            name: name.parse().unwrap(),
+            file: LibraryDownloadable {
+                path: path.to_string(),
+                checksum: "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed".to_string(),
+                size: 11,
+                url: "https://libraries.minecraft.net/".parse().unwrap(),
+                source: LibrarySource::Verified,
+            },
+            extract: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn install() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let store = LibraryStore::at(root);
+        let client = Client::new().await;
+
+        let libraries = [
+            library("a:b:1", "a/b/1/b-1.jar"),
+            library("a:c:1", "a/c/1/c-1.jar"),
+            library("a:b:1", "a/b/1/b-1.jar"),
+        ];
+
+        // Installed libraries are reused instead of being downloaded again
+        for library in &libraries {
+            let path = store.path(&library.file).unwrap();
+            tokio::fs::create_dir_all(path.parent().unwrap())
+                .await
+                .unwrap();
+            tokio::fs::write(path, "hello world").await.unwrap();
+        }
+
+        let classpath = store.install(&client, &libraries).await.unwrap();
+        assert_eq!(
+            classpath,
+            [root.join("a/b/1/b-1.jar"), root.join("a/c/1/c-1.jar")]
+        );
+
+        // Paths come from version JSONs, so they can't point outside of the store
+        for path in ["../escape.jar", "/tmp/escape.jar", "a/../../escape.jar", ""] {
+            let escaping = library("a:d:1", path);
+            let result = store.install(&client, &[escaping]).await;
+            assert!(matches!(result, Err(Error::UnsafeLibraryPath(_))));
+        }
+
+        client.destroy().await;
+    }
+
+    #[tokio::test]
+    async fn install_imported() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let store = LibraryStore::at(root);
+        let client = Client::new().await;
+
+        // Version JSONs of mod loaders list libraries by their repository only, and installers
+        // generate some of them locally
+        let libraries: Vec<Library> = [
+            r#"{"name": "net.fabricmc:fabric-loader:0.15.3", "url": "https://maven.fabricmc.net/"}"#,
+            r#"{
+                "name": "net.minecraftforge:forge:1.20.1-47.2.0:client",
+                "downloads": {"artifact": {"path": "net/minecraftforge/forge/1.20.1-47.2.0/forge-1.20.1-47.2.0-client.jar", "url": "", "sha1": "0", "size": 1}}
+            }"#,
+        ]
+        .into_iter()
+        .flat_map(|it| {
+            let library: VanillaLibrary = serde_json::from_str(it).unwrap();
+            Vec::<MaybeConditional<Library>>::try_from(library).unwrap()
+        })
+        .map(|it| it.value().clone())
+        .collect();
+
+        assert_eq!(libraries[0].file.source, LibrarySource::Unverified);
+        assert_eq!(libraries[1].file.source, LibrarySource::Local);
+
+        let result = store.install(&client, &libraries[1..]).await;
+        assert!(matches!(result, Err(Error::MissingLocalFile(_))));
+
+        for library in &libraries {
+            let path = store.path(&library.file).unwrap();
+            tokio::fs::create_dir_all(path.parent().unwrap())
+                .await
+                .unwrap();
+            tokio::fs::write(path, "hello world").await.unwrap();
+        }
+
+        let classpath = store.install(&client, &libraries).await.unwrap();
+        assert_eq!(
+            classpath,
+            [
+                root.join("net/fabricmc/fabric-loader/0.15.3/fabric-loader-0.15.3.jar"),
+                root.join("net/minecraftforge/forge/1.20.1-47.2.0/forge-1.20.1-47.2.0-client.jar"),
+            ]
+        );
+
+        client.destroy().await;
+    }
+}
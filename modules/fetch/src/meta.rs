@@ -15,6 +15,7 @@
 
 use url::Url;
 
+use data::core::assets::AssetIndex;
 use data::core::game::GameVersion;
 use data::core::java::JavaBuild;
 use data::web::meta::{MetaIndex, MetaIndexAnnouncement, VERSION};
@@ -69,3 +70,8 @@ pub async fn game_version(client: &Client, base: &Url, id: &str) -> Result<GameV
 pub async fn game_versions(client: &Client, base: &Url) -> Result<Vec<GameVersion>> {
     fetch_impl!(client, base, "v{VERSION}/game.ron")
 }
+
+/// Fetches an asset index from the metadata server.
+pub async fn asset_index(client: &Client, base: &Url, id: &str) -> Result<AssetIndex> {
+    fetch_impl!(client, base, "v{VERSION}/assets/{id}.ron")
+}
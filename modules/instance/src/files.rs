@@ -0,0 +1,116 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Helpers for copying directories around, and for placing files into them.
+
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use tokio::task;
+
+use crate::{Error, Result};
+
+/// Copies a directory and everything in it that passes the filter, recreating symbolic links
+/// instead of following them. The filter gets paths relative to the root of the copy, and
+/// directories that don't pass it are skipped entirely. This function is blocking!
+fn copy_dir_blocking(
+    source: &Path,
+    destination: &Path,
+    relative: &Path,
+    filter: &dyn Fn(&Path) -> bool,
+) -> io::Result<()> {
+    fs::create_dir_all(destination)?;
+
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let kind = entry.file_type()?;
+        let target = destination.join(entry.file_name());
+
+        let relative = relative.join(entry.file_name());
+        if !filter(&relative) {
+            continue;
+        }
+
+        if kind.is_dir() {
+            copy_dir_blocking(&entry.path(), &target, &relative, filter)?;
+        } else if kind.is_symlink() {
+            let link = fs::read_link(entry.path())?;
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(link, &target)?;
+            #[cfg(not(unix))]
+            tracing::warn!("Skipping symbolic link {}", link.display());
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Copies a directory and everything in it, recreating symbolic links instead of following them.
+pub async fn copy_dir(source: &Path, destination: &Path) -> io::Result<()> {
+    copy_dir_filtered(source, destination, |_| true).await
+}
+
+/// Copies a directory and everything in it that passes the filter, which gets paths relative to
+/// the source. Directories that don't pass it are skipped entirely.
+pub async fn copy_dir_filtered(
+    source: &Path,
+    destination: &Path,
+    filter: impl Fn(&Path) -> bool + Send + 'static,
+) -> io::Result<()> {
+    let source = source.to_path_buf();
+    let destination = destination.to_path_buf();
+
+    task::spawn_blocking(move || copy_dir_blocking(&source, &destination, Path::new(""), &filter))
+        .await
+        .expect("blocking thread panicked")
+}
+
+/// The path of a file in a directory, from a path relative to it that came from somewhere else
+/// (e.g. the index of a modpack). Paths that would end up outside of the directory are rejected.
+pub fn destination(directory: &Path, path: &str) -> Result<PathBuf> {
+    let relative = Path::new(path);
+    let safe = !path.is_empty()
+        && relative
+            .components()
+            .all(|it| matches!(it, Component::Normal(_)));
+
+    match safe {
+        true => Ok(directory.join(relative)),
+        false => Err(Error::UnsafePath(path.to_string())),
+    }
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths() {
+        let root = Path::new("/game");
+        assert_eq!(
+            destination(root, "mods/a.jar").unwrap(),
+            Path::new("/game/mods/a.jar")
+        );
+
+        for path in ["", "../a.jar", "mods/../../a.jar", "/etc/passwd", "./a.jar"] {
+            assert!(destination(root, path).is_err(), "{path}");
+        }
+    }
+}
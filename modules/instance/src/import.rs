@@ -0,0 +1,39 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Importing of instances from other launchers.
+
+pub mod curseforge;
+pub mod prism;
+
+/// How the files of an imported instance end up in its game directory.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ImportMode {
+    /// The files are copied, and the original instance is left untouched.
+    #[default]
+    Copy,
+    /// The game directory links to the original one, so both launchers share the same files.
+    /// Falls back to copying on platforms without symbolic links.
+    Link,
+}
+
+/// The outcome of an import.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ImportReport {
+    /// The ID of the new instance.
+    pub id: String,
+    /// Everything that couldn't be carried over, in a form that can be shown to the user.
+    pub unmapped: Vec<String>,
+}
@@ -0,0 +1,429 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Importing of CurseForge modpacks. The files of a pack are only listed by their project and file
+//! IDs, so they go through a [Resolver] to find out where they go and where they come from.
+
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use futures_util::{StreamExt, TryStreamExt, stream};
+use serde::de::DeserializeOwned;
+use tokio::fs;
+use url::Url;
+
+use data::core::instance::{Instance, InstanceLoader};
+use data::web::curseforge::{
+    API_URL, CurseForgeApiFile, CurseForgeFile, CurseForgeManifest, CurseForgeProject,
+    CurseForgeResponse, MANIFEST, MANIFEST_TYPE, SHA1,
+};
+use fetch::download::{self, Algorithm, DownloadOptions};
+use net::header::HeaderValue;
+use net::{Client, Method, Request};
+use utils::archive::{self, ExtractOptions, Format};
+use utils::directories;
+
+use super::ImportReport;
+use crate::{Error, InstanceManager, Result, files};
+
+/// The mod loaders that packs can have, by the prefix of their IDs.
+const LOADERS: &[&str] = &["forge", "neoforge", "fabric", "quilt"];
+
+/// The directories that files go into, by the class of their project. Files of other classes are
+/// treated as mods.
+const CLASSES: &[(u64, &str)] = &[(6, "mods"), (12, "resourcepacks"), (6552, "shaderpacks")];
+
+/// The class of worlds, which are archives of save directories that aren't installed.
+const WORLDS: u64 = 17;
+
+/// A file of a pack, resolved to where it goes and where it comes from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ResolvedFile {
+    /// The path of the file, relative to the game directory (e.g. `mods/jei.jar`).
+    pub path: String,
+    /// Where the file is downloaded from.
+    pub url: Url,
+    /// The SHA1 checksum of the file, if it is known.
+    pub sha1: Option<String>,
+    /// The size of the file in bytes, which is checked when there is no checksum.
+    pub size: u64,
+}
+
+/// What a file of a pack resolves to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Resolution {
+    /// The file can be downloaded.
+    File(ResolvedFile),
+    /// The file can't be downloaded (authors can disallow downloads from other launchers).
+    Unavailable,
+    /// The file is of a kind that isn't installed, described for the report (e.g. `world
+    /// Skyblock.zip`).
+    Unsupported(String),
+}
+
+/// Resolves and downloads the files of packs. [CurseForgeApi] does it with the CurseForge API,
+/// while tests use a local stand-in.
+pub trait Resolver {
+    /// Resolves a file to where it goes and where it comes from, if it can be installed.
+    fn resolve(&self, file: &CurseForgeFile) -> impl Future<Output = Result<Resolution>> + Send;
+
+    /// Downloads a resolved file to the path.
+    fn download(&self, file: &ResolvedFile, path: &Path)
+    -> impl Future<Output = Result<()>> + Send;
+}
+
+/// A [Resolver] that uses the CurseForge API, which needs an API key.
+pub struct CurseForgeApi<'a> {
+    client: &'a Client,
+    key: String,
+}
+
+// === impl ===
+
+impl<'a> CurseForgeApi<'a> {
+    /// Creates a resolver that sends its requests with the client, using the API key.
+    pub fn new(client: &'a Client, key: impl Into<String>) -> Self {
+        Self {
+            client,
+            key: key.into(),
+        }
+    }
+
+    /// Sends a GET request to an endpoint of the API, and returns the data of the response.
+    async fn get<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
+        let url = Url::parse(&format!("{API_URL}{endpoint}")).map_err(fetch::Error::from)?;
+        let mut request = Request::new(Method::GET, url);
+
+        let value = HeaderValue::from_str(&self.key)
+            .map_err(|_| Error::Import("invalid CurseForge API key".to_string()))?;
+        request.headers_mut().insert("x-api-key", value);
+
+        let response: CurseForgeResponse<T> = self
+            .client
+            .execute(request)
+            .await?
+            .json()
+            .await
+            .map_err(net::Error::from)?;
+        Ok(response.data)
+    }
+}
+
+impl Resolver for CurseForgeApi<'_> {
+    async fn resolve(&self, file: &CurseForgeFile) -> Result<Resolution> {
+        let (project, id) = (file.project_id, file.file_id);
+        let info: CurseForgeApiFile = self.get(&format!("/mods/{project}/files/{id}")).await?;
+        let Some(url) = info.download_url else {
+            return Ok(Resolution::Unavailable);
+        };
+
+        let project: CurseForgeProject = self.get(&format!("/mods/{project}")).await?;
+        if project.class_id == Some(WORLDS) {
+            return Ok(Resolution::Unsupported(format!("world {}", info.file_name)));
+        }
+
+        let directory = CLASSES
+            .iter()
+            .find(|(class, _)| Some(*class) == project.class_id)
+            .map_or("mods", |(_, directory)| *directory);
+
+        Ok(Resolution::File(ResolvedFile {
+            path: format!("{directory}/{}", info.file_name),
+            url,
+            sha1: info
+                .hashes
+                .into_iter()
+                .find(|it| it.algo == SHA1)
+                .map(|it| it.value),
+            size: info.file_length,
+        }))
+    }
+
+    async fn download(&self, file: &ResolvedFile, path: &Path) -> Result<()> {
+        match &file.sha1 {
+            Some(sha1) => {
+                download::verified(self.client, &file.url, path, sha1, Algorithm::Sha1).await?
+            }
+            None => download::sized(self.client, &file.url, path, file.size).await?,
+        }
+
+        Ok(())
+    }
+}
+
+/// Maps the manifest of a pack to a new instance. The primary mod loader becomes the loader of
+/// the instance, and any others are returned as unmapped.
+pub fn parse(manifest: &CurseForgeManifest, now: DateTime<Utc>) -> Result<(Instance, Vec<String>)> {
+    if manifest.manifest_type != MANIFEST_TYPE {
+        let kind = &manifest.manifest_type;
+        return Err(Error::Import(format!("unsupported manifest type {kind}")));
+    }
+
+    let mut instance = Instance::new(&manifest.name, &manifest.minecraft.version, now);
+    let mut unmapped = vec![];
+
+    let mut loaders: Vec<_> = manifest.minecraft.mod_loaders.iter().collect();
+    loaders.sort_by_key(|it| !it.primary);
+
+    for loader in loaders {
+        let parsed = loader
+            .id
+            .split_once('-')
+            .filter(|(id, _)| LOADERS.contains(id));
+
+        match parsed {
+            Some((id, version)) if instance.loader.is_none() => {
+                instance.loader = Some(InstanceLoader {
+                    id: id.to_string(),
+                    version: version.to_string(),
+                });
+            }
+            _ => unmapped.push(format!("Mod loader {}", loader.id)),
+        }
+    }
+
+    Ok((instance, unmapped))
+}
+
+/// Resolves and downloads one file of a pack into the game directory. Returns a description of the
+/// file if it can't be downloaded.
+async fn install_file(
+    resolver: &impl Resolver,
+    downloads: &DownloadOptions,
+    directory: &Path,
+    file: &CurseForgeFile,
+) -> Result<Option<String>> {
+    let (project, id) = (file.project_id, file.file_id);
+    let mut resolved = match resolver.resolve(file).await? {
+        Resolution::File(resolved) => resolved,
+        Resolution::Unavailable => {
+            return Ok(Some(format!(
+                "Project {project} file {id} (not available for download)"
+            )));
+        }
+        Resolution::Unsupported(kind) => {
+            return Ok(Some(format!(
+                "Project {project} file {id} ({kind} is not installed)"
+            )));
+        }
+    };
+
+    let path = files::destination(directory, &resolved.path)?;
+    resolved.url = downloads.mirror(&resolved.url);
+    resolver.download(&resolved, &path).await?;
+    Ok(None)
+}
+
+/// Installs the files of an extracted pack into a new instance and applies its overrides.
+async fn install(
+    manager: &mut InstanceManager,
+    resolver: &impl Resolver,
+    downloads: &DownloadOptions,
+    staging: &Path,
+) -> Result<ImportReport> {
+    let manifest: CurseForgeManifest =
+        serde_json::from_str(&fs::read_to_string(staging.join(MANIFEST)).await?)?;
+    let (instance, mut unmapped) = parse(&manifest, Utc::now())?;
+
+    let id = manager.create(instance).await?;
+    let directory = manager.directory(&id);
+
+    let result = async {
+        let missing: Vec<_> = stream::iter(manifest.files.iter().filter(|it| it.required))
+            .map(|file| install_file(resolver, downloads, &directory, file))
+            .buffered(downloads.concurrency)
+            .try_collect()
+            .await?;
+        unmapped.extend(missing.into_iter().flatten());
+
+        let overrides = files::destination(staging, &manifest.overrides)?;
+        if fs::try_exists(&overrides).await? {
+            files::copy_dir(&overrides, &directory).await?;
+        }
+
+        Ok(())
+    }
+    .await;
+
+    if let Err(err) = result {
+        let _ = manager.delete(&id).await;
+        return Err(err);
+    }
+
+    Ok(ImportReport { id, unmapped })
+}
+
+/// Imports a CurseForge modpack into a new instance. Optional files that the author left disabled
+/// are skipped.
+#[tracing::instrument(name = "curseforge::import", skip(manager, resolver, downloads))]
+pub async fn import(
+    manager: &mut InstanceManager,
+    resolver: &impl Resolver,
+    downloads: &DownloadOptions,
+    source: &Path,
+) -> Result<ImportReport> {
+    let name = source.file_stem().unwrap_or_default().to_string_lossy();
+    let staging: PathBuf = directories::CACHE.join("curseforge").join(name.as_ref());
+    let _ = fs::remove_dir_all(&staging).await;
+
+    // modpacks never need links, which could point outside of the game directory once copied
+    let options = ExtractOptions {
+        skip_links: true,
+        ..Default::default()
+    };
+
+    let result = async {
+        archive::extract_as(source, &staging, Format::Zip, options).await?;
+        install(manager, resolver, downloads, &staging).await
+    }
+    .await;
+
+    let _ = fs::remove_dir_all(&staging).await;
+    result
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+    use utils::archive::CreateOptions;
+
+    use super::*;
+
+    const MANIFEST_JSON: &str = r#"{
+        "minecraft": {
+            "version": "1.20.1",
+            "modLoaders": [
+                {"id": "fabric-0.15.7", "primary": false},
+                {"id": "forge-47.2.0", "primary": true}
+            ]
+        },
+        "manifestType": "minecraftModpack",
+        "manifestVersion": 1,
+        "name": "All the Mods",
+        "version": "1.0.0",
+        "author": "someone",
+        "files": [
+            {"projectID": 238222, "fileID": 4712866, "required": true},
+            {"projectID": 306612, "fileID": 4596743, "required": true},
+            {"projectID": 421873, "fileID": 4620120, "required": true},
+            {"projectID": 394468, "fileID": 4835191, "required": false}
+        ],
+        "overrides": "overrides"
+    }"#;
+
+    /// Resolves files to copies of local ones, with one project that can't be downloaded and one
+    /// that is a world.
+    struct LocalResolver {
+        root: PathBuf,
+        unavailable: u64,
+        world: u64,
+    }
+
+    impl Resolver for LocalResolver {
+        async fn resolve(&self, file: &CurseForgeFile) -> Result<Resolution> {
+            if file.project_id == self.unavailable {
+                return Ok(Resolution::Unavailable);
+            }
+            if file.project_id == self.world {
+                return Ok(Resolution::Unsupported("world Skyblock.zip".to_string()));
+            }
+
+            let name = format!("{}-{}.jar", file.project_id, file.file_id);
+            let path = self.root.join(&name);
+            Ok(Resolution::File(ResolvedFile {
+                path: format!("mods/{name}"),
+                size: std::fs::metadata(&path)?.len(),
+                url: Url::from_file_path(path).unwrap(),
+                sha1: None,
+            }))
+        }
+
+        async fn download(&self, file: &ResolvedFile, path: &Path) -> Result<()> {
+            fs::create_dir_all(path.parent().unwrap()).await?;
+            fs::copy(file.url.to_file_path().unwrap(), path).await?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let manifest: CurseForgeManifest = serde_json::from_str(MANIFEST_JSON).unwrap();
+        let (instance, unmapped) = super::parse(&manifest, Utc::now()).unwrap();
+
+        assert_eq!(instance.name, "All the Mods");
+        assert_eq!(instance.game_version, "1.20.1");
+        assert_eq!(
+            instance.loader,
+            Some(InstanceLoader {
+                id: "forge".to_string(),
+                version: "47.2.0".to_string()
+            })
+        );
+        assert_eq!(unmapped, ["Mod loader fabric-0.15.7"]);
+    }
+
+    #[tokio::test]
+    async fn import() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+
+        let pack = root.join("pack");
+        std::fs::create_dir_all(pack.join("overrides/config")).unwrap();
+        std::fs::write(pack.join(MANIFEST), MANIFEST_JSON).unwrap();
+        std::fs::write(pack.join("overrides/config/jei.toml"), "config").unwrap();
+
+        let archive = root.join("All the Mods.zip");
+        archive::create(&pack, &archive, CreateOptions::default())
+            .await
+            .unwrap();
+
+        let mirror = root.join("mirror");
+        std::fs::create_dir_all(&mirror).unwrap();
+        std::fs::write(mirror.join("238222-4712866.jar"), "jei").unwrap();
+
+        let resolver = LocalResolver {
+            root: mirror,
+            unavailable: 306612,
+            world: 421873,
+        };
+        let mut manager = InstanceManager::at(root.join("Instances")).await.unwrap();
+        let report = super::import(
+            &mut manager,
+            &resolver,
+            &DownloadOptions::default(),
+            &archive,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.id, "all-the-mods");
+        assert_eq!(
+            report.unmapped,
+            [
+                "Mod loader fabric-0.15.7",
+                "Project 306612 file 4596743 (not available for download)",
+                "Project 421873 file 4620120 (world Skyblock.zip is not installed)"
+            ]
+        );
+
+        let directory = manager.directory(&report.id);
+        assert!(directory.join("mods/238222-4712866.jar").is_file());
+        assert!(!directory.join("mods/394468-4835191.jar").exists());
+        assert!(directory.join("config/jei.toml").is_file());
+    }
+}
@@ -0,0 +1,383 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Importing of instances from Prism Launcher and MultiMC. An instance of theirs is a directory
+//! with an `instance.cfg` (INI settings), an `mmc-pack.json` (the components: the game, the mod
+//! loader, and their libraries) and the game directory (`.minecraft` or `minecraft`).
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use tokio::fs;
+
+use data::core::instance::{Instance, InstanceLoader};
+use data::web::prism::{GAME_UID, PrismPack};
+
+use super::{ImportMode, ImportReport};
+use crate::{Error, InstanceManager, Result, files};
+
+/// Components that are mod loaders, and the IDs of the loaders.
+const LOADERS: &[(&str, &str)] = &[
+    ("net.fabricmc.fabric-loader", "fabric"),
+    ("org.quiltmc.quilt-loader", "quilt"),
+    ("net.minecraftforge", "forge"),
+    ("net.neoforged", "neoforge"),
+];
+
+/// Components that come with the game version or the mod loader, so they need no mapping.
+const IMPLIED: &[&str] = &[
+    "org.lwjgl",
+    "org.lwjgl3",
+    "net.fabricmc.intermediary",
+    "org.quiltmc.hashed",
+];
+
+/// The names of the game directory, in order of preference.
+const GAME_DIRECTORIES: &[&str] = &[".minecraft", "minecraft"];
+
+/// The extensions that custom icons can have.
+const ICON_EXTENSIONS: &[&str] = &["png", "svg", "jpg", "jpeg", "ico"];
+
+/// Parses an INI file written by Qt, ignoring sections. Values can be quoted, with backslash
+/// escapes inside of the quotes.
+pub fn parse_config(text: &str) -> HashMap<String, String> {
+    let unquote = |value: &str| match value.strip_prefix('"').and_then(|it| it.strip_suffix('"')) {
+        Some(value) => value.replace("\\\"", "\"").replace("\\\\", "\\"),
+        None => value.to_string(),
+    };
+
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with(['[', ';', '#']))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), unquote(value.trim())))
+        .collect()
+}
+
+/// Splits a command line into arguments, the way a shell would (without any expansion).
+pub fn split_arguments(text: &str) -> Vec<String> {
+    let mut arguments = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    let mut started = false;
+    let mut chars = text.chars();
+
+    while let Some(char) = chars.next() {
+        match (char, quote) {
+            ('\\', _) => current.extend(chars.next()),
+            ('"' | '\'', None) => quote = Some(char),
+            (char, Some(open)) if char == open => quote = None,
+            (char, None) if char.is_whitespace() => {
+                if started {
+                    arguments.push(std::mem::take(&mut current));
+                }
+                started = false;
+                continue;
+            }
+            (char, _) => current.push(char),
+        }
+        started = true;
+    }
+
+    if started {
+        arguments.push(current);
+    }
+
+    arguments
+}
+
+/// Turns the settings and components of an instance into an [Instance]. Returns it along with
+/// everything that couldn't be mapped.
+pub fn parse(
+    config: &HashMap<String, String>,
+    pack: &PrismPack,
+    now: DateTime<Utc>,
+) -> Result<(Instance, Vec<String>)> {
+    let mut unmapped = Vec::new();
+    let enabled = |key: &str| config.get(key).is_some_and(|it| it == "true");
+    let value = |key: &str| config.get(key).filter(|it| !it.is_empty());
+
+    let game = pack
+        .components
+        .iter()
+        .find(|it| it.uid == GAME_UID)
+        .and_then(|it| it.version.clone())
+        .ok_or_else(|| Error::Import("the instance has no game version".to_string()))?;
+
+    let name = value("name").cloned().unwrap_or_else(|| game.clone());
+    let mut instance = Instance::new(name, game, now);
+
+    for component in &pack.components {
+        if component.uid == GAME_UID || IMPLIED.contains(&component.uid.as_str()) {
+            continue;
+        }
+
+        let loader = LOADERS.iter().find(|(uid, _)| *uid == component.uid);
+        match (loader, &component.version, &instance.loader) {
+            (Some((_, id)), Some(version), None) => {
+                instance.loader = Some(InstanceLoader {
+                    id: id.to_string(),
+                    version: version.clone(),
+                })
+            }
+            _ => {
+                let name = component.cached_name.as_deref().unwrap_or(&component.uid);
+                let version = component.version.as_deref().unwrap_or("unknown version");
+                unmapped.push(format!("Component {name} ({version})"));
+            }
+        }
+    }
+
+    if enabled("OverrideMemory") {
+        instance.memory = value("MaxMemAlloc").and_then(|it| it.parse().ok());
+    }
+
+    if enabled("OverrideJavaArgs")
+        && let Some(arguments) = value("JvmArgs")
+    {
+        instance.jvm_arguments = split_arguments(arguments);
+    }
+
+    if enabled("OverrideJavaLocation")
+        && let Some(path) = value("JavaPath")
+    {
+        unmapped.push(format!(
+            "Java runtime at {path} (picked automatically instead)"
+        ));
+    }
+
+    if enabled("OverrideCommands") {
+        if let Some(wrapper) = value("WrapperCommand") {
+            instance.wrapper = split_arguments(wrapper);
+        }
+
+        for key in ["PreLaunchCommand", "PostExitCommand"] {
+            if let Some(command) = value(key) {
+                unmapped.push(format!("{key} `{command}`"));
+            }
+        }
+    }
+
+    instance.last_played = value("lastLaunchTime")
+        .and_then(|it| it.parse().ok())
+        .and_then(DateTime::from_timestamp_millis);
+
+    Ok((instance, unmapped))
+}
+
+/// Finds a custom icon in the `icons` directory of the launcher that the instance is in.
+async fn find_icon(source: &Path, key: &str) -> Option<PathBuf> {
+    let icons = source.parent()?.parent()?.join("icons");
+    for extension in ICON_EXTENSIONS {
+        let path = icons.join(format!("{key}.{extension}"));
+        if fs::try_exists(&path).await.unwrap_or(false) {
+            return Some(path);
+        }
+    }
+
+    None
+}
+
+/// Fills the game directory of the new instance from the original one.
+async fn import_files(source: &Path, destination: &Path, mode: ImportMode) -> Result<()> {
+    match mode {
+        #[cfg(unix)]
+        ImportMode::Link => {
+            fs::remove_dir(destination).await?;
+            fs::symlink(source, destination).await?;
+        }
+        _ => files::copy_dir(source, destination).await?,
+    }
+
+    Ok(())
+}
+
+/// Imports a Prism Launcher or MultiMC instance from its directory.
+#[tracing::instrument(name = "prism::import", skip(manager))]
+pub async fn import(
+    manager: &mut InstanceManager,
+    source: &Path,
+    mode: ImportMode,
+) -> Result<ImportReport> {
+    let config = parse_config(&fs::read_to_string(source.join("instance.cfg")).await?);
+    let pack: PrismPack =
+        serde_json::from_str(&fs::read_to_string(source.join("mmc-pack.json")).await?)?;
+    let (mut instance, mut unmapped) = parse(&config, &pack, Utc::now())?;
+
+    let icon = match config.get("iconKey") {
+        Some(key) if key != "default" => match find_icon(source, key).await {
+            Some(icon) => Some(icon),
+            None => {
+                unmapped.push(format!("Icon {key}"));
+                None
+            }
+        },
+        _ => None,
+    };
+
+    if let Some(icon) = &icon {
+        let extension = icon.extension().unwrap_or_default().to_string_lossy();
+        instance.icon = Some(format!("icon.{extension}"));
+    }
+
+    let id = manager.create(instance.clone()).await?;
+    let result = async {
+        if let (Some(icon), Some(name)) = (&icon, &instance.icon) {
+            fs::copy(icon, manager.path(&id).join(name)).await?;
+        }
+
+        for name in GAME_DIRECTORIES {
+            let directory = source.join(name);
+            if fs::try_exists(&directory).await? {
+                import_files(&directory, &manager.directory(&id), mode).await?;
+                break;
+            }
+        }
+
+        Ok(())
+    }
+    .await;
+
+    if let Err(err) = result {
+        let _ = manager.delete(&id).await;
+        return Err(err);
+    }
+
+    Ok(ImportReport { id, unmapped })
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    const CONFIG: &str = r#"[General]
+InstanceType=OneSix
+name="Fabulously \"Optimized\""
+iconKey=flame
+OverrideMemory=true
+MaxMemAlloc=6144
+MinMemAlloc=512
+OverrideJavaArgs=true
+JvmArgs=-XX:+UseZGC -Dfoo="bar baz"
+OverrideJavaLocation=true
+JavaPath=/usr/lib/jvm/java-17/bin/java
+OverrideCommands=true
+WrapperCommand=gamemoderun
+PreLaunchCommand=
+lastLaunchTime=1700000000000
+"#;
+
+    const PACK: &str = r#"{
+        "components": [
+            {"uid": "org.lwjgl3", "version": "3.3.2", "dependencyOnly": true},
+            {"uid": "net.minecraft", "version": "1.20.4", "cachedName": "Minecraft"},
+            {"uid": "net.fabricmc.intermediary", "version": "1.20.4", "dependencyOnly": true},
+            {"uid": "net.fabricmc.fabric-loader", "version": "0.15.7", "cachedName": "Fabric Loader"},
+            {"uid": "com.mumfrey.liteloader", "version": "1.12.2", "cachedName": "LiteLoader"}
+        ],
+        "formatVersion": 1
+    }"#;
+
+    #[test]
+    fn arguments() {
+        assert_eq!(
+            split_arguments(r#" -Xss1M  -Dname="a b" 'c d'\ e "#),
+            ["-Xss1M", "-Dname=a b", "c d e"]
+        );
+        assert_eq!(split_arguments(r#"-Dempty="""#), ["-Dempty="]);
+        assert!(split_arguments("   ").is_empty());
+    }
+
+    #[test]
+    fn parse() {
+        let pack: PrismPack = serde_json::from_str(PACK).unwrap();
+        let (instance, unmapped) = super::parse(&parse_config(CONFIG), &pack, Utc::now()).unwrap();
+
+        assert_eq!(instance.name, "Fabulously \"Optimized\"");
+        assert_eq!(instance.game_version, "1.20.4");
+        assert_eq!(
+            instance.loader,
+            Some(InstanceLoader {
+                id: "fabric".to_string(),
+                version: "0.15.7".to_string()
+            })
+        );
+        assert_eq!(instance.memory, Some(6144));
+        assert_eq!(instance.jvm_arguments, ["-XX:+UseZGC", "-Dfoo=bar baz"]);
+        assert_eq!(instance.wrapper, ["gamemoderun"]);
+        assert_eq!(
+            instance.last_played,
+            DateTime::from_timestamp_millis(1700000000000)
+        );
+        assert_eq!(
+            unmapped,
+            [
+                "Component LiteLoader (1.12.2)",
+                "Java runtime at /usr/lib/jvm/java-17/bin/java (picked automatically instead)"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn import() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let source = root.join("PrismLauncher/instances/Fabulously Optimized");
+        std::fs::create_dir_all(source.join(".minecraft/mods")).unwrap();
+        std::fs::create_dir_all(root.join("PrismLauncher/icons")).unwrap();
+        std::fs::write(source.join("instance.cfg"), CONFIG).unwrap();
+        std::fs::write(source.join("mmc-pack.json"), PACK).unwrap();
+        std::fs::write(source.join(".minecraft/mods/sodium.jar"), "mod").unwrap();
+        std::fs::write(root.join("PrismLauncher/icons/flame.png"), "png").unwrap();
+
+        let mut manager = InstanceManager::at(root.join("Instances")).await.unwrap();
+        let report = super::import(&mut manager, &source, ImportMode::Copy)
+            .await
+            .unwrap();
+
+        assert_eq!(report.id, "fabulously-optimized");
+        assert_eq!(report.unmapped.len(), 2);
+
+        let instance = manager.get(&report.id).unwrap();
+        assert_eq!(instance.icon.as_deref(), Some("icon.png"));
+        assert!(manager.path(&report.id).join("icon.png").is_file());
+        assert!(
+            manager
+                .directory(&report.id)
+                .join("mods/sodium.jar")
+                .is_file()
+        );
+
+        // clones of linked instances get their own copy of the game directory
+        #[cfg(unix)]
+        {
+            let report = super::import(&mut manager, &source, ImportMode::Link)
+                .await
+                .unwrap();
+            let linked = manager.directory(&report.id);
+            assert!(linked.symlink_metadata().unwrap().is_symlink());
+
+            let clone = manager.clone(&report.id, "Clone").await.unwrap();
+            let directory = manager.directory(&clone);
+            assert!(!directory.symlink_metadata().unwrap().is_symlink());
+            assert!(directory.join("mods/sodium.jar").is_file());
+        }
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Launcher Instance Module
+//! ========================
+//!
+//! This module manages game instances. Each instance has its own directory, which holds its
+//! settings (see [Instance]) and its game directory, where the game keeps saves, options, mods,
+//! and so on. It consists of a few separate parts:
+//!
+//! - [manager] - Creating, cloning, renaming and deleting instances.
+//! - [import] - Importing instances from other launchers.
+//! - [mods] - Reading the metadata of mods, toggling them, and checking their dependencies.
+//! - [modrinth] - Installing Modrinth modpacks, and exporting instances as modpacks.
+//! - [settings] - The global settings, which instances can override.
+//! - [vanilla] - Exchanging versions with the game directory layout of the official launcher.
+//!
+//! [Instance]: data::core::instance::Instance
+
+pub use manager::InstanceManager;
+
+pub mod import;
+pub mod manager;
+pub mod modrinth;
+pub mod mods;
+pub mod settings;
+pub mod vanilla;
+
+mod files;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("registry error: {0}")]
+    Registry(#[from] persistence::Error),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("instance {0} does not exist")]
+    NotFound(String),
+    #[error("json parse error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("failed to import instance: {0}")]
+    Import(String),
+    #[error("version error: {0}")]
+    Version(#[from] data::web::vanilla::VanillaError),
+    #[error("version {0} inherits from itself")]
+    Inheritance(String),
+    #[error("fetch error: {0}")]
+    Fetch(Box<fetch::Error>),
+    #[error("network error: {0}")]
+    Network(#[from] net::Error),
+    #[error("archive error: {0}")]
+    Archive(#[from] utils::archive::Error),
+    #[error("path {0} is outside of the game directory")]
+    UnsafePath(String),
+    #[error("failed to export instance: {0}")]
+    Export(String),
+    #[error("invalid mod metadata: {0}")]
+    Metadata(String),
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+impl From<fetch::Error> for Error {
+    fn from(value: fetch::Error) -> Self {
+        Self::Fetch(Box::new(value))
+    }
+}
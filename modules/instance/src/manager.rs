@@ -0,0 +1,288 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Management of the instances in the data directory.
+
+use std::path::PathBuf;
+
+use chrono::Utc;
+use tokio::fs;
+
+use data::core::instance::{Instance, InstanceJava};
+use persistence::DirectoryRegistry;
+use utils::directories;
+
+use crate::{Error, Result, files};
+
+/// The file in each instance's directory that its [Instance] is stored in.
+const INFO_FILE: &str = "Instance.toml";
+
+/// The name of the game directory inside of each instance's directory.
+const GAME_DIRECTORY: &str = "game";
+
+/// Turns a name into an ID that is safe to use as a directory name, e.g. `My Modpack!` into
+/// `my-modpack`.
+fn slug(name: &str) -> String {
+    let slug = name
+        .to_lowercase()
+        .split(|it: char| !it.is_alphanumeric())
+        .filter(|it| !it.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    match slug.is_empty() {
+        true => "instance".to_string(),
+        false => slug,
+    }
+}
+
+/// Keeps track of the instances. Each instance is stored in its own directory, under an ID that is
+/// derived from its name when it is created. The ID never changes afterwards, even if the instance
+/// is renamed.
+pub struct InstanceManager {
+    registry: DirectoryRegistry<Instance>,
+}
+
+impl InstanceManager {
+    /// Opens the default instances directory in the data directory.
+    pub async fn new() -> Result<Self> {
+        Self::at(directories::DATA.join("Instances")).await
+    }
+
+    /// Opens an instances directory at a custom location.
+    pub async fn at(root: impl Into<PathBuf>) -> Result<Self> {
+        let registry = DirectoryRegistry::at(root.into(), INFO_FILE).await?;
+        Ok(Self { registry })
+    }
+
+    /// Lists the instances, sorted by their name.
+    pub fn list(&self) -> Vec<(&str, &Instance)> {
+        let mut list: Vec<_> = self.registry.iter().collect();
+        list.sort_by(|a, b| (&a.1.name, a.0).cmp(&(&b.1.name, b.0)));
+        list
+    }
+
+    /// Gets an instance.
+    pub fn get(&self, id: &str) -> Option<&Instance> {
+        self.registry.get(id)
+    }
+
+    /// The directory of an instance, which holds its settings and game directory.
+    pub fn path(&self, id: &str) -> PathBuf {
+        self.registry.path(id)
+    }
+
+    /// The game directory of an instance, where the game keeps its saves, options, mods, etc.
+    pub fn directory(&self, id: &str) -> PathBuf {
+        self.path(id).join(GAME_DIRECTORY)
+    }
+
+    /// The IDs of the Java runtimes that instances are pinned to (see [InstanceJava::Pinned]).
+    pub fn pinned_java(&self) -> Vec<&str> {
+        let mut pinned: Vec<_> = self
+            .registry
+            .iter()
+            .filter_map(|(_, instance)| match &instance.java {
+                InstanceJava::Pinned(id) => Some(id.as_str()),
+                InstanceJava::Automatic => None,
+            })
+            .collect();
+
+        pinned.sort_unstable();
+        pinned.dedup();
+        pinned
+    }
+
+    /// Finds an ID for a new instance with a name, that isn't taken by another instance yet.
+    async fn unique_id(&self, name: &str) -> Result<String> {
+        let base = slug(name);
+        let mut id = base.clone();
+        let mut counter = 1;
+
+        while self.get(&id).is_some() || fs::try_exists(self.path(&id)).await? {
+            counter += 1;
+            id = format!("{base}-{counter}");
+        }
+
+        Ok(id)
+    }
+
+    /// Adds a new instance and creates its game directory. Returns the ID of the instance.
+    #[tracing::instrument(name = "InstanceManager::create", skip_all, fields(name = instance.name))]
+    pub async fn create(&mut self, instance: Instance) -> Result<String> {
+        let id = self.unique_id(&instance.name).await?;
+        fs::create_dir_all(self.directory(&id)).await?;
+        self.registry.insert(&id, instance).await?;
+
+        tracing::info!("Created instance {id}");
+        Ok(id)
+    }
+
+    /// Saves changed settings of an instance, updating when it was last modified.
+    pub async fn save(&mut self, id: &str, mut instance: Instance) -> Result<()> {
+        if self.get(id).is_none() {
+            return Err(Error::NotFound(id.to_string()));
+        }
+
+        instance.modified = Utc::now();
+        self.registry.insert(id, instance).await?;
+        Ok(())
+    }
+
+    /// Changes the name of an instance. Its ID (and directory) stays the same.
+    pub async fn rename(&mut self, id: &str, name: impl Into<String>) -> Result<()> {
+        let mut instance = self
+            .get(id)
+            .cloned()
+            .ok_or_else(|| Error::NotFound(id.to_string()))?;
+
+        instance.name = name.into();
+        self.save(id, instance).await
+    }
+
+    /// Creates a copy of an instance (including everything in its directory) with a new name.
+    /// Returns the ID of the copy.
+    #[tracing::instrument(name = "InstanceManager::clone", skip(self, name))]
+    pub async fn clone(&mut self, id: &str, name: impl Into<String>) -> Result<String> {
+        let mut instance = self
+            .get(id)
+            .cloned()
+            .ok_or_else(|| Error::NotFound(id.to_string()))?;
+
+        let now = Utc::now();
+        instance.name = name.into();
+        instance.created = now;
+        instance.modified = now;
+        instance.last_played = None;
+
+        let copy = self.unique_id(&instance.name).await?;
+        let result = self.copy_into(id, &copy, instance).await;
+        if result.is_err() {
+            let _ = fs::remove_dir_all(self.path(&copy)).await;
+        }
+
+        result.map(|()| copy)
+    }
+
+    /// Copies the directory of an instance to a new ID, and saves the settings there.
+    async fn copy_into(&mut self, id: &str, copy: &str, instance: Instance) -> Result<()> {
+        let source = self.path(id);
+        let destination = self.path(copy);
+
+        let mut stream = fs::read_dir(&source).await?;
+        while let Some(entry) = stream.next_entry().await? {
+            if entry.file_name() == INFO_FILE {
+                continue;
+            }
+
+            // Linked imports have their game directory somewhere else, which the clone gets a copy
+            // of, so that it doesn't share the saves and mods of the original
+            let target = destination.join(entry.file_name());
+            match fs::metadata(entry.path()).await?.is_dir() {
+                true => files::copy_dir(&entry.path(), &target).await?,
+                false => {
+                    fs::create_dir_all(&destination).await?;
+                    fs::copy(entry.path(), &target).await?;
+                }
+            }
+        }
+
+        fs::create_dir_all(self.directory(copy)).await?;
+        self.registry.insert(copy, instance).await?;
+        Ok(())
+    }
+
+    /// Deletes an instance *and everything in its directory*. Use carefully!
+    #[tracing::instrument(name = "InstanceManager::delete", skip(self))]
+    pub async fn delete(&mut self, id: &str) -> Result<()> {
+        if self.get(id).is_none() {
+            return Err(Error::NotFound(id.to_string()));
+        }
+
+        self.registry.delete(id).await?;
+        Ok(())
+    }
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use data::core::instance::InstanceLoader;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn slug() {
+        assert_eq!(super::slug("My Modpack!"), "my-modpack");
+        assert_eq!(super::slug("1.20.4 -- Fabric"), "1-20-4-fabric");
+        assert_eq!(super::slug("???"), "instance");
+    }
+
+    #[tokio::test]
+    async fn lifecycle() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let mut manager = InstanceManager::at(&root).await.unwrap();
+
+        let mut instance = Instance::new("Fabric 1.20.4", "1.20.4", Utc::now());
+        instance.loader = Some(InstanceLoader {
+            id: "fabric".to_string(),
+            version: "0.15.7".to_string(),
+        });
+        instance.java = InstanceJava::Pinned("zulu-17.0.13-jre".to_string());
+        instance
+            .environment
+            .insert("MESA_GL_VERSION_OVERRIDE".to_string(), "4.5".to_string());
+
+        let id = manager.create(instance.clone()).await.unwrap();
+        assert_eq!(id, "fabric-1-20-4");
+        assert_eq!(
+            manager.create(instance.clone()).await.unwrap(),
+            "fabric-1-20-4-2"
+        );
+
+        let options = manager.directory(&id).join("options.txt");
+        std::fs::write(&options, "fov:0.5").unwrap();
+
+        manager.rename(&id, "Fabric").await.unwrap();
+        let copy = manager.clone(&id, "Fabric (copy)").await.unwrap();
+        assert_eq!(copy, "fabric-copy");
+        assert_eq!(
+            std::fs::read_to_string(manager.directory(&copy).join("options.txt")).unwrap(),
+            "fov:0.5"
+        );
+
+        // everything is read back from the disk
+        let mut manager = InstanceManager::at(&root).await.unwrap();
+        let names: Vec<_> = manager
+            .list()
+            .iter()
+            .map(|(_, it)| it.name.as_str())
+            .collect();
+        assert_eq!(names, ["Fabric", "Fabric (copy)", "Fabric 1.20.4"]);
+        assert_eq!(manager.get(&copy).unwrap().loader, instance.loader);
+        assert_eq!(
+            manager.get(&copy).unwrap().environment,
+            instance.environment
+        );
+        assert_eq!(manager.pinned_java(), ["zulu-17.0.13-jre"]);
+
+        manager.delete(&id).await.unwrap();
+        assert!(!manager.path(&id).exists());
+        assert!(matches!(manager.delete(&id).await, Err(Error::NotFound(_))));
+    }
+}
@@ -0,0 +1,398 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Installing Modrinth modpacks (`.mrpack`) into new instances, and exporting instances as
+//! modpacks. See [data::web::modrinth] for the format.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use futures_util::{StreamExt, TryStreamExt, stream};
+use tokio::fs;
+
+use data::core::instance::{Instance, InstanceLoader};
+use data::web::modrinth::{
+    CLIENT_OVERRIDES, FORMAT_VERSION, GAME_DEPENDENCY, INDEX, ModrinthFile, ModrinthIndex,
+    OVERRIDES,
+};
+use fetch::download::{self, Algorithm, DownloadOptions};
+use net::Client;
+use utils::archive::{self, CreateOptions, ExtractOptions, Format};
+use utils::directories;
+
+use crate::import::ImportReport;
+use crate::{Error, InstanceManager, Result, files, vanilla};
+
+/// Dependencies that are mod loaders, and the IDs of the loaders.
+const LOADERS: &[(&str, &str)] = &[
+    ("fabric-loader", "fabric"),
+    ("quilt-loader", "quilt"),
+    ("forge", "forge"),
+    ("neoforge", "neoforge"),
+];
+
+/// Paths in the game directory that are left out of exports by default: worlds, logs, and the
+/// files of the official launcher's layout.
+pub const EXCLUDED: &[&str] = &[
+    "saves",
+    "logs",
+    "crash-reports",
+    "screenshots",
+    vanilla::VERSIONS,
+    vanilla::PROFILES,
+];
+
+/// Options for exporting an instance as a modpack.
+#[derive(Clone, Debug)]
+pub struct ExportOptions {
+    /// The version of the pack (e.g. `1.0.0`).
+    pub version_id: String,
+    /// A short description of the pack.
+    pub summary: Option<String>,
+    /// Files that are downloaded from Modrinth instead of being put in the pack itself.
+    pub files: Vec<ModrinthFile>,
+    /// Paths in the game directory that are left out of the pack.
+    pub exclude: Vec<String>,
+}
+
+// === impl ===
+
+impl ExportOptions {
+    /// Options for exporting everything except the [EXCLUDED] paths into the pack itself.
+    pub fn new(version_id: impl Into<String>) -> Self {
+        Self {
+            version_id: version_id.into(),
+            summary: None,
+            files: vec![],
+            exclude: EXCLUDED.iter().map(|it| it.to_string()).collect(),
+        }
+    }
+}
+
+/// Maps the dependencies of a pack to a new instance. Dependencies other than the game and a mod
+/// loader are returned as unmapped.
+pub fn parse(index: &ModrinthIndex, now: DateTime<Utc>) -> Result<(Instance, Vec<String>)> {
+    if index.game != GAME_DEPENDENCY {
+        return Err(Error::Import(format!("modpack is for {}", index.game)));
+    }
+
+    let game = index
+        .dependencies
+        .get(GAME_DEPENDENCY)
+        .ok_or_else(|| Error::Import("modpack has no game version".to_string()))?;
+
+    let mut instance = Instance::new(&index.name, game, now);
+    let mut unmapped = vec![];
+
+    for (name, version) in &index.dependencies {
+        if name == GAME_DEPENDENCY {
+            continue;
+        }
+
+        match LOADERS.iter().find(|(it, _)| it == name) {
+            Some((_, id)) if instance.loader.is_none() => {
+                instance.loader = Some(InstanceLoader {
+                    id: id.to_string(),
+                    version: version.clone(),
+                });
+            }
+            _ => unmapped.push(format!("Dependency {name} ({version})")),
+        }
+    }
+
+    Ok((instance, unmapped))
+}
+
+/// Downloads a file of a pack, trying its mirrors in order. Both of its checksums are verified.
+async fn download_file(
+    client: &Client,
+    downloads: &DownloadOptions,
+    file: &ModrinthFile,
+    path: &Path,
+) -> Result<()> {
+    let mut result = Err(Error::Import(format!(
+        "file {} has no downloads",
+        file.path
+    )));
+
+    for url in &file.downloads {
+        let url = &downloads.mirror(url);
+        result = async {
+            download::verified(client, url, path, &file.hashes.sha512, Algorithm::Sha512).await?;
+
+            let sha1 = download::sha1(path).await?;
+            if !sha1.eq_ignore_ascii_case(&file.hashes.sha1) {
+                let _ = fs::remove_file(path).await;
+                let expected = file.hashes.sha1.clone();
+                return Err(fetch::Error::ChecksumMismatch(url.to_string(), expected, sha1).into());
+            }
+
+            Ok(())
+        }
+        .await;
+
+        match &result {
+            Ok(()) => break,
+            Err(err) => tracing::warn!("Failed to download {url}: {err}"),
+        }
+    }
+
+    result
+}
+
+/// Downloads the files of an extracted pack into a new instance and applies its overrides.
+async fn install(
+    manager: &mut InstanceManager,
+    client: &Client,
+    downloads: &DownloadOptions,
+    staging: &Path,
+) -> Result<ImportReport> {
+    let index: ModrinthIndex =
+        serde_json::from_str(&fs::read_to_string(staging.join(INDEX)).await?)?;
+    let (instance, unmapped) = parse(&index, Utc::now())?;
+
+    let id = manager.create(instance).await?;
+    let directory = manager.directory(&id);
+
+    let result = async {
+        let files = index
+            .files
+            .iter()
+            .filter(|it| it.is_client())
+            .map(|it| Ok((it, files::destination(&directory, &it.path)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        stream::iter(files)
+            .map(|(file, path)| async move { download_file(client, downloads, file, &path).await })
+            .buffer_unordered(downloads.concurrency)
+            .try_collect::<()>()
+            .await?;
+
+        for overrides in [OVERRIDES, CLIENT_OVERRIDES] {
+            let source = staging.join(overrides);
+            if fs::try_exists(&source).await? {
+                files::copy_dir(&source, &directory).await?;
+            }
+        }
+
+        Ok(())
+    }
+    .await;
+
+    if let Err(err) = result {
+        let _ = manager.delete(&id).await;
+        return Err(err);
+    }
+
+    Ok(ImportReport { id, unmapped })
+}
+
+/// Installs a modpack into a new instance. Files that aren't needed on the client are skipped,
+/// while optional ones are installed.
+#[tracing::instrument(name = "modrinth::import", skip(manager, client, downloads))]
+pub async fn import(
+    manager: &mut InstanceManager,
+    client: &Client,
+    downloads: &DownloadOptions,
+    source: &Path,
+) -> Result<ImportReport> {
+    let name = source.file_stem().unwrap_or_default().to_string_lossy();
+    let staging = directories::CACHE.join("mrpack").join(name.as_ref());
+    let _ = fs::remove_dir_all(&staging).await;
+
+    // modpacks never need links, and ones that point outside of the overrides would end up
+    // pointing somewhere else entirely once they are copied into the game directory
+    let options = ExtractOptions {
+        skip_links: true,
+        ..Default::default()
+    };
+
+    let result = async {
+        archive::extract_as(source, &staging, Format::Zip, options).await?;
+        install(manager, client, downloads, &staging).await
+    }
+    .await;
+
+    let _ = fs::remove_dir_all(&staging).await;
+    result
+}
+
+/// Exports an instance as a modpack. The game directory goes into the overrides, except for the
+/// excluded paths and the files that are downloaded instead.
+#[tracing::instrument(name = "modrinth::export", skip(manager, options))]
+pub async fn export(
+    manager: &InstanceManager,
+    id: &str,
+    archive: &Path,
+    options: ExportOptions,
+) -> Result<()> {
+    let instance = manager
+        .get(id)
+        .ok_or_else(|| Error::NotFound(id.to_string()))?;
+
+    let mut dependencies =
+        BTreeMap::from([(GAME_DEPENDENCY.to_string(), instance.game_version.clone())]);
+
+    if let Some(loader) = &instance.loader {
+        let (name, _) = LOADERS
+            .iter()
+            .find(|(_, it)| *it == loader.id)
+            .ok_or_else(|| Error::Export(format!("mod loader {} has no modpack", loader.id)))?;
+        dependencies.insert(name.to_string(), loader.version.clone());
+    }
+
+    let mut downloaded = HashSet::new();
+    for file in &options.files {
+        downloaded.insert(files::destination(Path::new(""), &file.path)?);
+    }
+
+    let index = ModrinthIndex {
+        format_version: FORMAT_VERSION,
+        game: GAME_DEPENDENCY.to_string(),
+        version_id: options.version_id,
+        name: instance.name.clone(),
+        summary: options.summary,
+        files: options.files,
+        dependencies,
+    };
+
+    let staging = directories::CACHE
+        .join("mrpack")
+        .join(format!("export-{id}"));
+    let _ = fs::remove_dir_all(&staging).await;
+
+    let result = async {
+        fs::create_dir_all(&staging).await?;
+        fs::write(staging.join(INDEX), serde_json::to_string_pretty(&index)?).await?;
+
+        let exclude: Vec<PathBuf> = options.exclude.iter().map(PathBuf::from).collect();
+        let filter =
+            move |path: &Path| !downloaded.contains(path) && !exclude.iter().any(|it| it == path);
+
+        let overrides = staging.join(OVERRIDES);
+        files::copy_dir_filtered(&manager.directory(id), &overrides, filter).await?;
+        archive::create(&staging, archive, CreateOptions::default()).await?;
+        Ok(())
+    }
+    .await;
+
+    let _ = fs::remove_dir_all(&staging).await;
+    result
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    const INDEX_JSON: &str = r#"{
+        "formatVersion": 1,
+        "game": "minecraft",
+        "versionId": "5.0.0",
+        "name": "Fabulously Optimized",
+        "files": [
+            {
+                "path": "mods/sodium.jar",
+                "hashes": {"sha1": "0", "sha512": "0"},
+                "env": {"client": "required", "server": "unsupported"},
+                "downloads": ["https://cdn.modrinth.com/data/AANobbMI/versions/sodium.jar"],
+                "fileSize": 1
+            },
+            {
+                "path": "mods/lithium.jar",
+                "hashes": {"sha1": "0", "sha512": "0"},
+                "env": {"client": "unsupported", "server": "required"},
+                "downloads": ["https://cdn.modrinth.com/data/gvQqBUqZ/versions/lithium.jar"],
+                "fileSize": 1
+            }
+        ],
+        "dependencies": {"minecraft": "1.20.4", "fabric-loader": "0.15.7", "fabric-api": "0.9"}
+    }"#;
+
+    #[test]
+    fn parse() {
+        let index: ModrinthIndex = serde_json::from_str(INDEX_JSON).unwrap();
+        let (instance, unmapped) = super::parse(&index, Utc::now()).unwrap();
+
+        assert_eq!(instance.name, "Fabulously Optimized");
+        assert_eq!(instance.game_version, "1.20.4");
+        assert_eq!(
+            instance.loader,
+            Some(InstanceLoader {
+                id: "fabric".to_string(),
+                version: "0.15.7".to_string()
+            })
+        );
+        assert_eq!(unmapped, ["Dependency fabric-api (0.9)"]);
+
+        let client: Vec<_> = index.files.iter().filter(|it| it.is_client()).collect();
+        assert_eq!(client.len(), 1);
+        assert_eq!(client[0].path, "mods/sodium.jar");
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+
+        let mut manager = InstanceManager::at(root.join("Instances")).await.unwrap();
+        let mut instance = Instance::new("Pack", "1.20.4", Utc::now());
+        instance.loader = Some(InstanceLoader {
+            id: "quilt".to_string(),
+            version: "0.23.1".to_string(),
+        });
+        let id = manager.create(instance).await.unwrap();
+
+        let directory = manager.directory(&id);
+        for path in [
+            "mods/a.jar",
+            "config/a.toml",
+            "saves/world/level.dat",
+            "options.txt",
+        ] {
+            let path = directory.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "data").unwrap();
+        }
+
+        let archive = root.join("Pack.mrpack");
+        let mut options = ExportOptions::new("1.0.0");
+        options.exclude.push("options.txt".to_string());
+        export(&manager, &id, &archive, options).await.unwrap();
+
+        let client = Client::new().await;
+        let report = import(&mut manager, &client, &DownloadOptions::default(), &archive)
+            .await
+            .unwrap();
+        client.destroy().await;
+
+        assert_eq!(report.id, "pack-2");
+        assert!(report.unmapped.is_empty());
+
+        let imported = manager.get(&report.id).unwrap();
+        assert_eq!(imported.game_version, "1.20.4");
+        assert_eq!(imported.loader.as_ref().unwrap().id, "quilt");
+
+        let directory = manager.directory(&report.id);
+        assert!(directory.join("mods/a.jar").is_file());
+        assert!(directory.join("config/a.toml").is_file());
+        assert!(!directory.join("saves").exists());
+        assert!(!directory.join("options.txt").exists());
+    }
+}
@@ -0,0 +1,610 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The mods of an instance: reading their metadata from the JARs in the `mods` directory,
+//! enabling and disabling them, and checking that their dependencies are met before launching.
+//!
+//! Mods are disabled by adding a `.disabled` suffix to their file name, which is what most other
+//! launchers do as well, so that mod loaders skip them.
+
+use std::collections::{BTreeMap, HashSet};
+use std::io;
+use std::path::Path;
+
+use tokio::{fs, task};
+
+use data::core::instance::InstanceLoader;
+use data::core::mods::{InstalledMod, ModDependencyKind, ModInfo, ModProblem};
+use data::web::mods::{
+    FABRIC_METADATA, FORGE_METADATA, FORGE_NESTED, FabricMod, ForgeMods, ForgeNestedJars,
+    JAR_MANIFEST, NEOFORGE_METADATA, QUILT_METADATA, QuiltMod,
+};
+use utils::archive;
+
+use crate::{Error, InstanceManager, Result, files};
+
+/// The directory in the game directory that mod loaders load mods from.
+pub const MODS: &str = "mods";
+
+/// The suffix of the file names of disabled mods.
+pub const DISABLED: &str = ".disabled";
+
+/// How deep JARs nested in other JARs are read.
+const MAX_DEPTH: usize = 4;
+
+/// Reads the version from the manifest of a JAR, which `mods.toml` can refer to.
+fn manifest_version(data: &[u8]) -> Result<Option<String>> {
+    let Some(manifest) = archive::read_from(data, JAR_MANIFEST)? else {
+        return Ok(None);
+    };
+
+    let version = String::from_utf8_lossy(&manifest).lines().find_map(|line| {
+        line.strip_prefix("Implementation-Version:")
+            .map(|it| it.trim().to_string())
+    });
+
+    Ok(version)
+}
+
+/// Reads the mods in a JAR, and adds the mods in JARs nested in it to `nested`. The metadata of
+/// Quilt comes before that of Fabric, and that of NeoForge before that of Forge, as the loaders
+/// prefer them when a JAR has both. This function is blocking!
+fn parse(data: &[u8], depth: usize, nested: &mut Vec<ModInfo>) -> Result<Vec<ModInfo>> {
+    let mut mods = Vec::new();
+    let mut jars = Vec::new();
+
+    if let Some(json) = archive::read_from(data, QUILT_METADATA)? {
+        let value: QuiltMod = serde_json::from_slice(&json)?;
+        jars.extend(value.quilt_loader.jars.iter().cloned());
+        mods.push(value.into());
+    }
+
+    if let Some(json) = archive::read_from(data, FABRIC_METADATA)? {
+        let value: FabricMod = serde_json::from_slice(&json)?;
+        jars.extend(value.jars.iter().map(|it| it.file.clone()));
+        mods.push(value.into());
+    }
+
+    for (name, neoforge) in [(NEOFORGE_METADATA, true), (FORGE_METADATA, false)] {
+        let Some(toml) = archive::read_from(data, name)? else {
+            continue;
+        };
+
+        let toml = String::from_utf8_lossy(&toml);
+        let value: ForgeMods =
+            toml::from_str(&toml).map_err(|err| Error::Metadata(format!("{name}: {err}")))?;
+        let version = manifest_version(data)?;
+        mods.extend(ModInfo::forge(value, neoforge, version.as_deref()));
+    }
+
+    if let Some(json) = archive::read_from(data, FORGE_NESTED)? {
+        let value: ForgeNestedJars = serde_json::from_slice(&json)?;
+        jars.extend(value.jars.into_iter().map(|it| it.path));
+    }
+
+    if depth < MAX_DEPTH {
+        for path in jars {
+            let Some(jar) = archive::read_from(data, &path)? else {
+                continue;
+            };
+
+            match parse(&jar, depth + 1, nested) {
+                Ok(mods) => nested.extend(mods),
+                Err(err) => tracing::warn!("Failed to read nested mod {path}: {err}"),
+            }
+        }
+    }
+
+    Ok(mods)
+}
+
+/// Reads the metadata of a mod JAR, along with that of the JARs nested in it.
+pub async fn read(path: &Path) -> Result<InstalledMod> {
+    let file = path
+        .file_name()
+        .map(|it| it.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let enabled = !file.ends_with(DISABLED);
+
+    let data = fs::read(path).await?;
+    let (mods, nested) = task::spawn_blocking(move || {
+        let mut nested = Vec::new();
+        parse(&data, 0, &mut nested).map(|mods| (mods, nested))
+    })
+    .await
+    .expect("blocking thread panicked")?;
+
+    Ok(InstalledMod {
+        file,
+        enabled,
+        mods,
+        nested,
+    })
+}
+
+/// Lists the mods in a game directory, enabled or not, sorted by their file name. Files whose
+/// metadata can't be read are listed without any mods.
+pub async fn scan(directory: &Path) -> Result<Vec<InstalledMod>> {
+    let directory = directory.join(MODS);
+    if !fs::try_exists(&directory).await? {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    let mut entries = fs::read_dir(&directory).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let Ok(file) = entry.file_name().into_string() else {
+            continue;
+        };
+
+        let jar = file.strip_suffix(DISABLED).unwrap_or(&file);
+        if jar.ends_with(".jar") && fs::metadata(entry.path()).await?.is_file() {
+            files.push(file);
+        }
+    }
+
+    files.sort_unstable();
+
+    let mut mods = Vec::with_capacity(files.len());
+    for file in files {
+        let path = directory.join(&file);
+        let installed = read(&path).await.unwrap_or_else(|err| {
+            tracing::warn!("Failed to read mod {file}: {err}");
+            InstalledMod {
+                enabled: !file.ends_with(DISABLED),
+                file,
+                mods: Vec::new(),
+                nested: Vec::new(),
+            }
+        });
+
+        mods.push(installed);
+    }
+
+    Ok(mods)
+}
+
+/// Enables or disables a mod in a game directory by renaming it, and returns its new file name.
+pub async fn set_enabled(directory: &Path, file: &str, enabled: bool) -> Result<String> {
+    let mods = directory.join(MODS);
+    let source = files::destination(&mods, file)?;
+
+    let name = match (enabled, file.strip_suffix(DISABLED)) {
+        (true, Some(name)) => name.to_string(),
+        (false, None) => format!("{file}{DISABLED}"),
+        _ => return Ok(file.to_string()),
+    };
+
+    let destination = files::destination(&mods, &name)?;
+    if fs::try_exists(&destination).await? {
+        let message = format!("{name} already exists");
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, message).into());
+    }
+
+    fs::rename(source, destination).await?;
+    Ok(name)
+}
+
+/// The IDs that the game and the mod loader are known by in dependencies, with their versions.
+/// An unknown version matches any range.
+fn environment<'a>(
+    game_version: &'a str,
+    loader: Option<&'a InstanceLoader>,
+) -> BTreeMap<&'a str, Vec<Option<&'a str>>> {
+    let mut available = BTreeMap::new();
+    available.insert("minecraft", vec![Some(game_version)]);
+    // The Java runtime is picked when launching, so it is always taken to be suitable.
+    available.insert("java", vec![None]);
+
+    if let Some(loader) = loader {
+        let version = Some(loader.version.as_str());
+        match loader.id.as_str() {
+            "fabric" => {
+                available.insert("fabricloader", vec![version]);
+            }
+            "quilt" => {
+                available.insert("quilt_loader", vec![version]);
+                // Quilt provides the Fabric loader as well, in a version that fits the mods.
+                available.insert("fabricloader", vec![None]);
+            }
+            id @ ("forge" | "neoforge") => {
+                available.insert(id, vec![version]);
+            }
+            _ => {}
+        }
+    }
+
+    available
+}
+
+/// Checks the enabled mods for problems that would keep the game from launching: missing,
+/// mismatched and incompatible dependencies, mods that are installed more than once, and mods for
+/// a different mod loader than the one of the instance.
+pub fn check(
+    game_version: &str,
+    loader: Option<&InstanceLoader>,
+    mods: &[InstalledMod],
+) -> Vec<ModProblem> {
+    let loads = |info: &ModInfo| loader.is_some_and(|it| info.platform.is_loaded_by(&it.id));
+
+    let mut problems = Vec::new();
+    let mut available = environment(game_version, loader);
+    let mut loaded = Vec::new();
+    let mut files = BTreeMap::<&str, Vec<String>>::new();
+
+    for installed in mods.iter().filter(|it| it.enabled) {
+        let Some(first) = installed.mods.first() else {
+            continue;
+        };
+
+        // When a JAR has metadata for more than one platform, the loader only reads one of them.
+        let mut seen = HashSet::new();
+        let mods: Vec<_> = (installed.mods.iter())
+            .filter(|it| loads(it) && seen.insert(it.id.as_str()))
+            .collect();
+
+        if mods.is_empty() {
+            problems.push(ModProblem::WrongLoader {
+                file: installed.file.clone(),
+                platform: first.platform,
+            });
+            continue;
+        }
+
+        for info in &mods {
+            files
+                .entry(info.id.as_str())
+                .or_default()
+                .push(installed.file.clone());
+        }
+
+        // Nested mods can be in more than one JAR, in which case the loader picks one of them.
+        loaded.extend(mods);
+        loaded.extend(installed.nested.iter().filter(|it| loads(it)));
+    }
+
+    for (id, files) in files {
+        if files.len() > 1 {
+            problems.push(ModProblem::Duplicate {
+                id: id.to_string(),
+                files,
+            });
+        }
+    }
+
+    for info in &loaded {
+        for id in std::iter::once(&info.id).chain(&info.provides) {
+            let versions = available.entry(id.as_str()).or_default();
+            versions.push(Some(info.version.as_str()));
+        }
+    }
+
+    for info in &loaded {
+        for dependency in info.dependencies.iter().filter(|it| it.id != info.id) {
+            let versions = available.get(dependency.id.as_str());
+            let matches = |it: &Option<&str>| it.is_none_or(|it| dependency.versions.matches(it));
+
+            let problem = match (dependency.kind, versions) {
+                (ModDependencyKind::Required, None) => ModProblem::Missing {
+                    id: info.id.clone(),
+                    dependency: dependency.id.clone(),
+                    versions: dependency.versions.clone(),
+                },
+                (ModDependencyKind::Incompatible, Some(versions)) => {
+                    let found = versions.iter().flatten().find(|it| matches(&Some(it)));
+                    let Some(version) = found else {
+                        continue;
+                    };
+
+                    ModProblem::Incompatible {
+                        id: info.id.clone(),
+                        other: dependency.id.clone(),
+                        version: version.to_string(),
+                    }
+                }
+                (ModDependencyKind::Incompatible, None) => continue,
+                (_, Some(versions)) if !versions.iter().any(matches) => ModProblem::Mismatch {
+                    id: info.id.clone(),
+                    dependency: dependency.id.clone(),
+                    versions: dependency.versions.clone(),
+                    found: versions
+                        .iter()
+                        .flatten()
+                        .copied()
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                },
+                _ => continue,
+            };
+
+            if !problems.contains(&problem) {
+                problems.push(problem);
+            }
+        }
+    }
+
+    problems
+}
+
+/// Checks the mods of an instance for problems, before launching it.
+pub async fn check_instance(manager: &InstanceManager, id: &str) -> Result<Vec<ModProblem>> {
+    let instance = manager
+        .get(id)
+        .ok_or_else(|| Error::NotFound(id.to_string()))?;
+    let mods = scan(&manager.directory(id)).await?;
+
+    Ok(check(
+        &instance.game_version,
+        instance.loader.as_ref(),
+        &mods,
+    ))
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use data::core::mods::{ModDependency, ModPlatform, ModVersionRange};
+    use tempfile::TempDir;
+    use utils::archive::CreateOptions;
+
+    use super::*;
+
+    /// Packs files into a JAR.
+    async fn jar(root: &Path, path: PathBuf, entries: &[(&str, &[u8])]) {
+        let source = root.join("source");
+        for (name, contents) in entries {
+            let file = source.join(name);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(file, contents).unwrap();
+        }
+
+        archive::create(&source, &path, CreateOptions::default())
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(source).unwrap();
+    }
+
+    fn info(id: &str, version: &str, platform: ModPlatform) -> ModInfo {
+        ModInfo {
+            id: id.to_string(),
+            name: None,
+            version: version.to_string(),
+            platform,
+            provides: vec![],
+            dependencies: vec![],
+        }
+    }
+
+    fn dependency(id: &str, range: &str, kind: ModDependencyKind) -> ModDependency {
+        ModDependency {
+            id: id.to_string(),
+            versions: ModVersionRange::Predicates(vec![range.to_string()]),
+            kind,
+        }
+    }
+
+    fn installed(file: &str, mods: Vec<ModInfo>) -> InstalledMod {
+        InstalledMod {
+            file: file.to_string(),
+            enabled: true,
+            mods,
+            nested: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn scan_and_toggle() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let mods = root.join(MODS);
+        std::fs::create_dir_all(&mods).unwrap();
+
+        let nested = root.join("api.jar");
+        jar(
+            root,
+            nested.clone(),
+            &[(
+                FABRIC_METADATA,
+                br#"{"id": "fabric-api-base", "version": "0.4.36"}"#,
+            )],
+        )
+        .await;
+        let nested = std::fs::read(nested).unwrap();
+
+        let fabric = br#"{
+            "schemaVersion": 1,
+            "id": "sodium",
+            "version": "0.5.8+mc1.20.4",
+            "name": "Sodium",
+            "depends": {"minecraft": ["1.20.3", "1.20.4"], "fabricloader": ">=0.12.0"},
+            "breaks": {"optifabric": "*"},
+            "jars": [{"file": "META-INF/jars/api.jar"}]
+        }"#;
+        jar(
+            root,
+            mods.join("sodium.jar"),
+            &[
+                (FABRIC_METADATA, fabric),
+                ("META-INF/jars/api.jar", &nested),
+            ],
+        )
+        .await;
+
+        let forge = br#"
+            modLoader = "javafml"
+            loaderVersion = "[47,)"
+
+            [[mods]]
+            modId = "jei"
+            version = "${file.jarVersion}"
+            displayName = "Just Enough Items"
+
+            [[dependencies.jei]]
+            modId = "forge"
+            mandatory = true
+            versionRange = "[47.1.3,)"
+            side = "BOTH"
+
+            [[dependencies.jei]]
+            modId = "optifine"
+            type = "INCOMPATIBLE"
+        "#;
+        let manifest = b"Manifest-Version: 1.0\r\nImplementation-Version: 15.3.0.4\r\n";
+        jar(
+            root,
+            mods.join("jei.jar.disabled"),
+            &[(FORGE_METADATA, forge), (JAR_MANIFEST, manifest)],
+        )
+        .await;
+        std::fs::write(mods.join("readme.txt"), "not a mod").unwrap();
+
+        let scanned = scan(root).await.unwrap();
+        assert_eq!(scanned.len(), 2);
+
+        let jei = &scanned[0];
+        assert_eq!(jei.file, "jei.jar.disabled");
+        assert!(!jei.enabled);
+        assert_eq!(jei.mods[0].version, "15.3.0.4");
+        assert_eq!(jei.mods[0].platform, ModPlatform::Forge);
+        assert_eq!(
+            jei.mods[0].dependencies[0].kind,
+            ModDependencyKind::Required
+        );
+        assert_eq!(
+            jei.mods[0].dependencies[1].kind,
+            ModDependencyKind::Incompatible
+        );
+
+        let sodium = &scanned[1];
+        assert!(sodium.enabled);
+        assert_eq!(sodium.mods[0].name.as_deref(), Some("Sodium"));
+        assert_eq!(sodium.mods[0].dependencies.len(), 3);
+        assert_eq!(sodium.nested[0].id, "fabric-api-base");
+
+        assert_eq!(
+            set_enabled(root, "jei.jar.disabled", true).await.unwrap(),
+            "jei.jar"
+        );
+        assert_eq!(set_enabled(root, "jei.jar", true).await.unwrap(), "jei.jar");
+        assert_eq!(
+            set_enabled(root, "sodium.jar", false).await.unwrap(),
+            "sodium.jar.disabled"
+        );
+        assert!(mods.join("jei.jar").exists());
+        assert!(mods.join("sodium.jar.disabled").exists());
+        assert!(set_enabled(root, "../sodium.jar", false).await.is_err());
+    }
+
+    #[test]
+    fn problems() {
+        let loader = InstanceLoader {
+            id: "fabric".to_string(),
+            version: "0.15.7".to_string(),
+        };
+
+        let mut sodium = info("sodium", "0.5.8", ModPlatform::Fabric);
+        sodium.dependencies = vec![
+            dependency("minecraft", "1.20.x", ModDependencyKind::Required),
+            dependency("fabricloader", ">=0.12.0", ModDependencyKind::Required),
+            dependency("java", ">=17", ModDependencyKind::Required),
+            dependency("optifabric", "*", ModDependencyKind::Incompatible),
+        ];
+
+        let mut iris = info("iris", "1.6.17", ModPlatform::Fabric);
+        iris.dependencies = vec![
+            dependency("sodium", "0.5.x", ModDependencyKind::Required),
+            dependency("fabric-api", "*", ModDependencyKind::Required),
+        ];
+
+        let mut old = info("lithium", "0.11.2", ModPlatform::Fabric);
+        old.dependencies = vec![dependency(
+            "minecraft",
+            "1.20.1",
+            ModDependencyKind::Required,
+        )];
+
+        let mods = vec![
+            installed("iris.jar", vec![iris]),
+            installed("lithium.jar", vec![old]),
+            installed(
+                "optifabric.jar",
+                vec![info("optifabric", "1.14.3", ModPlatform::Fabric)],
+            ),
+            installed("sodium.jar", vec![sodium.clone()]),
+            installed(
+                "sodium-old.jar",
+                vec![info("sodium", "0.5.3", ModPlatform::Fabric)],
+            ),
+            installed("jei.jar", vec![info("jei", "15.3.0.4", ModPlatform::Forge)]),
+            InstalledMod {
+                enabled: false,
+                ..installed(
+                    "disabled.jar",
+                    vec![info("jei", "15.3.0.4", ModPlatform::Forge)],
+                )
+            },
+        ];
+
+        assert_eq!(
+            check("1.20.4", Some(&loader), &mods),
+            vec![
+                ModProblem::WrongLoader {
+                    file: "jei.jar".to_string(),
+                    platform: ModPlatform::Forge,
+                },
+                ModProblem::Duplicate {
+                    id: "sodium".to_string(),
+                    files: vec!["sodium.jar".to_string(), "sodium-old.jar".to_string()],
+                },
+                ModProblem::Missing {
+                    id: "iris".to_string(),
+                    dependency: "fabric-api".to_string(),
+                    versions: ModVersionRange::Predicates(vec!["*".to_string()]),
+                },
+                ModProblem::Mismatch {
+                    id: "lithium".to_string(),
+                    dependency: "minecraft".to_string(),
+                    versions: ModVersionRange::Predicates(vec!["1.20.1".to_string()]),
+                    found: "1.20.4".to_string(),
+                },
+                ModProblem::Incompatible {
+                    id: "sodium".to_string(),
+                    other: "optifabric".to_string(),
+                    version: "1.14.3".to_string(),
+                },
+            ]
+        );
+
+        // Quilt loads Fabric mods and provides the Fabric loader, and provided IDs count as well.
+        let quilt = InstanceLoader {
+            id: "quilt".to_string(),
+            version: "0.23.1".to_string(),
+        };
+        let mut api = info("qsl", "7.0.0", ModPlatform::Quilt);
+        api.provides = vec!["fabric-api".to_string()];
+        let mut iris = info("iris", "1.6.17", ModPlatform::Fabric);
+        iris.dependencies = vec![dependency("fabric-api", "*", ModDependencyKind::Required)];
+
+        let mods = vec![
+            installed("iris.jar", vec![iris]),
+            installed("qsl.jar", vec![api]),
+            installed("sodium.jar", vec![sodium]),
+        ];
+        assert_eq!(check("1.20.4", Some(&quilt), &mods), vec![]);
+    }
+}
@@ -0,0 +1,41 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Storage of the global launcher settings (see [Settings]).
+
+use data::core::instance::Instance;
+use data::core::settings::{EffectiveSettings, Settings};
+use persistence::FileRegistry;
+
+use crate::Result;
+
+/// The file in the config directory that the global settings are stored in.
+const SETTINGS_FILE: &str = "Settings.toml";
+
+pub type SettingsRegistry = FileRegistry<Settings>;
+
+/// Opens the global settings. They are created with nothing set (so everything has the built-in
+/// default) if they don't exist yet.
+pub async fn open() -> Result<SettingsRegistry> {
+    Ok(FileRegistry::new(SETTINGS_FILE).await?)
+}
+
+/// Resolves the effective settings, optionally for an instance (see [Settings::resolve]).
+pub async fn effective(
+    registry: &SettingsRegistry,
+    instance: Option<&Instance>,
+) -> EffectiveSettings {
+    registry.get().await.resolve(instance)
+}
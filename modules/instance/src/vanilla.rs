@@ -0,0 +1,285 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Compatibility with the game directory layout of the official launcher. Mod loader installers
+//! expect a `launcher_profiles.json`, and the version they install for in
+//! `versions/<id>/<id>.json`. They put the versions they install next to it, which usually only
+//! list what they change and inherit the rest (`inheritsFrom`).
+
+use std::path::{Component, Path, PathBuf};
+
+use tokio::fs;
+
+use data::core::game::GameVersion;
+use data::core::loader::ModLoaderVersion;
+use data::web::vanilla::VanillaVersion;
+
+use crate::{Error, Result};
+
+/// The file that the official launcher keeps its profiles in.
+pub const PROFILES: &str = "launcher_profiles.json";
+
+/// The directory that versions are kept in.
+pub const VERSIONS: &str = "versions";
+
+/// The path of the JSON of a version in a game directory. IDs come from the JSONs themselves (e.g.
+/// `inheritsFrom`), so ones that aren't a plain directory name are rejected.
+pub fn version_path(directory: &Path, id: &str) -> Result<PathBuf> {
+    let safe = !id.contains(['/', '\\'])
+        && matches!(
+            Path::new(id).components().next(),
+            Some(Component::Normal(_))
+        );
+
+    match safe {
+        true => Ok(directory.join(VERSIONS).join(id).join(format!("{id}.json"))),
+        false => Err(Error::UnsafePath(id.to_string())),
+    }
+}
+
+/// Creates an empty `launcher_profiles.json` and the versions directory, unless they exist.
+pub async fn prepare(directory: &Path) -> Result<()> {
+    fs::create_dir_all(directory.join(VERSIONS)).await?;
+
+    let path = directory.join(PROFILES);
+    if !fs::try_exists(&path).await? {
+        let profiles = serde_json::json!({ "profiles": {}, "settings": {}, "version": 3 });
+        fs::write(&path, serde_json::to_string_pretty(&profiles)?).await?;
+    }
+
+    Ok(())
+}
+
+/// Writes a version to a game directory, combined with a mod loader if one is given. The combined
+/// version is named after both (e.g. `1.20.4-0.15.7`). Returns the ID of the written version.
+pub async fn export(
+    directory: &Path,
+    version: GameVersion,
+    loader: Option<ModLoaderVersion>,
+) -> Result<String> {
+    prepare(directory).await?;
+
+    let version = match loader {
+        Some(loader) => {
+            let id = format!("{}-{}", version.id, loader.loader_version);
+            GameVersion {
+                id,
+                ..version.with_loader(loader)
+            }
+        }
+        None => version,
+    };
+
+    let id = version.id.clone();
+    let path = version_path(directory, &id)?;
+    let json = serde_json::to_string_pretty(&VanillaVersion::try_from(version)?)?;
+
+    fs::create_dir_all(directory.join(VERSIONS).join(&id)).await?;
+    fs::write(path, json).await?;
+    Ok(id)
+}
+
+/// Places the game's JAR file next to the JSON of a version. Forge's installer reads the game from
+/// there.
+pub async fn export_jar(directory: &Path, id: &str, jar: &Path) -> Result<()> {
+    let destination = version_path(directory, id)?.with_file_name(format!("{id}.jar"));
+    fs::create_dir_all(directory.join(VERSIONS).join(id)).await?;
+    fs::copy(jar, destination).await?;
+    Ok(())
+}
+
+/// Lists the IDs of the versions in a game directory, sorted.
+pub async fn list(directory: &Path) -> Result<Vec<String>> {
+    let mut versions = vec![];
+    let Ok(mut stream) = fs::read_dir(directory.join(VERSIONS)).await else {
+        return Ok(versions);
+    };
+
+    while let Some(entry) = stream.next_entry().await? {
+        let id = entry.file_name().to_string_lossy().to_string();
+        if fs::try_exists(version_path(directory, &id)?).await? {
+            versions.push(id);
+        }
+    }
+
+    versions.sort();
+    Ok(versions)
+}
+
+/// Reads the JSON of a version as-is, without what it inherits.
+pub async fn read(directory: &Path, id: &str) -> Result<VanillaVersion> {
+    let text = fs::read_to_string(version_path(directory, id)?).await?;
+    Ok(serde_json::from_str(&text)?)
+}
+
+/// Reads a version, along with all versions that it inherits from.
+pub async fn load(directory: &Path, id: &str) -> Result<GameVersion> {
+    let mut version = read(directory, id).await?;
+    let mut seen = vec![id.to_string()];
+
+    while let Some(parent) = version.inherits_from.clone() {
+        if seen.contains(&parent) {
+            return Err(Error::Inheritance(parent));
+        }
+
+        version = version.inherit(read(directory, &parent).await?);
+        seen.push(parent);
+    }
+
+    Ok(GameVersion::try_from(version)?)
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use url::Url;
+
+    use data::core::conditional::MaybeConditional;
+    use data::core::game::{GameVersionAssetIndex, GameVersionStability};
+    use data::core::library::{Library, LibraryDownloadable, LibrarySource};
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn file(path: &str) -> LibraryDownloadable {
+        LibraryDownloadable {
+            path: path.to_string(),
+            checksum: "0".repeat(40),
+            size: 1,
+            url: Url::parse("https://libraries.minecraft.net/")
+                .unwrap()
+                .join(path)
+                .unwrap(),
+            source: LibrarySource::Verified,
+        }
+    }
+
+    fn library(name: &str) -> MaybeConditional<Library> {
+        let name: data::core::maven::MavenIdentifier = name.parse().unwrap();
+        MaybeConditional::Unconditional(Library {
+            file: file(&name.path()),
+            name,
+            extract: None,
+        })
+    }
+
+    fn version() -> GameVersion {
+        GameVersion {
+            id: "1.20.4".to_string(),
+            release_date: DateTime::default(),
+            stability: GameVersionStability::Release,
+            java_version: ">=17".parse().unwrap(),
+            main_class: "net.minecraft.client.main.Main".to_string(),
+            libraries: vec![library("org.ow2.asm:asm:9.3")],
+            java_arguments: ["-cp", "${classpath}"]
+                .map(|it| MaybeConditional::Unconditional(it.to_string()))
+                .to_vec(),
+            game_arguments: vec![],
+            logging: None,
+            client: file("com/mojang/minecraft/1.20.4/minecraft-1.20.4-client.jar"),
+            server: None,
+            asset_index: GameVersionAssetIndex {
+                id: "12".to_string(),
+                file: file("indexes/12.json"),
+                total_size: 1,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn export_and_load() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+
+        let loader = ModLoaderVersion {
+            loader_version: "0.15.7".to_string(),
+            game_version: "1.20.4".to_string(),
+            libraries: vec![library("org.ow2.asm:asm:9.6")],
+            main_class: "net.fabricmc.loader.impl.launch.knot.KnotClient".to_string(),
+            game_arguments: vec![],
+            java_arguments: vec![],
+        };
+
+        assert_eq!(export(root, version(), None).await.unwrap(), "1.20.4");
+        assert_eq!(
+            export(root, version(), Some(loader)).await.unwrap(),
+            "1.20.4-0.15.7"
+        );
+        assert!(root.join(PROFILES).is_file());
+
+        // What an installer would write: a version that only has what it changes.
+        std::fs::create_dir_all(root.join("versions/quilt")).unwrap();
+        std::fs::write(
+            version_path(root, "quilt").unwrap(),
+            r#"{
+                "id": "quilt",
+                "inheritsFrom": "1.20.4",
+                "mainClass": "org.quiltmc.loader.impl.launch.knot.KnotClient",
+                "libraries": [{"name": "org.quiltmc:quilt-loader:0.23.1", "url": "https://maven.quiltmc.org/repository/release/"}]
+            }"#,
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join("versions/loop")).unwrap();
+        std::fs::write(
+            version_path(root, "loop").unwrap(),
+            r#"{"id": "loop", "inheritsFrom": "loop"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            list(root).await.unwrap(),
+            ["1.20.4", "1.20.4-0.15.7", "loop", "quilt"]
+        );
+
+        let combined = load(root, "1.20.4-0.15.7").await.unwrap();
+        assert_eq!(
+            combined.main_class,
+            "net.fabricmc.loader.impl.launch.knot.KnotClient"
+        );
+        assert_eq!(combined.libraries.len(), 1);
+        assert_eq!(combined.libraries[0].value().name.version, "9.6");
+
+        let quilt = load(root, "quilt").await.unwrap();
+        assert_eq!(quilt.libraries.len(), 2);
+        assert_eq!(
+            quilt.libraries[0].value().file.url.as_str(),
+            "https://maven.quiltmc.org/repository/release/org/quiltmc/quilt-loader/0.23.1/quilt-loader-0.23.1.jar"
+        );
+        assert_eq!(quilt.client.path, version().client.path);
+        assert_eq!(quilt.java_version, version().java_version);
+
+        assert!(matches!(
+            load(root, "loop").await,
+            Err(Error::Inheritance(_))
+        ));
+
+        // Versions can't reach outside of the versions directory
+        std::fs::create_dir_all(root.join("versions/escape")).unwrap();
+        std::fs::write(
+            version_path(root, "escape").unwrap(),
+            r#"{"id": "escape", "inheritsFrom": "../../escape"}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            load(root, "escape").await,
+            Err(Error::UnsafePath(_))
+        ));
+        for id in ["..", ".", "", "a/b", "a\\b", "/escape"] {
+            assert!(version_path(root, id).is_err(), "{id}");
+        }
+    }
+}
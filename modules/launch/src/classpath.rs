@@ -0,0 +1,51 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::path::PathBuf;
+
+use tokio::fs;
+
+use crate::{Error, LaunchOptions, Result};
+
+/// The separator between class path entries on the current platform.
+pub const SEPARATOR: &str = if cfg!(windows) { ";" } else { ":" };
+
+/// Builds the class path from the libraries of the version (which must all be installed already),
+/// followed by the game's own JAR file.
+pub async fn build(options: &LaunchOptions) -> Result<Vec<PathBuf>> {
+    let mut classpath = Vec::with_capacity(options.version.libraries.len() + 1);
+
+    let libraries = options.version.libraries.iter();
+    let libraries = libraries.map(|it| options.libraries.join(&it.file.path));
+
+    for path in libraries.chain([options.client.clone()]) {
+        if !fs::try_exists(&path).await? {
+            return Err(Error::MissingLibrary(path));
+        }
+
+        classpath.push(path);
+    }
+
+    Ok(classpath)
+}
+
+/// Joins class path entries into a single string, as expected by the `-cp` argument.
+pub fn join(classpath: &[PathBuf]) -> String {
+    classpath
+        .iter()
+        .map(|it| it.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(SEPARATOR)
+}
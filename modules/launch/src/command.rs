@@ -0,0 +1,236 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::path::{Path, PathBuf};
+
+use data::core::template::{SubstitutionMode, Variable, Variables};
+
+use crate::{AssetLayout, LAUNCHER_NAME, LAUNCHER_VERSION, LaunchOptions, Result, classpath};
+
+/// A fully assembled command line, ready to be spawned.
+#[derive(Clone, Debug)]
+pub struct LaunchCommand {
+    /// The Java executable.
+    pub program: PathBuf,
+    /// All arguments: the JVM arguments, the main class, and the game arguments.
+    pub arguments: Vec<String>,
+    /// The working directory of the game.
+    pub directory: PathBuf,
+    /// The variables that were used to build the command, kept to redact secrets when logging.
+    variables: Variables,
+}
+
+impl LaunchCommand {
+    /// The whole command line as a single string, with all secrets redacted. Safe to be logged.
+    pub fn redacted(&self) -> String {
+        let command = [self.program.to_string_lossy().to_string()]
+            .into_iter()
+            .chain(self.arguments.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        self.variables.redact(&command)
+    }
+}
+
+/// Assembles the values of all variables that can appear in the arguments of the version.
+pub fn variables(options: &LaunchOptions, classpath: &[PathBuf], natives: &Path) -> Variables {
+    let path = |path: &Path| path.to_string_lossy().to_string();
+
+    // Old versions read their assets by their names instead of from the store
+    let index = &options.version.asset_index.id;
+    let game_assets = match options.asset_layout {
+        AssetLayout::Objects => options.assets.clone(),
+        AssetLayout::Virtual => options.assets.join("virtual").join(index),
+        AssetLayout::Resources => options.instance.join("resources"),
+    };
+
+    let mut variables = Variables::new();
+    variables
+        .set_account(&options.account)
+        .set(Variable::AuthXuid, "0")
+        .set(Variable::ClientId, "0")
+        .set(Variable::VersionName, &options.version.id)
+        .set(Variable::VersionType, LAUNCHER_NAME)
+        .set(Variable::GameDirectory, path(&options.instance))
+        .set(Variable::AssetsRoot, path(&options.assets))
+        .set(Variable::AssetsIndexName, index)
+        .set(Variable::GameAssets, path(&game_assets))
+        .set(Variable::Classpath, classpath::join(classpath))
+        .set(Variable::ClasspathSeparator, classpath::SEPARATOR)
+        .set(Variable::LibraryDirectory, path(&options.libraries))
+        .set(Variable::NativesDirectory, path(natives))
+        .set(Variable::LauncherName, LAUNCHER_NAME)
+        .set(Variable::LauncherVersion, LAUNCHER_VERSION);
+
+    variables
+}
+
+/// Substitutes the arguments of the version and assembles them into a [LaunchCommand].
+pub fn build(
+    options: &LaunchOptions,
+    classpath: &[PathBuf],
+    natives: &Path,
+) -> Result<LaunchCommand> {
+    let variables = variables(options, classpath, natives);
+
+    let mode = SubstitutionMode::Strict;
+
+    let mut arguments = variables.substitute_all(&options.version.java_arguments, mode)?;
+    if let Some(tuning) = &options.tuning {
+        arguments = tuning.merge(&arguments);
+    }
+
+    // The logging config makes the game write log4j events instead of plain text (see log4j).
+    if let Some(logging) = &options.version.logging {
+        let config = options.assets.join(&logging.file.path);
+        match config.exists() {
+            true => arguments.push(
+                logging
+                    .argument
+                    .replace("${path}", &config.to_string_lossy()),
+            ),
+            false => tracing::warn!("Logging config is not installed: {}", config.display()),
+        }
+    }
+
+    arguments.push(options.version.main_class.clone());
+    arguments.extend(variables.substitute_all(&options.version.game_arguments, mode)?);
+
+    Ok(LaunchCommand {
+        program: options.java.clone(),
+        arguments,
+        directory: options.instance.clone(),
+        variables,
+    })
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use semver::VersionReq;
+
+    use data::core::auth::{Account, AccountCredentials};
+    use data::core::game::{GameVersionAssetIndex, ResolvedGameVersion};
+    use data::core::library::{LibraryDownloadable, LibrarySource};
+
+    use super::*;
+
+    fn file(path: &str) -> LibraryDownloadable {
+        LibraryDownloadable {
+            path: path.to_string(),
+            checksum: "0".repeat(40),
+            size: 0,
+            url: "https://piston-data.mojang.com/".parse().unwrap(),
+            source: LibrarySource::Verified,
+        }
+    }
+
+    fn options() -> LaunchOptions {
+        let version = ResolvedGameVersion {
+            id: "1.20.4".to_string(),
+            java_version: VersionReq::parse(">=17").unwrap(),
+            main_class: "net.minecraft.client.main.Main".to_string(),
+            libraries: vec![],
+            java_arguments: vec![
+                "-Djava.library.path=${natives_directory}".to_string(),
+                "-cp".to_string(),
+                "${classpath}".to_string(),
+            ],
+            game_arguments: vec![
+                "--username".to_string(),
+                "${auth_player_name}".to_string(),
+                "--accessToken".to_string(),
+                "${auth_access_token}".to_string(),
+                "--assetIndex".to_string(),
+                "${assets_index_name}".to_string(),
+            ],
+            logging: None,
+            client: file("com/mojang/minecraft/1.20.4/minecraft-1.20.4-client.jar"),
+            asset_index: GameVersionAssetIndex {
+                id: "12".to_string(),
+                file: file("indexes/12.json"),
+                total_size: 0,
+            },
+        };
+
+        let account = Account {
+            id: "069a79f444e94726a5befca90e38aaf5".to_string(),
+            username: "Notch".to_string(),
+            has_profile: true,
+            token: "very-secret-token".to_string(),
+            expires: None,
+            credentials: AccountCredentials::Offline,
+        };
+
+        let mut options = LaunchOptions::new(version, "/usr/bin/java", account, "/tmp/instance");
+        options.libraries = PathBuf::from("/tmp/libraries");
+        options.client = PathBuf::from("/tmp/client.jar");
+        options
+    }
+
+    #[test]
+    fn build() {
+        let classpath = [
+            PathBuf::from("/tmp/libraries/a.jar"),
+            PathBuf::from("/tmp/client.jar"),
+        ];
+
+        let command = super::build(&options(), &classpath, Path::new("/tmp/natives")).unwrap();
+        let separator = classpath::SEPARATOR;
+
+        assert_eq!(
+            command.arguments,
+            [
+                "-Djava.library.path=/tmp/natives",
+                "-cp",
+                &format!("/tmp/libraries/a.jar{separator}/tmp/client.jar"),
+                "net.minecraft.client.main.Main",
+                "--username",
+                "Notch",
+                "--accessToken",
+                "very-secret-token",
+                "--assetIndex",
+                "12",
+            ]
+        );
+
+        assert!(!command.redacted().contains("very-secret-token"));
+        assert!(command.redacted().contains("--accessToken <redacted>"));
+    }
+
+    #[test]
+    fn game_assets() {
+        let mut options = options();
+        options.assets = PathBuf::from("/tmp/assets");
+        let game_assets = |options: &LaunchOptions| {
+            let variables = variables(options, &[], Path::new("/tmp/natives"));
+            variables.get(Variable::GameAssets).map(PathBuf::from)
+        };
+
+        assert_eq!(game_assets(&options), Some(PathBuf::from("/tmp/assets")));
+        options.asset_layout = AssetLayout::Virtual;
+        assert_eq!(
+            game_assets(&options),
+            Some(PathBuf::from("/tmp/assets/virtual/12"))
+        );
+        options.asset_layout = AssetLayout::Resources;
+        assert_eq!(
+            game_assets(&options),
+            Some(PathBuf::from("/tmp/instance/resources"))
+        );
+    }
+}
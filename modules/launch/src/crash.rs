@@ -0,0 +1,627 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Analysis of game crashes. After the game exits abnormally, [analyze] looks for the newest crash
+//! report written by the game (`crash-reports/crash-*.txt`) and the newest fatal error log written
+//! by the JVM (`hs_err_pid*.log`), extracts the useful parts of them, and matches everything that
+//! is known about the crash against a table of known causes to come up with [Diagnosis]es.
+
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use semver::{Version, VersionReq};
+
+use crate::Result;
+use crate::log4j::{Level, LogBuffer};
+
+/// Mod IDs that show up as suspects in crash reports but aren't actually mods.
+const NOT_MODS: &[&str] = &["minecraft", "forge", "neoforge", "fabricloader", "java"];
+
+/// A crash report written by the game itself.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CrashReport {
+    /// Where the crash report was found.
+    pub path: PathBuf,
+    /// The short description of what the game was doing (e.g. "Rendering overlay").
+    pub description: Option<String>,
+    /// The stack trace of the exception that crashed the game.
+    pub stack_trace: Option<String>,
+    /// The IDs (or names) of mods that the mod loader suspects to be involved.
+    pub suspected_mods: Vec<String>,
+    /// The version of Java that the game ran on.
+    pub java_version: Option<String>,
+}
+
+/// A fatal error log written by the JVM when it crashes (e.g. in native code).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct JvmError {
+    /// Where the error log was found.
+    pub path: PathBuf,
+    /// A summary of the error, taken from the header of the log.
+    pub summary: String,
+    /// The frame that the JVM crashed in (e.g. `C  [liblwjgl.so+0x1234]`).
+    pub problematic_frame: Option<String>,
+    /// The version of Java that crashed.
+    pub java_version: Option<String>,
+}
+
+/// A known cause of a crash, with what can be done about it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Diagnosis {
+    /// The game was launched with a version of Java that it doesn't support.
+    WrongJava {
+        required: VersionReq,
+        found: Option<u64>,
+    },
+    /// Native libraries couldn't be loaded.
+    MissingNatives { detail: String },
+    /// The game ran out of memory.
+    OutOfMemory,
+    /// Mods that can't be used together, or that failed to apply their changes to the game.
+    ModConflict { mods: Vec<String> },
+    /// Mods that the mod loader suspects to have caused the crash.
+    SuspectedMods { mods: Vec<String> },
+}
+
+impl Display for Diagnosis {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::WrongJava {
+                required,
+                found: Some(found),
+            } => write!(
+                f,
+                "The game requires Java {required}, but was launched with Java {found}. Select a \
+                 matching Java installation."
+            ),
+            Self::WrongJava {
+                required,
+                found: None,
+            } => write!(
+                f,
+                "The game requires Java {required}, but was launched with a different version. \
+                 Select a matching Java installation."
+            ),
+            Self::MissingNatives { detail } => write!(
+                f,
+                "Native libraries failed to load ({detail}). Try reinstalling the game version."
+            ),
+            Self::OutOfMemory => write!(
+                f,
+                "The game ran out of memory. Try allocating more memory or removing some mods."
+            ),
+            Self::ModConflict { mods } if mods.is_empty() => {
+                write!(
+                    f,
+                    "Some mods conflict with each other. Try updating your mods."
+                )
+            }
+            Self::ModConflict { mods } => write!(
+                f,
+                "Some mods conflict with each other ({}). Try updating or removing them.",
+                mods.join(", ")
+            ),
+            Self::SuspectedMods { mods } => write!(
+                f,
+                "The crash was likely caused by {}. Try updating or removing them.",
+                mods.join(", ")
+            ),
+        }
+    }
+}
+
+/// The result of analyzing a crash.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Analysis {
+    /// The newest crash report written since the game was started, if there was one.
+    pub crash_report: Option<CrashReport>,
+    /// The newest JVM error log written since the game was started, if there was one.
+    pub jvm_error: Option<JvmError>,
+    /// The known causes that match the crash. Empty if the cause is unknown.
+    pub diagnoses: Vec<Diagnosis>,
+}
+
+// === parsing ===
+
+/// Gets the major version of Java from a version string (e.g. `17.0.1` or `1.8.0_312`).
+fn java_major(version: &str) -> Option<u64> {
+    let mut parts = version.split(|it: char| !it.is_ascii_digit());
+    match parts.next()?.parse().ok()? {
+        1 => parts.next()?.parse().ok(),
+        major => Some(major),
+    }
+}
+
+/// Gets the suspected mods out of an entry list like `Sodium (sodium), Version: 0.5.3`.
+fn mod_entries(entries: &str) -> impl Iterator<Item = String> {
+    entries
+        .split(", ")
+        .map(str::trim)
+        .filter(|it| !it.is_empty() && !it.starts_with("Version:"))
+        .filter(|it| !["NONE", "Unknown", "Not a mod"].contains(it))
+        .filter_map(|it| {
+            let id = it
+                .rsplit_once(" (")
+                .and_then(|(_, id)| id.strip_suffix(')'))
+                .unwrap_or(it);
+            (!NOT_MODS.contains(&id)).then(|| id.to_string())
+        })
+}
+
+impl CrashReport {
+    /// Parses the text of a crash report.
+    pub fn parse(path: PathBuf, text: &str) -> Self {
+        let lines: Vec<&str> = text.lines().collect();
+        let mut report = Self {
+            path,
+            description: None,
+            stack_trace: None,
+            suspected_mods: Vec::new(),
+            java_version: None,
+        };
+
+        for (i, line) in lines.iter().enumerate() {
+            let trimmed = line.trim();
+
+            if let Some(description) = line.strip_prefix("Description: ") {
+                report.description = Some(description.trim().to_string());
+
+                // the stack trace follows the description after a blank line, up to the next one
+                let trace: Vec<&str> = lines[i + 1..]
+                    .iter()
+                    .skip_while(|it| it.trim().is_empty())
+                    .take_while(|it| !it.trim().is_empty())
+                    .copied()
+                    .collect();
+                if !trace.is_empty() {
+                    report.stack_trace = Some(trace.join("\n"));
+                }
+            } else if let Some(mods) = trimmed.strip_prefix("Suspected Mods:") {
+                report.suspected_mods.extend(mod_entries(mods));
+
+                // the suspects can also be listed on the following, more indented lines
+                let indent = |it: &str| it.len() - it.trim_start().len();
+                let depth = lines[i + 1..].first().map_or(0, |it| indent(it));
+                let entries = lines[i + 1..]
+                    .iter()
+                    .take_while(|it| !it.trim().is_empty() && indent(it) > indent(line))
+                    .filter(|it| indent(it) == depth);
+                report
+                    .suspected_mods
+                    .extend(entries.flat_map(|it| mod_entries(it)));
+            } else if let Some(version) = trimmed.strip_prefix("Java Version:") {
+                let version = version.split(',').next().unwrap_or_default().trim();
+                report
+                    .java_version
+                    .get_or_insert_with(|| version.to_string());
+            }
+        }
+
+        // mixins name the mod that they come from when they fail to apply
+        let stack_trace = report.stack_trace.as_deref().unwrap_or_default();
+        for rest in stack_trace.split("from mod ").skip(1) {
+            let id: String = rest
+                .chars()
+                .take_while(|it| it.is_alphanumeric() || matches!(it, '_' | '-'))
+                .collect();
+            if !id.is_empty() {
+                report.suspected_mods.push(id);
+            }
+        }
+
+        // mods can be suspected in several places, keep them in the order they were first named in
+        let mut seen = HashSet::new();
+        report.suspected_mods.retain(|it| seen.insert(it.clone()));
+        report
+    }
+}
+
+impl JvmError {
+    /// Parses the text of a JVM fatal error log.
+    pub fn parse(path: PathBuf, text: &str) -> Self {
+        let header: Vec<&str> = text
+            .lines()
+            .map_while(|it| it.strip_prefix('#'))
+            .map(str::trim)
+            .collect();
+
+        let value = |prefix: &str| {
+            header
+                .iter()
+                .find_map(|it| it.strip_prefix(prefix))
+                .map(str::trim)
+        };
+
+        let summary = header
+            .iter()
+            .take_while(|it| {
+                !it.starts_with("JRE version:") && !it.starts_with("Possible reasons:")
+            })
+            .filter(|it| !it.is_empty() && !it.starts_with("A fatal error has been detected"))
+            .copied()
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let problematic_frame = header
+            .iter()
+            .position(|it| *it == "Problematic frame:")
+            .and_then(|i| header.get(i + 1))
+            .filter(|it| !it.is_empty())
+            .map(|it| it.to_string());
+
+        // e.g. `OpenJDK Runtime Environment (17.0.1+12) (build 17.0.1+12)`
+        let java_version = value("JRE version:")
+            .and_then(|it| it.split_once('(')?.1.split_once(')'))
+            .map(|(version, _)| version.to_string());
+
+        Self {
+            path,
+            summary,
+            problematic_frame,
+            java_version,
+        }
+    }
+}
+
+// === rules ===
+
+/// Everything that is known about a crash, which the rules are matched against.
+struct Evidence<'a> {
+    /// The versions of Java that the game version supports.
+    required_java: &'a VersionReq,
+    crash_report: Option<&'a CrashReport>,
+    jvm_error: Option<&'a JvmError>,
+    /// All error text: the stack trace, the JVM error summary, and warnings and errors in the log.
+    text: String,
+}
+
+impl Evidence<'_> {
+    /// Finds the first line of the error text that contains the pattern.
+    fn line(&self, pattern: &str) -> Option<&str> {
+        self.text
+            .lines()
+            .find(|it| it.contains(pattern))
+            .map(str::trim)
+    }
+
+    /// The major version of Java that the game ran on, if it is known.
+    fn java(&self) -> Option<u64> {
+        let crash_report = self.crash_report.and_then(|it| it.java_version.as_deref());
+        let jvm_error = self.jvm_error.and_then(|it| it.java_version.as_deref());
+        crash_report.or(jvm_error).and_then(java_major)
+    }
+}
+
+type Rule = fn(&Evidence) -> Option<Diagnosis>;
+
+/// The table of known causes, in the order that they are checked.
+const RULES: &[Rule] = &[wrong_java, missing_natives, out_of_memory, mods];
+
+fn wrong_java(evidence: &Evidence) -> Option<Diagnosis> {
+    // Java class file versions are 44 ahead of the Java version (e.g. 61 is Java 17)
+    let class_version = |pattern: &str| {
+        let line = evidence.line(pattern)?;
+        let rest = &line[line.find(pattern)? + pattern.len()..];
+        let digits: String = rest.chars().take_while(char::is_ascii_digit).collect();
+        digits.parse::<u64>().ok()?.checked_sub(44)
+    };
+
+    if evidence.line("UnsupportedClassVersionError").is_some() {
+        return Some(Diagnosis::WrongJava {
+            required: evidence.required_java.clone(),
+            found: class_version("recognizes class file versions up to ").or(evidence.java()),
+        });
+    }
+
+    let found = evidence.java()?;
+    let supported = evidence.required_java.matches(&Version::new(found, 0, 0));
+    (!supported).then(|| Diagnosis::WrongJava {
+        required: evidence.required_java.clone(),
+        found: Some(found),
+    })
+}
+
+fn missing_natives(evidence: &Evidence) -> Option<Diagnosis> {
+    [
+        "UnsatisfiedLinkError",
+        "Failed to locate library",
+        "in java.library.path",
+    ]
+    .into_iter()
+    .find_map(|it| evidence.line(it))
+    .map(|it| Diagnosis::MissingNatives {
+        detail: it.to_string(),
+    })
+}
+
+fn out_of_memory(evidence: &Evidence) -> Option<Diagnosis> {
+    [
+        "java.lang.OutOfMemoryError",
+        "insufficient memory for the Java Runtime",
+    ]
+    .into_iter()
+    .any(|it| evidence.line(it).is_some())
+    .then_some(Diagnosis::OutOfMemory)
+}
+
+fn mods(evidence: &Evidence) -> Option<Diagnosis> {
+    let mods = evidence
+        .crash_report
+        .map(|it| it.suspected_mods.clone())
+        .unwrap_or_default();
+
+    let conflict = [
+        "Incompatible mods found",
+        "Mixin apply failed",
+        "MixinApplyError",
+        "InvalidInjectionException",
+        "DuplicateModsFoundException",
+        "ModResolutionException",
+    ];
+
+    match conflict.into_iter().any(|it| evidence.line(it).is_some()) {
+        true => Some(Diagnosis::ModConflict { mods }),
+        false if !mods.is_empty() => Some(Diagnosis::SuspectedMods { mods }),
+        false => None,
+    }
+}
+
+/// Matches the reports and the log of a crash against the table of known causes.
+pub fn diagnose(
+    required_java: &VersionReq,
+    crash_report: Option<&CrashReport>,
+    jvm_error: Option<&JvmError>,
+    log: Option<&LogBuffer>,
+) -> Vec<Diagnosis> {
+    let log = log.into_iter().flat_map(|it| it.search("", Level::Warn));
+    let text = crash_report
+        .and_then(|it| it.stack_trace.clone())
+        .into_iter()
+        .chain(jvm_error.map(|it| it.summary.clone()))
+        .chain(
+            log.flat_map(|it| [Some(it.message.clone()), it.throwable.clone()])
+                .flatten(),
+        )
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let evidence = Evidence {
+        required_java,
+        crash_report,
+        jvm_error,
+        text,
+    };
+
+    RULES.iter().filter_map(|rule| rule(&evidence)).collect()
+}
+
+// === analysis ===
+
+/// Finds the most recently modified file in a directory that was modified after `since` and whose
+/// name matches.
+async fn newest(
+    directory: &Path,
+    since: SystemTime,
+    matches: impl Fn(&str) -> bool,
+) -> Result<Option<PathBuf>> {
+    let mut entries = match tokio::fs::read_dir(directory).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut newest = None;
+    while let Some(entry) = entries.next_entry().await? {
+        if !matches(&entry.file_name().to_string_lossy()) {
+            continue;
+        }
+
+        let metadata = entry.metadata().await?;
+        let modified = metadata.modified()?;
+        if metadata.is_file()
+            && modified >= since
+            && newest.as_ref().is_none_or(|(it, _)| modified > *it)
+        {
+            newest = Some((modified, entry.path()));
+        }
+    }
+
+    Ok(newest.map(|(_, path)| path))
+}
+
+/// Analyzes a crash of the game that ran in the instance directory: finds the reports that were
+/// written since the game was started, parses them, and diagnoses the crash.
+#[tracing::instrument(name = "crash::analyze", skip(required_java, log))]
+pub async fn analyze(
+    instance: &Path,
+    since: SystemTime,
+    required_java: &VersionReq,
+    log: Option<&LogBuffer>,
+) -> Result<Analysis> {
+    let crash_reports = instance.join("crash-reports");
+    let crash_report = newest(&crash_reports, since, |it| {
+        it.starts_with("crash-") && it.ends_with(".txt")
+    });
+
+    let crash_report = match crash_report.await? {
+        Some(path) => {
+            let text = tokio::fs::read(&path).await?;
+            Some(CrashReport::parse(path, &String::from_utf8_lossy(&text)))
+        }
+        None => None,
+    };
+
+    let jvm_error = newest(instance, since, |it| {
+        it.starts_with("hs_err_pid") && it.ends_with(".log")
+    });
+
+    let jvm_error = match jvm_error.await? {
+        Some(path) => {
+            let text = tokio::fs::read(&path).await?;
+            Some(JvmError::parse(path, &String::from_utf8_lossy(&text)))
+        }
+        None => None,
+    };
+
+    let diagnoses = diagnose(
+        required_java,
+        crash_report.as_ref(),
+        jvm_error.as_ref(),
+        log,
+    );
+    for diagnosis in &diagnoses {
+        tracing::info!("Diagnosis: {diagnosis}");
+    }
+
+    Ok(Analysis {
+        crash_report,
+        jvm_error,
+        diagnoses,
+    })
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use crate::log4j::LogRecord;
+
+    use super::*;
+
+    const CRASH_REPORT: &str = "---- Minecraft Crash Report ----
+// Who set us up the TNT?
+
+Time: 2024-01-01 12:00:00
+Description: Rendering overlay
+
+java.lang.RuntimeException: Mixin transformation of net.minecraft.class_310 failed
+\tat net.fabricmc.loader.impl.launch.knot.KnotClassDelegate.loadClass(KnotClassDelegate.java:427)
+Caused by: org.spongepowered.asm.mixin.throwables.MixinApplyError: Mixin [sodium.mixins.json:MinecraftClientMixin] from mod sodium failed injection check
+
+
+A detailed walkthrough of the error, its code path and all known details is as follows:
+---------------------------------------------------------------------------------------
+
+-- System Details --
+Details:
+\tMinecraft Version: 1.20.4
+\tJava Version: 17.0.8, Eclipse Adoptium
+\tSuspected Mods: 
+\t\tIris (iris), Version: 1.6.11
+\t\t\tIssue tracker URL: https://github.com/IrisShaders/Iris/issues
+\t\tMinecraft (minecraft), Version: 1.20.4
+";
+
+    const JVM_ERROR: &str = "#
+# A fatal error has been detected by the Java Runtime Environment:
+#
+#  SIGSEGV (0xb) at pc=0x00007f0c3c1b2c5d, pid=4242, tid=4243
+#
+# JRE version: OpenJDK Runtime Environment Temurin-21.0.1+12 (21.0.1+12) (build 21.0.1+12-LTS)
+# Java VM: OpenJDK 64-Bit Server VM Temurin-21.0.1+12 (21.0.1+12-LTS, mixed mode, linux-amd64)
+# Problematic frame:
+# C  [liblwjgl.so+0x1c5d]
+#
+
+---------------  S U M M A R Y ------------
+";
+
+    #[test]
+    fn crash_report() {
+        let report = CrashReport::parse(PathBuf::from("crash.txt"), CRASH_REPORT);
+
+        assert_eq!(report.description.as_deref(), Some("Rendering overlay"));
+        assert_eq!(report.java_version.as_deref(), Some("17.0.8"));
+        assert_eq!(report.suspected_mods, ["iris", "sodium"]);
+
+        let stack_trace = report.stack_trace.unwrap();
+        assert!(stack_trace.starts_with("java.lang.RuntimeException: Mixin transformation"));
+        assert!(stack_trace.ends_with("failed injection check"));
+
+        // every section of the report can list its own suspects
+        let text = "Description: Ticking entity\n\n\
+            -- Head --\n\tSuspected Mods: Iris (iris), Sodium (sodium)\n\n\
+            -- System Details --\n\tSuspected Mods: Sodium (sodium), Iris (iris)\n";
+        let report = CrashReport::parse(PathBuf::from("crash.txt"), text);
+        assert_eq!(report.suspected_mods, ["iris", "sodium"]);
+    }
+
+    #[test]
+    fn jvm_error() {
+        let error = JvmError::parse(PathBuf::from("hs_err_pid4242.log"), JVM_ERROR);
+
+        assert_eq!(
+            error.summary,
+            "SIGSEGV (0xb) at pc=0x00007f0c3c1b2c5d, pid=4242, tid=4243"
+        );
+        assert_eq!(
+            error.problematic_frame.as_deref(),
+            Some("C  [liblwjgl.so+0x1c5d]")
+        );
+        assert_eq!(error.java_version.as_deref(), Some("21.0.1+12"));
+        assert_eq!(java_major("21.0.1+12"), Some(21));
+        assert_eq!(java_major("1.8.0_312"), Some(8));
+    }
+
+    #[test]
+    fn rules() {
+        let java = VersionReq::parse(">=17").unwrap();
+        let report = CrashReport::parse(PathBuf::from("crash.txt"), CRASH_REPORT);
+
+        assert_eq!(
+            diagnose(&java, Some(&report), None, None),
+            [Diagnosis::ModConflict {
+                mods: vec!["iris".to_string(), "sodium".to_string()]
+            }]
+        );
+
+        // Errors on stderr (before a crash report could be written) also count
+        let mut log = LogBuffer::new(16);
+        log.push(LogRecord::plain(
+            Level::Warn,
+            "java.lang.UnsupportedClassVersionError: net/minecraft/client/main/Main has been \
+             compiled by a more recent version of the Java Runtime (class file version 61.0), this \
+             version of the Java Runtime only recognizes class file versions up to 52.0",
+        ));
+        log.push(LogRecord::plain(
+            Level::Warn,
+            "java.lang.UnsatisfiedLinkError: no lwjgl in java.library.path",
+        ));
+
+        let diagnoses = diagnose(&java, None, None, Some(&log));
+        assert_eq!(diagnoses.len(), 2);
+        assert_eq!(
+            diagnoses[0],
+            Diagnosis::WrongJava {
+                required: java.clone(),
+                found: Some(8),
+            }
+        );
+        assert!(matches!(diagnoses[1], Diagnosis::MissingNatives { .. }));
+
+        // Java version reported by the JVM doesn't match the requirement
+        let java = VersionReq::parse("=8").unwrap();
+        let error = JvmError::parse(PathBuf::from("hs_err_pid4242.log"), JVM_ERROR);
+        assert_eq!(
+            diagnose(&java, None, Some(&error), None),
+            [Diagnosis::WrongJava {
+                required: java.clone(),
+                found: Some(21),
+            }]
+        );
+    }
+}
@@ -0,0 +1,362 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Tuning of the JVM that runs the game: the size of the heap, and the garbage collector along
+//! with its flags.
+
+use std::collections::HashSet;
+
+use sysinfo::{MemoryRefreshKind, RefreshKind, System};
+
+use data::core::settings::EffectiveSettings;
+
+/// The smallest heap that the game is given, in MiB.
+const MIN_HEAP: u64 = 1024;
+
+/// The largest heap that the game is given automatically, in MiB. Bigger heaps only make pauses
+/// longer for vanilla and most modpacks, so going above this is left to the user.
+const MAX_HEAP: u64 = 6144;
+
+/// How much memory is left for the rest of the system, in MiB.
+const RESERVED: u64 = 2048;
+
+/// The garbage collector that the game runs with.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Collector {
+    /// ZGC on Java 21 and newer (where it is generational), G1 otherwise.
+    #[default]
+    Auto,
+    /// G1, with the flags from Aikar (https://docs.papermc.io/paper/aikars-flags).
+    G1,
+    /// ZGC, which has much shorter pauses, at the cost of more memory and CPU usage.
+    Z,
+    /// Whatever the JVM picks by itself, with no extra flags.
+    Default,
+}
+
+/// What the user wants from the JVM. Memory sizes are in MiB.
+#[derive(Clone, Debug, Default)]
+pub struct JvmPreferences {
+    /// The initial size of the heap (`-Xms`). The JVM picks one itself if unset.
+    pub min_memory: Option<u64>,
+    /// The maximum size of the heap (`-Xmx`). It is sized from the system memory if unset.
+    pub max_memory: Option<u64>,
+    /// The garbage collector to use.
+    pub collector: Collector,
+    /// Any extra arguments for the JVM.
+    pub arguments: Vec<String>,
+}
+
+/// Arguments for the JVM that are merged into the ones of the game version (see [Tuning::merge]).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Tuning {
+    /// Flags that size the heap.
+    pub memory: Vec<String>,
+    /// Flags that select and configure the garbage collector.
+    pub collector: Vec<String>,
+    /// Extra arguments from the user.
+    pub extra: Vec<String>,
+}
+
+/// The total memory of the system, in MiB.
+pub fn system_memory() -> u64 {
+    let refresh = RefreshKind::nothing().with_memory(MemoryRefreshKind::nothing().with_ram());
+    System::new_with_specifics(refresh).total_memory() / 1024 / 1024
+}
+
+/// The maximum heap size for a system with some amount of memory, in MiB: half of the memory,
+/// leaving some for the rest of the system, rounded down to 512 MiB.
+pub fn recommended_memory(system_memory: u64) -> u64 {
+    let heap = (system_memory / 2).min(system_memory.saturating_sub(RESERVED));
+    (heap / 512 * 512).clamp(MIN_HEAP, MAX_HEAP)
+}
+
+/// Aikar's flags for G1, which were made for servers, but work just as well for the client. The
+/// only one left out is `-XX:+AlwaysPreTouch`, which makes the game start slower.
+fn g1(java_major: u64, heap: u64) -> Vec<String> {
+    // bigger heaps get a bigger young generation and bigger regions
+    let large = heap >= 12 * 1024;
+
+    let mut flags = vec![
+        "-XX:+UseG1GC".to_string(),
+        "-XX:+ParallelRefProcEnabled".to_string(),
+        "-XX:MaxGCPauseMillis=200".to_string(),
+        "-XX:+UnlockExperimentalVMOptions".to_string(),
+        "-XX:+DisableExplicitGC".to_string(),
+        format!("-XX:G1NewSizePercent={}", if large { 40 } else { 30 }),
+        format!("-XX:G1MaxNewSizePercent={}", if large { 50 } else { 40 }),
+        format!("-XX:G1HeapRegionSize={}", if large { "16M" } else { "8M" }),
+        format!("-XX:G1ReservePercent={}", if large { 15 } else { 20 }),
+        "-XX:G1HeapWastePercent=5".to_string(),
+        "-XX:G1MixedGCCountTarget=4".to_string(),
+        format!(
+            "-XX:InitiatingHeapOccupancyPercent={}",
+            if large { 20 } else { 15 }
+        ),
+        "-XX:G1MixedGCLiveThresholdPercent=90".to_string(),
+        "-XX:SurvivorRatio=32".to_string(),
+        "-XX:+PerfDisableSharedMem".to_string(),
+        "-XX:MaxTenuringThreshold=1".to_string(),
+    ];
+
+    // this one became obsolete with the rewrite of remembered sets in Java 20
+    if java_major < 20 {
+        flags.push("-XX:G1RSetUpdatingPauseTimePercent=5".to_string());
+    }
+
+    flags
+}
+
+/// Flags for ZGC. It is only generational by default since Java 23.
+fn z(java_major: u64) -> Vec<String> {
+    let mut flags = vec!["-XX:+UseZGC".to_string()];
+    if (21..23).contains(&java_major) {
+        flags.push("-XX:+ZGenerational".to_string());
+    }
+
+    flags
+}
+
+/// The name of a flag, used to tell whether two arguments set the same thing. For example, both
+/// `-XX:+UseG1GC` and `-XX:-UseG1GC` are `UseG1GC`, and `-Xmx2G` is `-Xmx`.
+fn key(argument: &str) -> &str {
+    if let Some(option) = argument.strip_prefix("-XX:") {
+        let option = option.trim_start_matches(['+', '-']);
+        return option.split_once('=').map_or(option, |(name, _)| name);
+    }
+
+    if argument.starts_with("-D") {
+        return argument.split_once('=').map_or(argument, |(name, _)| name);
+    }
+
+    for prefix in ["-Xmx", "-Xms", "-Xss", "-Xmn"] {
+        if argument.starts_with(prefix) {
+            return prefix;
+        }
+    }
+
+    argument
+}
+
+/// Whether an argument selects a garbage collector, e.g. `-XX:+UseG1GC`.
+fn is_collector(argument: &str) -> bool {
+    let key = key(argument);
+    argument.starts_with("-XX:+") && key.starts_with("Use") && key.ends_with("GC")
+}
+
+impl Tuning {
+    /// Creates the arguments for a version of Java, on a system with some amount of memory (in
+    /// MiB), according to the preferences.
+    pub fn new(java_major: u64, system_memory: u64, preferences: &JvmPreferences) -> Self {
+        let heap = preferences
+            .max_memory
+            .unwrap_or_else(|| recommended_memory(system_memory));
+
+        let mut memory = vec![format!("-Xmx{heap}M")];
+        if let Some(min) = preferences.min_memory {
+            memory.push(format!("-Xms{}M", min.min(heap)));
+        }
+
+        let collector = match preferences.collector {
+            Collector::Auto if java_major >= 21 => z(java_major),
+            Collector::Auto | Collector::G1 => g1(java_major, heap),
+            Collector::Z => z(java_major),
+            Collector::Default => Vec::new(),
+        };
+
+        Self {
+            memory,
+            collector,
+            extra: preferences.arguments.clone(),
+        }
+    }
+
+    /// Merges the tuning into the (already substituted) JVM arguments of a game version. Flags
+    /// that the version already sets (e.g. `-XstartOnFirstThread` or `-Xss1M`) are not repeated,
+    /// and the extra arguments of the user replace the memory and collector flags that set the
+    /// same thing. If the version or the user selects a garbage collector, none of the collector
+    /// flags are added, as the JVM refuses to start with two of them.
+    pub fn merge(&self, arguments: &[String]) -> Vec<String> {
+        let existing: HashSet<_> = arguments.iter().map(|it| key(it)).collect();
+        let explicit: HashSet<_> = self.extra.iter().map(|it| key(it)).collect();
+        let has_collector = arguments
+            .iter()
+            .chain(&self.extra)
+            .any(|it| is_collector(it));
+
+        let collector = match has_collector {
+            true => &[][..],
+            false => &self.collector[..],
+        };
+
+        let mut seen = HashSet::new();
+        let tuning = self
+            .memory
+            .iter()
+            .chain(collector)
+            .filter(|it| !explicit.contains(key(it)))
+            .chain(&self.extra)
+            .filter(|it| !existing.contains(key(it)) && seen.insert(key(it)));
+
+        tuning.chain(arguments).cloned().collect()
+    }
+}
+
+// === conversion ===
+
+impl From<&EffectiveSettings> for JvmPreferences {
+    /// The preferences that the settings (optionally resolved for an instance) ask for.
+    fn from(value: &EffectiveSettings) -> Self {
+        Self {
+            max_memory: value.memory.value,
+            arguments: value.jvm_arguments.value.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use data::core::instance::Instance;
+    use data::core::settings::Settings;
+
+    use super::*;
+
+    #[test]
+    fn memory() {
+        assert_eq!(recommended_memory(2048), 1024);
+        assert_eq!(recommended_memory(4096), 2048);
+        assert_eq!(recommended_memory(8192), 4096);
+        assert_eq!(recommended_memory(7000), 3072);
+        assert_eq!(recommended_memory(65536), 6144);
+    }
+
+    #[test]
+    fn collectors() {
+        let preferences = JvmPreferences::default();
+
+        let tuning = Tuning::new(8, 16384, &preferences);
+        assert_eq!(tuning.memory, ["-Xmx6144M"]);
+        assert!(tuning.collector.contains(&"-XX:+UseG1GC".to_string()));
+        assert!(
+            tuning
+                .collector
+                .contains(&"-XX:G1RSetUpdatingPauseTimePercent=5".to_string())
+        );
+
+        let tuning = Tuning::new(21, 16384, &preferences);
+        assert_eq!(tuning.collector, ["-XX:+UseZGC", "-XX:+ZGenerational"]);
+
+        let preferences = JvmPreferences {
+            min_memory: Some(16384),
+            max_memory: Some(12288),
+            collector: Collector::G1,
+            ..Default::default()
+        };
+        let tuning = Tuning::new(21, 16384, &preferences);
+        assert_eq!(tuning.memory, ["-Xmx12288M", "-Xms12288M"]);
+        assert!(
+            tuning
+                .collector
+                .contains(&"-XX:G1HeapRegionSize=16M".to_string())
+        );
+        assert!(
+            !tuning
+                .collector
+                .contains(&"-XX:G1RSetUpdatingPauseTimePercent=5".to_string())
+        );
+    }
+
+    #[test]
+    fn settings() {
+        let instance = Instance {
+            memory: Some(8192),
+            ..Instance::new("Test", "1.21.4", chrono::Utc::now())
+        };
+        let settings = Settings {
+            memory: Some(4096),
+            jvm_arguments: Some(vec!["-Dfile.encoding=UTF-8".to_string()]),
+            ..Default::default()
+        };
+
+        let preferences = JvmPreferences::from(&settings.resolve(Some(&instance)));
+        let tuning = Tuning::new(21, 16384, &preferences);
+        assert_eq!(tuning.memory, ["-Xmx8192M"]);
+        assert_eq!(tuning.extra, ["-Dfile.encoding=UTF-8"]);
+    }
+
+    #[test]
+    fn merge() {
+        let tuning = Tuning {
+            memory: vec!["-Xmx4096M".to_string()],
+            collector: vec!["-XX:+UseZGC".to_string()],
+            extra: vec![
+                "-XstartOnFirstThread".to_string(),
+                "-Xss2M".to_string(),
+                "-Dfile.encoding=UTF-8".to_string(),
+            ],
+        };
+
+        let arguments = ["-XstartOnFirstThread", "-Xss1M", "-cp", "a.jar"].map(String::from);
+        assert_eq!(
+            tuning.merge(&arguments),
+            [
+                "-Xmx4096M",
+                "-XX:+UseZGC",
+                "-Dfile.encoding=UTF-8",
+                "-XstartOnFirstThread",
+                "-Xss1M",
+                "-cp",
+                "a.jar"
+            ]
+        );
+
+        // the version's own collector wins
+        let arguments = ["-XX:+UseG1GC", "-cp", "a.jar"].map(String::from);
+        assert_eq!(
+            tuning.merge(&arguments),
+            [
+                "-Xmx4096M",
+                "-XstartOnFirstThread",
+                "-Xss2M",
+                "-Dfile.encoding=UTF-8",
+                "-XX:+UseG1GC",
+                "-cp",
+                "a.jar"
+            ]
+        );
+    }
+
+    #[test]
+    fn explicit() {
+        let tuning = Tuning {
+            memory: vec!["-Xmx4096M".to_string(), "-Xms1024M".to_string()],
+            collector: vec![
+                "-XX:+UseG1GC".to_string(),
+                "-XX:MaxGCPauseMillis=200".to_string(),
+            ],
+            extra: vec!["-XX:+UseZGC".to_string(), "-Xmx8G".to_string()],
+        };
+
+        // the user's collector and heap size win over the tuning
+        let arguments = ["-cp", "a.jar"].map(String::from);
+        assert_eq!(
+            tuning.merge(&arguments),
+            ["-Xms1024M", "-XX:+UseZGC", "-Xmx8G", "-cp", "a.jar"]
+        );
+    }
+}
@@ -0,0 +1,219 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Launcher Launch Module
+//! ======================
+//!
+//! This module takes a [ResolvedGameVersion] and turns it into a running game. The process of
+//! launching the game is split into a few steps:
+//!
+//! - [classpath] - Assembling the class path from installed libraries.
+//! - [natives] - Extracting native libraries into a per-launch directory.
+//! - [jvm] - Sizing the heap and picking the garbage collector (optional).
+//! - [command] - Substituting the arguments and assembling the final command line.
+//! - [process] - Spawning the JVM and keeping track of the running game.
+//!
+//! The easiest way to use all of them together is [launch], which takes [LaunchOptions] and
+//! returns a [GameProcess]. To keep track of multiple running games (e.g. from the UI), use the
+//! [Supervisor] instead, which also parses the output of the game (see [log4j]) and analyzes
+//! crashes (see [crash]).
+
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use data::core::assets::AssetIndex;
+use data::core::auth::Account;
+use data::core::game::ResolvedGameVersion;
+use data::core::template::TemplateError;
+use utils::directories;
+
+pub use process::GameProcess;
+pub use supervisor::Supervisor;
+
+pub mod classpath;
+pub mod command;
+pub mod crash;
+pub mod jvm;
+pub mod log4j;
+pub mod natives;
+pub mod process;
+pub mod supervisor;
+
+/// The name of the launcher, as reported to the game.
+pub const LAUNCHER_NAME: &str = "Launcher";
+
+/// The version of the launcher, as reported to the game.
+pub const LAUNCHER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("java executable not found at {0}")]
+    JavaNotFound(PathBuf),
+    #[error("library is not installed: {0}")]
+    MissingLibrary(PathBuf),
+    #[error("failed to extract natives from {0}: {1}")]
+    Natives(PathBuf, utils::archive::Error),
+    #[error("failed to substitute argument: {0}")]
+    Template(#[from] TemplateError),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("instance {0} is already running")]
+    AlreadyRunning(String),
+    #[error("instance {0} is not running")]
+    NotRunning(String),
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// Everything that is needed to launch the game.
+#[derive(Clone, Debug)]
+pub struct LaunchOptions {
+    /// The game version to launch, resolved for the current platform.
+    pub version: ResolvedGameVersion,
+    /// Path to the Java executable that will run the game.
+    pub java: PathBuf,
+    /// The account to play with.
+    pub account: Account,
+    /// The directory that the game will run in (saves, options, mods, etc.).
+    pub instance: PathBuf,
+    /// The root directory of the library store, in Maven layout.
+    pub libraries: PathBuf,
+    /// The root directory of the asset store.
+    pub assets: PathBuf,
+    /// Where the game looks for its assets, depending on its asset index.
+    pub asset_layout: AssetLayout,
+    /// Path to the game's own JAR file.
+    pub client: PathBuf,
+    /// Heap and garbage collector flags, merged into the JVM arguments of the version.
+    pub tuning: Option<jvm::Tuning>,
+    /// The language that the game starts in the first time that it runs in the instance (e.g.
+    /// `en-US`). Afterwards, the game remembers whatever the player picks.
+    pub language: Option<String>,
+}
+
+/// Where the game looks for its assets. Most versions read them from the asset store, but older
+/// ones need them laid out by their names (see [AssetIndex]).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum AssetLayout {
+    /// The assets are only in the hash-addressed store (modern indexes).
+    #[default]
+    Objects,
+    /// The assets are laid out in `virtual/{index}` of the asset store (the "legacy" index).
+    Virtual,
+    /// The assets are copied into the `resources` directory of the instance (the "pre-1.6" index).
+    Resources,
+}
+
+impl From<&AssetIndex> for AssetLayout {
+    fn from(index: &AssetIndex) -> Self {
+        match (index.map_to_resources, index.is_virtual) {
+            (true, _) => Self::Resources,
+            (false, true) => Self::Virtual,
+            (false, false) => Self::Objects,
+        }
+    }
+}
+
+impl LaunchOptions {
+    /// Creates launch options that use the default library and asset stores in the data directory.
+    pub fn new(
+        version: ResolvedGameVersion,
+        java: impl Into<PathBuf>,
+        account: Account,
+        instance: impl Into<PathBuf>,
+    ) -> Self {
+        let libraries = directories::DATA.join("libraries");
+        let client = libraries.join(&version.client.path);
+
+        Self {
+            version,
+            java: java.into(),
+            account,
+            instance: instance.into(),
+            libraries,
+            assets: directories::DATA.join("assets"),
+            asset_layout: AssetLayout::default(),
+            client,
+            tuning: None,
+            language: None,
+        }
+    }
+}
+
+/// Launches the game: builds the class path, extracts natives, assembles the command line and
+/// spawns the JVM.
+#[tracing::instrument(name = "launch::launch", skip_all, fields(version = %options.version.id))]
+pub async fn launch(options: LaunchOptions) -> Result<GameProcess> {
+    if !tokio::fs::try_exists(&options.java).await? {
+        return Err(Error::JavaNotFound(options.java));
+    }
+
+    tokio::fs::create_dir_all(&options.instance).await?;
+    if let Some(language) = &options.language {
+        seed_language(&options.instance, language).await?;
+    }
+
+    let classpath = classpath::build(&options).await?;
+    let natives = natives::extract(&options.version.libraries, &options.libraries).await?;
+
+    let command = command::build(&options, &classpath, &natives);
+    let command = match command {
+        Ok(command) => command,
+        Err(err) => {
+            natives::clean(&natives).await;
+            return Err(err);
+        }
+    };
+
+    process::spawn(command, natives).await
+}
+
+/// Sets the language of the game in a new instance, which doesn't have its `options.txt` yet. The
+/// game names languages like `en_us` instead of `en-US`.
+async fn seed_language(instance: &Path, language: &str) -> Result<()> {
+    let path = instance.join("options.txt");
+    if tokio::fs::try_exists(&path).await? {
+        return Ok(());
+    }
+
+    let code = language.replace('-', "_").to_lowercase();
+    tokio::fs::write(&path, format!("lang:{code}\n")).await?;
+    Ok(())
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn language() {
+        let temp = TempDir::new().unwrap();
+        let instance = temp.path();
+
+        seed_language(instance, "de-DE").await.unwrap();
+        let options = std::fs::read_to_string(instance.join("options.txt")).unwrap();
+        assert_eq!(options, "lang:de_de\n");
+
+        // the options of an instance that has run before are left alone
+        seed_language(instance, "fr-FR").await.unwrap();
+        let options = std::fs::read_to_string(instance.join("options.txt")).unwrap();
+        assert_eq!(options, "lang:de_de\n");
+    }
+}
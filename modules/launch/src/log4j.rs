@@ -0,0 +1,497 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Parsing of the log4j XML events that the game writes to its standard output when it is launched
+//! with a logging config (see [GameVersionLogging]). A single event looks like this:
+//!
+//! ```xml
+//! <log4j:Event logger="net.minecraft.client.Minecraft" timestamp="1700000000000" level="INFO" thread="Render thread">
+//!   <log4j:Message><![CDATA[Setting user: Notch]]></log4j:Message>
+//! </log4j:Event>
+//! ```
+//!
+//! [LogParser] turns such events (and any plain lines in between them) into [LogRecord]s, which
+//! can be kept in a bounded [LogBuffer] and searched later.
+//!
+//! [GameVersionLogging]: data::core::game::GameVersionLogging
+
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+
+use chrono::{DateTime, Utc};
+
+const EVENT_START: &str = "<log4j:Event";
+const EVENT_END: &str = "</log4j:Event>";
+const CDATA_START: &str = "<![CDATA[";
+const CDATA_END: &str = "]]>";
+
+/// Events that grow larger than this without ending are given up on and kept as plain text.
+const MAX_EVENT_SIZE: usize = 1024 * 1024;
+
+/// The severity of a [LogRecord], from least to most severe.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl Level {
+    /// Parses a log4j level name (e.g. `WARN`).
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "TRACE" => Some(Self::Trace),
+            "DEBUG" => Some(Self::Debug),
+            "INFO" => Some(Self::Info),
+            "WARN" => Some(Self::Warn),
+            "ERROR" => Some(Self::Error),
+            "FATAL" => Some(Self::Fatal),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Trace => "TRACE",
+            Self::Debug => "DEBUG",
+            Self::Info => "INFO",
+            Self::Warn => "WARN",
+            Self::Error => "ERROR",
+            Self::Fatal => "FATAL",
+        })
+    }
+}
+
+/// A single entry of the game's log.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LogRecord {
+    /// When the entry was logged. Only known for log4j events.
+    pub timestamp: Option<DateTime<Utc>>,
+    /// The severity of the entry.
+    pub level: Level,
+    /// The name of the thread that logged the entry. Only known for log4j events.
+    pub thread: Option<String>,
+    /// The name of the logger (usually a class name). Only known for log4j events.
+    pub logger: Option<String>,
+    /// The message itself.
+    pub message: String,
+    /// The stack trace of the exception that was logged with the message, if there was one.
+    pub throwable: Option<String>,
+}
+
+impl LogRecord {
+    /// Creates a record for a line of plain text that isn't a log4j event.
+    pub fn plain(level: Level, line: impl Into<String>) -> Self {
+        Self {
+            timestamp: None,
+            level,
+            thread: None,
+            logger: None,
+            message: line.into(),
+            throwable: None,
+        }
+    }
+
+    /// Whether the message, throwable or logger contain the (already lowercase) text.
+    fn matches(&self, text: &str) -> bool {
+        [
+            Some(&self.message),
+            self.throwable.as_ref(),
+            self.logger.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .any(|it| it.to_lowercase().contains(text))
+    }
+}
+
+/// A line-based streaming parser of log4j events. Lines are fed in one by one as they are read
+/// from the game's output, and records come out once they are complete.
+#[derive(Clone, Debug)]
+pub struct LogParser {
+    /// The level given to plain lines.
+    fallback: Level,
+    /// The event that is currently being read, if any.
+    event: Option<String>,
+}
+
+impl LogParser {
+    /// Creates a parser that gives plain lines (that aren't log4j events) the fallback level.
+    pub fn new(fallback: Level) -> Self {
+        Self {
+            fallback,
+            event: None,
+        }
+    }
+
+    /// Feeds a single line of output (without the line ending) into the parser. Returns the records
+    /// that the line completes: plain text (outside of events) and events that end on this line.
+    /// Blank text is skipped.
+    pub fn push(&mut self, line: &str) -> Vec<LogRecord> {
+        let mut records = Vec::new();
+        let mut rest = line;
+
+        if let Some(event) = self.event.as_mut() {
+            event.push('\n');
+        }
+
+        loop {
+            let Some(event) = self.event.as_mut() else {
+                let (text, next) = match rest.find(EVENT_START) {
+                    Some(start) => (&rest[..start], Some(&rest[start..])),
+                    None => (rest, None),
+                };
+
+                // whole lines are kept as they are, e.g. the indentation of stack traces
+                if text.len() == line.len() && !text.trim().is_empty() {
+                    records.push(LogRecord::plain(self.fallback, text));
+                } else if !text.trim().is_empty() {
+                    records.push(LogRecord::plain(self.fallback, text.trim()));
+                }
+
+                match next {
+                    Some(next) => {
+                        self.event = Some(String::new());
+                        rest = next;
+                        continue;
+                    }
+                    None => break,
+                }
+            };
+
+            match rest.find(EVENT_END) {
+                Some(end) => {
+                    let end = end + EVENT_END.len();
+                    event.push_str(&rest[..end]);
+                    rest = &rest[end..];
+
+                    let event = std::mem::take(event);
+                    self.event = None;
+
+                    let record = parse_event(&event);
+                    if record.is_none() {
+                        tracing::debug!("Failed to parse log4j event, keeping it as plain text");
+                    }
+                    records.push(record.unwrap_or_else(|| LogRecord::plain(self.fallback, event)));
+                }
+                None => {
+                    event.push_str(rest);
+                    if event.len() > MAX_EVENT_SIZE {
+                        records.extend(self.finish());
+                    }
+                    break;
+                }
+            }
+        }
+
+        records
+    }
+
+    /// Returns what has been read of an incomplete event as plain text. Should be called once the
+    /// output ends, so that nothing is lost.
+    pub fn finish(&mut self) -> Option<LogRecord> {
+        let event = self.event.take()?;
+        Some(LogRecord::plain(self.fallback, event))
+    }
+}
+
+/// Parses a complete `<log4j:Event>` element.
+fn parse_event(xml: &str) -> Option<LogRecord> {
+    let header = &xml[EVENT_START.len()..];
+    let header = &header[..header.find('>')?];
+    let attributes = attributes(header.trim_end_matches('/'))?;
+    let attribute = |name: &str| {
+        attributes
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.clone())
+    };
+
+    let timestamp = attribute("timestamp")
+        .and_then(|it| it.parse().ok())
+        .and_then(DateTime::from_timestamp_millis);
+
+    Some(LogRecord {
+        timestamp,
+        level: attribute("level")
+            .and_then(|it| Level::parse(&it))
+            .unwrap_or(Level::Info),
+        thread: attribute("thread"),
+        logger: attribute("logger"),
+        message: element(xml, "log4j:Message").unwrap_or_default(),
+        throwable: element(xml, "log4j:Throwable"),
+    })
+}
+
+/// Parses the attributes of a start tag (the part after the tag name), e.g. `a="1" b='2'`.
+fn attributes(mut header: &str) -> Option<Vec<(&str, String)>> {
+    let mut attributes = Vec::new();
+
+    loop {
+        header = header.trim_start();
+        if header.is_empty() {
+            return Some(attributes);
+        }
+
+        let (name, rest) = header.split_once('=')?;
+        let rest = rest.trim_start();
+        let quote = rest.chars().next().filter(|it| *it == '"' || *it == '\'')?;
+        let (value, rest) = rest[1..].split_once(quote)?;
+
+        attributes.push((name.trim(), unescape(value)));
+        header = rest;
+    }
+}
+
+/// Gets the text content of the first element with the given name.
+fn element(xml: &str, name: &str) -> Option<String> {
+    let start = format!("<{name}>");
+    let end = format!("</{name}>");
+
+    let content = &xml[xml.find(&start)? + start.len()..];
+    let content = &content[..content.find(&end)?];
+    Some(text(content))
+}
+
+/// Decodes text content, which is a mix of escaped text and CDATA sections.
+fn text(mut content: &str) -> String {
+    let mut output = String::with_capacity(content.len());
+
+    while let Some(start) = content.find(CDATA_START) {
+        output.push_str(&unescape(&content[..start]));
+        content = &content[start + CDATA_START.len()..];
+
+        let end = content.find(CDATA_END).unwrap_or(content.len());
+        output.push_str(&content[..end]);
+        content = content.get(end + CDATA_END.len()..).unwrap_or_default();
+    }
+
+    output.push_str(&unescape(content));
+    output
+}
+
+/// Replaces XML entities and character references with the characters they stand for. Anything
+/// that doesn't look like an entity is left as-is.
+fn unescape(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest.find(';').map(|end| (&rest[1..end], end));
+        let decoded = entity.and_then(|(name, end)| {
+            let char = match name {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => name
+                    .strip_prefix("#x")
+                    .map(|it| u32::from_str_radix(it, 16))
+                    .or_else(|| name.strip_prefix('#').map(str::parse))
+                    .and_then(Result::ok)
+                    .and_then(char::from_u32),
+            };
+            char.map(|it| (it, end))
+        });
+
+        match decoded {
+            Some((char, end)) => {
+                output.push(char);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                output.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// A bounded ring buffer of [LogRecord]s. Once it is full, the oldest records are dropped to make
+/// room for new ones.
+#[derive(Clone, Debug)]
+pub struct LogBuffer {
+    records: VecDeque<LogRecord>,
+    capacity: usize,
+    dropped: usize,
+}
+
+impl LogBuffer {
+    /// Creates an empty buffer that holds at most `capacity` records.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            records: VecDeque::with_capacity(capacity.min(1024)),
+            capacity,
+            dropped: 0,
+        }
+    }
+
+    /// Adds a record, dropping the oldest one if the buffer is full.
+    pub fn push(&mut self, record: LogRecord) {
+        if self.records.len() >= self.capacity {
+            self.dropped += 1;
+            if self.records.pop_front().is_none() {
+                return;
+            }
+        }
+
+        self.records.push_back(record);
+    }
+
+    /// The number of records in the buffer.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Whether there are no records in the buffer.
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// The number of records that have been dropped because the buffer was full.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Iterates over the records, from oldest to newest.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &LogRecord> {
+        self.records.iter()
+    }
+
+    /// Finds records that are at least as severe as `level` and contain the text in their message,
+    /// throwable or logger (ignoring case). An empty text matches every record.
+    pub fn search(&self, text: &str, level: Level) -> impl DoubleEndedIterator<Item = &LogRecord> {
+        let text = text.to_lowercase();
+        self.records
+            .iter()
+            .filter(move |it| it.level >= level && it.matches(&text))
+    }
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(lines: &str) -> Vec<LogRecord> {
+        let mut parser = LogParser::new(Level::Info);
+        let mut records: Vec<_> = lines.lines().flat_map(|it| parser.push(it)).collect();
+        records.extend(parser.finish());
+        records
+    }
+
+    #[test]
+    fn events() {
+        let records = parse(concat!(
+            "<log4j:Event logger=\"net.minecraft.client.Minecraft\" timestamp=\"1700000000000\" ",
+            "level=\"WARN\" thread=\"Render thread\">\n",
+            "  <log4j:Message><![CDATA[Missing <sound> & stuff]]></log4j:Message>\n",
+            "</log4j:Event>\n",
+            "\n",
+            "Plain line before the next event\n",
+            "<log4j:Event logger='crash' timestamp='1' level='ERROR' thread='main'>",
+            "<log4j:Message>Oh &lt;no&gt;</log4j:Message>",
+            "<log4j:Throwable><![CDATA[java.lang.Exception: ]]]]><![CDATA[>\n",
+            "\tat Main.main(Main.java)]]></log4j:Throwable></log4j:Event>\n",
+        ));
+
+        assert_eq!(records.len(), 3);
+
+        assert_eq!(records[0].level, Level::Warn);
+        assert_eq!(records[0].thread.as_deref(), Some("Render thread"));
+        assert_eq!(
+            records[0].logger.as_deref(),
+            Some("net.minecraft.client.Minecraft")
+        );
+        assert_eq!(records[0].message, "Missing <sound> & stuff");
+        assert_eq!(
+            records[0].timestamp,
+            DateTime::from_timestamp_millis(1700000000000)
+        );
+
+        assert_eq!(
+            records[1],
+            LogRecord::plain(Level::Info, "Plain line before the next event")
+        );
+
+        assert_eq!(records[2].level, Level::Error);
+        assert_eq!(records[2].message, "Oh <no>");
+        assert_eq!(
+            records[2].throwable.as_deref(),
+            Some("java.lang.Exception: ]]>\n\tat Main.main(Main.java)")
+        );
+    }
+
+    #[test]
+    fn surrounding_text() {
+        let records = parse(concat!(
+            "[12:00:00] Starting <log4j:Event level=\"INFO\" thread=\"main\">",
+            "<log4j:Message>Hello</log4j:Message></log4j:Event> trailing text\n",
+        ));
+
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            records[0],
+            LogRecord::plain(Level::Info, "[12:00:00] Starting")
+        );
+        assert_eq!(records[1].message, "Hello");
+        assert_eq!(records[1].thread.as_deref(), Some("main"));
+        assert_eq!(records[2], LogRecord::plain(Level::Info, "trailing text"));
+    }
+
+    #[test]
+    fn incomplete() {
+        let records = parse("<log4j:Event level=\"INFO\">\n<log4j:Message>cut off");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].level, Level::Info);
+        assert!(records[0].message.starts_with("<log4j:Event"));
+    }
+
+    #[test]
+    fn buffer() {
+        let mut buffer = LogBuffer::new(3);
+        buffer.push(LogRecord::plain(Level::Info, "Loading world"));
+        buffer.push(LogRecord::plain(Level::Error, "Failed to load texture"));
+        buffer.push(LogRecord::plain(Level::Warn, "Texture is too large"));
+        buffer.push(LogRecord::plain(Level::Info, "Done loading"));
+
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(buffer.dropped(), 1);
+        assert_eq!(
+            buffer.iter().next().unwrap().message,
+            "Failed to load texture"
+        );
+
+        let found: Vec<_> = buffer
+            .search("TEXTURE", Level::Warn)
+            .map(|it| it.level)
+            .collect();
+        assert_eq!(found, [Level::Error, Level::Warn]);
+        assert_eq!(buffer.search("", Level::Error).count(), 1);
+    }
+}
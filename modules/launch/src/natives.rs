@@ -0,0 +1,141 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tokio::fs;
+
+use data::core::library::{Library, LibraryExtract};
+use utils::archive::{self, ExtractOptions, Format};
+use utils::directories;
+
+use crate::{Error, Result};
+
+/// Extracts the contents of a single native JAR into a directory, skipping the paths excluded by
+/// the rules.
+async fn extract_jar(path: &Path, directory: &Path, rules: LibraryExtract) -> Result<()> {
+    let options = ExtractOptions {
+        // the rules exclude directories with a trailing slash (e.g. `META-INF/`), and always use
+        // forward slashes like the names in the archive do
+        filter: Some(Arc::new(move |path: &Path| {
+            let name: Vec<_> = path.iter().map(|it| it.to_string_lossy()).collect();
+            rules.includes(&format!("{}/", name.join("/")))
+        })),
+        ..Default::default()
+    };
+
+    archive::extract_as(path, directory, Format::Jar, options)
+        .await
+        .map_err(|err| Error::Natives(path.into(), err))
+}
+
+/// Extracts the native libraries (the ones with extract rules) among the given libraries into a
+/// new, unique directory in the cache directory, and returns the path to it. The `root` is the
+/// root of the library store.
+pub async fn extract(libraries: &[Library], root: &Path) -> Result<PathBuf> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|it| it.as_nanos())
+        .unwrap_or_default();
+
+    let directory = directories::CACHE
+        .join("natives")
+        .join(format!("{}-{nanos}", std::process::id()));
+
+    fs::create_dir_all(&directory).await?;
+
+    for library in libraries {
+        let Some(rules) = library.extract.clone() else {
+            continue;
+        };
+
+        let path = root.join(&library.file.path);
+
+        tracing::debug!("Extracting natives from {}", path.display());
+        if let Err(err) = extract_jar(&path, &directory, rules).await {
+            clean(&directory).await;
+            return Err(err);
+        }
+    }
+
+    Ok(directory)
+}
+
+/// Removes a natives directory created by [extract]. Failures are only logged, as there is nothing
+/// else to be done about them.
+pub async fn clean(directory: &Path) {
+    match fs::remove_dir_all(directory).await {
+        Ok(()) => tracing::debug!("Removed natives directory {}", directory.display()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => tracing::warn!("Failed to remove {}: {err}", directory.display()),
+    }
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use tempfile::TempDir;
+    use utils::archive::CreateOptions;
+    use zip::ZipWriter;
+    use zip::write::SimpleFileOptions;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn extract_jar() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let source = root.join("source");
+        std::fs::create_dir_all(source.join("META-INF")).unwrap();
+        std::fs::write(source.join("liblwjgl.so"), "native").unwrap();
+        std::fs::write(source.join("META-INF/MANIFEST.MF"), "manifest").unwrap();
+
+        let jar = root.join("lwjgl-natives-linux.jar");
+        archive::create(&source, &jar, CreateOptions::default())
+            .await
+            .unwrap();
+
+        let rules = LibraryExtract {
+            exclude: vec!["META-INF/".to_string()],
+        };
+        let directory = root.join("natives");
+        super::extract_jar(&jar, &directory, rules).await.unwrap();
+
+        assert!(directory.join("liblwjgl.so").is_file());
+        assert!(!directory.join("META-INF").exists());
+
+        // archives with entries that would escape the directory are rejected
+        let jar = root.join("lwjgl-natives-evil.jar");
+        let mut writer = ZipWriter::new(std::fs::File::create(&jar).unwrap());
+        for name in ["liblwjgl.so", "../escape.so"] {
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(b"native").unwrap();
+        }
+        writer.finish().unwrap();
+
+        let rules = LibraryExtract { exclude: vec![] };
+        let result = super::extract_jar(&jar, &directory, rules).await;
+        assert!(matches!(result, Err(Error::Natives(..))));
+        assert!(!root.join("escape.so").exists());
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::path::PathBuf;
+use std::process::{ExitStatus, Stdio};
+
+use tokio::process::{Child, ChildStderr, ChildStdout, Command};
+
+use crate::command::LaunchCommand;
+use crate::{Result, natives};
+
+/// A running (or exited) game process. The natives directory of the launch is removed once the
+/// process has exited (see [GameProcess::wait]).
+#[derive(Debug)]
+pub struct GameProcess {
+    child: Child,
+    natives: PathBuf,
+}
+
+/// Spawns the command as a new process with piped output.
+pub(crate) async fn spawn(command: LaunchCommand, natives: PathBuf) -> Result<GameProcess> {
+    tracing::info!("Launching game: {}", command.redacted());
+
+    let child = Command::new(&command.program)
+        .args(&command.arguments)
+        .current_dir(&command.directory)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+
+    match child {
+        Ok(child) => Ok(GameProcess { child, natives }),
+        Err(err) => {
+            natives::clean(&natives).await;
+            Err(err.into())
+        }
+    }
+}
+
+impl GameProcess {
+    /// The OS-assigned process ID, or None if the process has already exited.
+    pub fn id(&self) -> Option<u32> {
+        self.child.id()
+    }
+
+    /// Takes the standard output stream of the game. Returns None if it has already been taken.
+    pub fn take_stdout(&mut self) -> Option<ChildStdout> {
+        self.child.stdout.take()
+    }
+
+    /// Takes the standard error stream of the game. Returns None if it has already been taken.
+    pub fn take_stderr(&mut self) -> Option<ChildStderr> {
+        self.child.stderr.take()
+    }
+
+    /// Waits for the game to exit, cleans up after it, and returns its exit status.
+    pub async fn wait(&mut self) -> Result<ExitStatus> {
+        let status = self.child.wait().await?;
+        tracing::info!("Game exited with {status}");
+        natives::clean(&self.natives).await;
+        Ok(status)
+    }
+
+    /// Forcefully kills the game and waits for it to exit.
+    pub async fn kill(&mut self) -> Result<()> {
+        self.child.kill().await?;
+        natives::clean(&self.natives).await;
+        Ok(())
+    }
+}
@@ -0,0 +1,568 @@
+// Copyright © 2023-2025 andre4ik3
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex as StdMutex, PoisonError};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use semver::VersionReq;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::{Mutex, broadcast, watch};
+use tokio::task::JoinHandle;
+
+use crate::crash::Analysis;
+use crate::log4j::{Level, LogBuffer, LogParser};
+use crate::{Error, GameProcess, LaunchOptions, Result};
+
+/// How many log lines can be buffered for slow log subscribers before they start missing lines.
+const LOG_CAPACITY: usize = 1024;
+
+/// How many parsed log records are kept for each running game.
+const LOG_RECORDS: usize = 4096;
+
+/// How long to wait for the rest of the output after a game exits.
+const OUTPUT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Information about a game started by the [Supervisor].
+#[derive(Clone, Debug)]
+pub struct RunningGame {
+    /// The ID of the instance that the game was started from.
+    pub instance: String,
+    /// The OS-assigned process ID of the game.
+    pub pid: Option<u32>,
+    /// When the game was started.
+    pub started: DateTime<Utc>,
+    /// The UUID of the account that the game was started with.
+    pub account_id: String,
+    /// The in-game username of the account that the game was started with.
+    pub username: String,
+}
+
+/// The stream that a [LogLine] was written to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+/// A single line of output from a running game.
+#[derive(Clone, Debug)]
+pub struct LogLine {
+    pub stream: LogStream,
+    pub line: String,
+}
+
+/// The different ways that a game can exit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExitKind {
+    /// The game exited on its own with a zero exit code.
+    Clean,
+    /// The game exited on its own with a non-zero exit code, or was terminated by a signal.
+    Crashed,
+    /// The game was killed using [Supervisor::kill].
+    Killed,
+}
+
+/// A report of how a game exited.
+#[derive(Clone, Debug)]
+pub struct ExitReport {
+    /// Information about the game while it was running.
+    pub game: RunningGame,
+    /// How the game exited.
+    pub kind: ExitKind,
+    /// The exit code of the process, or None if it was terminated by a signal.
+    pub code: Option<i32>,
+    /// When the game exited.
+    pub exited: DateTime<Utc>,
+    /// The analysis of the crash (see [crate::crash]). Only present if the game crashed.
+    pub analysis: Option<Analysis>,
+}
+
+/// Bookkeeping for a single running game.
+struct Entry {
+    game: RunningGame,
+    logs: broadcast::Sender<LogLine>,
+    records: Arc<StdMutex<LogBuffer>>,
+    /// Set to true to kill the game. It stays in place until the game exits, so that killing a
+    /// game that is already being killed still succeeds.
+    kill: watch::Sender<bool>,
+    exit: watch::Receiver<Option<ExitReport>>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Games that are currently running, by instance ID.
+    games: HashMap<String, Entry>,
+}
+
+/// Marks an instance as being launched for as long as it is alive. It is dropped once the launch
+/// either succeeds or fails, but also if the launch is cancelled halfway.
+struct Starting {
+    instances: Arc<StdMutex<HashSet<String>>>,
+    instance: String,
+}
+
+/// Keeps track of running games. It makes sure that each instance runs at most once, streams the
+/// output of games, and reports how they exited. The supervisor is a cheap handle that can be
+/// cloned and shared freely -- all clones refer to the same set of games.
+#[derive(Clone)]
+pub struct Supervisor {
+    state: Arc<Mutex<State>>,
+    /// Instances that are in the process of being launched. Only locked while the state is locked
+    /// as well, except when a [Starting] is dropped.
+    starting: Arc<StdMutex<HashSet<String>>>,
+    exits: broadcast::Sender<ExitReport>,
+}
+
+impl Default for Supervisor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Starting {
+    fn drop(&mut self) {
+        let mut instances = self
+            .instances
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        instances.remove(&self.instance);
+    }
+}
+
+/// What is needed to analyze a crash of a game once it exits.
+struct CrashContext {
+    directory: PathBuf,
+    java_version: VersionReq,
+    records: Arc<StdMutex<LogBuffer>>,
+    /// The tasks that read the output of the game.
+    pumps: Vec<JoinHandle<()>>,
+}
+
+impl CrashContext {
+    async fn analyze(self, game: &RunningGame) -> Option<Analysis> {
+        // wait for the rest of the output, so that the log is complete
+        for pump in self.pumps {
+            let _ = tokio::time::timeout(OUTPUT_TIMEOUT, pump).await;
+        }
+
+        let log = self.records.lock().ok()?.clone();
+        let since = game.started.into();
+        let analysis =
+            crate::crash::analyze(&self.directory, since, &self.java_version, Some(&log));
+
+        analysis
+            .await
+            .inspect_err(|err| tracing::warn!("Failed to analyze crash: {err}"))
+            .ok()
+    }
+}
+
+/// Forwards each line of a stream to the log channel, and parses it into the log buffer, until the
+/// stream ends.
+fn pump(
+    stream: impl AsyncRead + Unpin + Send + 'static,
+    kind: LogStream,
+    tx: broadcast::Sender<LogLine>,
+    records: Arc<StdMutex<LogBuffer>>,
+) -> JoinHandle<()> {
+    let mut parser = LogParser::new(match kind {
+        LogStream::Stdout => Level::Info,
+        LogStream::Stderr => Level::Warn,
+    });
+
+    let store = move |record| {
+        if let Ok(mut records) = records.lock() {
+            records.push(record);
+        }
+    };
+
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(stream).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            for record in parser.push(&line) {
+                store(record);
+            }
+
+            // sending only fails if nobody is listening, in which case the line is dropped
+            let _ = tx.send(LogLine { stream: kind, line });
+        }
+
+        if let Some(record) = parser.finish() {
+            store(record);
+        }
+    })
+}
+
+impl Supervisor {
+    /// Creates a new supervisor with no running games.
+    pub fn new() -> Self {
+        let (exits, _) = broadcast::channel(16);
+        Self {
+            state: Arc::new(Mutex::new(State::default())),
+            starting: Arc::new(StdMutex::new(HashSet::new())),
+            exits,
+        }
+    }
+
+    /// Launches an instance (see [crate::launch]) and starts supervising it. Fails with
+    /// [Error::AlreadyRunning] if the instance is already running or being launched.
+    #[tracing::instrument(name = "Supervisor::launch", skip(self, options))]
+    pub async fn launch(&self, instance: &str, options: LaunchOptions) -> Result<RunningGame> {
+        let instance = instance.to_string();
+
+        let _starting = {
+            let state = self.state.lock().await;
+            let mut starting = self.starting.lock().unwrap_or_else(PoisonError::into_inner);
+            if state.games.contains_key(&instance) || !starting.insert(instance.clone()) {
+                return Err(Error::AlreadyRunning(instance));
+            }
+
+            Starting {
+                instances: self.starting.clone(),
+                instance: instance.clone(),
+            }
+        };
+
+        let account = options.account.clone();
+        let directory = options.instance.clone();
+        let java_version = options.version.java_version.clone();
+        let result = crate::launch(options).await;
+
+        // the instance stays marked as starting until it is running (or has failed to launch)
+        let mut state = self.state.lock().await;
+        let mut process = result?;
+
+        let game = RunningGame {
+            instance: instance.clone(),
+            pid: process.id(),
+            started: Utc::now(),
+            account_id: account.id,
+            username: account.username,
+        };
+
+        let (logs, _) = broadcast::channel(LOG_CAPACITY);
+        let records = Arc::new(StdMutex::new(LogBuffer::new(LOG_RECORDS)));
+        let mut pumps = Vec::new();
+        if let Some(stdout) = process.take_stdout() {
+            pumps.push(pump(
+                stdout,
+                LogStream::Stdout,
+                logs.clone(),
+                records.clone(),
+            ));
+        }
+        if let Some(stderr) = process.take_stderr() {
+            pumps.push(pump(
+                stderr,
+                LogStream::Stderr,
+                logs.clone(),
+                records.clone(),
+            ));
+        }
+
+        let context = CrashContext {
+            directory,
+            java_version,
+            records: records.clone(),
+            pumps,
+        };
+
+        let (kill_tx, kill_rx) = watch::channel(false);
+        let (exit_tx, exit_rx) = watch::channel(None);
+
+        state.games.insert(
+            instance,
+            Entry {
+                game: game.clone(),
+                logs,
+                records,
+                kill: kill_tx,
+                exit: exit_rx,
+            },
+        );
+
+        let monitor = self
+            .clone()
+            .monitor(game.clone(), process, context, kill_rx, exit_tx);
+        tokio::spawn(monitor);
+        Ok(game)
+    }
+
+    /// Waits for a game to exit (or to be killed), then reports how it exited. Crashes are analyzed
+    /// before they are reported.
+    async fn monitor(
+        self,
+        game: RunningGame,
+        mut process: GameProcess,
+        context: CrashContext,
+        mut kill: watch::Receiver<bool>,
+        exit: watch::Sender<Option<ExitReport>>,
+    ) {
+        let result = tokio::select! {
+            status = process.wait() => status.map(|it| match it.success() {
+                true => (ExitKind::Clean, it.code()),
+                false => (ExitKind::Crashed, it.code()),
+            }),
+            Ok(()) = async { kill.wait_for(|it| *it).await.map(|_| ()) } => {
+                process.kill().await.map(|()| (ExitKind::Killed, None))
+            }
+        };
+
+        let (kind, code) = result.unwrap_or_else(|err| {
+            tracing::error!("Lost track of instance {}: {err}", game.instance);
+            (ExitKind::Crashed, None)
+        });
+
+        tracing::info!(
+            "Instance {} exited ({kind:?}, code {code:?})",
+            game.instance
+        );
+        let exited = Utc::now();
+        let analysis = match kind {
+            ExitKind::Crashed => context.analyze(&game).await,
+            ExitKind::Clean | ExitKind::Killed => None,
+        };
+
+        self.state.lock().await.games.remove(&game.instance);
+
+        let report = ExitReport {
+            game,
+            kind,
+            code,
+            exited,
+            analysis,
+        };
+
+        let _ = exit.send(Some(report.clone()));
+        let _ = self.exits.send(report);
+    }
+
+    /// Lists all running games.
+    pub async fn running(&self) -> Vec<RunningGame> {
+        let state = self.state.lock().await;
+        state.games.values().map(|it| it.game.clone()).collect()
+    }
+
+    /// Gets a running game by instance ID.
+    pub async fn get(&self, instance: &str) -> Option<RunningGame> {
+        let state = self.state.lock().await;
+        state.games.get(instance).map(|it| it.game.clone())
+    }
+
+    /// Whether an instance is currently running or being launched.
+    pub async fn is_running(&self, instance: &str) -> bool {
+        let state = self.state.lock().await;
+        let starting = self.starting.lock().unwrap_or_else(PoisonError::into_inner);
+        state.games.contains_key(instance) || starting.contains(instance)
+    }
+
+    /// Subscribes to the output of a running game. Only lines written after subscribing are
+    /// received.
+    pub async fn logs(&self, instance: &str) -> Option<broadcast::Receiver<LogLine>> {
+        let state = self.state.lock().await;
+        state.games.get(instance).map(|it| it.logs.subscribe())
+    }
+
+    /// Takes a snapshot of the parsed log of a running game (see [crate::log4j]).
+    pub async fn records(&self, instance: &str) -> Option<LogBuffer> {
+        let state = self.state.lock().await;
+        let records = state.games.get(instance)?.records.lock().ok()?;
+        Some(records.clone())
+    }
+
+    /// Subscribes to exit reports of all games.
+    pub fn exits(&self) -> broadcast::Receiver<ExitReport> {
+        self.exits.subscribe()
+    }
+
+    /// Waits until a running game exits and returns the report. Returns None if the instance isn't
+    /// running.
+    pub async fn wait(&self, instance: &str) -> Option<ExitReport> {
+        let mut exit = {
+            let state = self.state.lock().await;
+            state.games.get(instance)?.exit.clone()
+        };
+
+        let report = exit.wait_for(Option::is_some).await.ok()?;
+        report.clone()
+    }
+
+    /// Kills a running game. The exit is reported with [ExitKind::Killed].
+    pub async fn kill(&self, instance: &str) -> Result<()> {
+        let state = self.state.lock().await;
+        let entry = state.games.get(instance);
+        let entry = entry.ok_or_else(|| Error::NotRunning(instance.to_string()))?;
+
+        // the monitor may have just exited on its own, in which case there is nothing to kill
+        entry.kill.send_replace(true);
+        Ok(())
+    }
+}
+
+// === test ===
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    use semver::VersionReq;
+    use tempfile::TempDir;
+
+    use data::core::auth::{Account, AccountCredentials};
+    use data::core::game::{GameVersionAssetIndex, ResolvedGameVersion};
+    use data::core::library::{LibraryDownloadable, LibrarySource};
+
+    use super::*;
+
+    fn file(path: &str) -> LibraryDownloadable {
+        LibraryDownloadable {
+            path: path.to_string(),
+            checksum: "0".repeat(40),
+            size: 0,
+            url: "https://piston-data.mojang.com/".parse().unwrap(),
+            source: LibrarySource::Verified,
+        }
+    }
+
+    /// "Launches" a shell script instead of the game: `/bin/sh -c {script} {main class}`.
+    fn options(directory: &Path, script: &str) -> LaunchOptions {
+        std::fs::write(directory.join("client.jar"), []).unwrap();
+
+        let version = ResolvedGameVersion {
+            id: "test".to_string(),
+            java_version: VersionReq::STAR,
+            main_class: "main".to_string(),
+            libraries: vec![],
+            java_arguments: vec!["-c".to_string(), script.to_string()],
+            game_arguments: vec![],
+            logging: None,
+            client: file("client.jar"),
+            asset_index: GameVersionAssetIndex {
+                id: "test".to_string(),
+                file: file("indexes/test.json"),
+                total_size: 0,
+            },
+        };
+
+        let account = Account {
+            id: "id".to_string(),
+            username: "Player".to_string(),
+            has_profile: true,
+            token: "offline".to_string(),
+            expires: None,
+            credentials: AccountCredentials::Offline,
+        };
+
+        let mut options = LaunchOptions::new(version, "/bin/sh", account, directory);
+        options.libraries = PathBuf::from(directory);
+        options.client = directory.join("client.jar");
+        options
+    }
+
+    #[tokio::test]
+    async fn exit() {
+        let temp = TempDir::new().unwrap();
+        let supervisor = Supervisor::new();
+        let options = options(temp.path(), "sleep 0.5; echo hello; exit 3");
+
+        supervisor.launch("exit", options).await.unwrap();
+        let mut logs = supervisor.logs("exit").await.unwrap();
+
+        let line = logs.recv().await.unwrap();
+        assert_eq!(line.line, "hello");
+        assert_eq!(line.stream, LogStream::Stdout);
+
+        let records = supervisor.records("exit").await.unwrap();
+        assert_eq!(records.search("hello", Level::Info).count(), 1);
+
+        let report = supervisor.wait("exit").await.unwrap();
+        assert_eq!(report.kind, ExitKind::Crashed);
+        assert_eq!(report.code, Some(3));
+        assert_eq!(report.analysis.unwrap().diagnoses, []);
+        assert!(!supervisor.is_running("exit").await);
+    }
+
+    #[tokio::test]
+    async fn kill() {
+        let temp = TempDir::new().unwrap();
+        let supervisor = Supervisor::new();
+
+        supervisor
+            .launch("kill", options(temp.path(), "sleep 10"))
+            .await
+            .unwrap();
+        assert_eq!(supervisor.running().await.len(), 1);
+
+        // The same instance can't be launched twice
+        let Err(Error::AlreadyRunning(_)) = supervisor
+            .launch("kill", options(temp.path(), "true"))
+            .await
+        else {
+            panic!("launching a running instance should fail");
+        };
+
+        let mut exits = supervisor.exits();
+        supervisor.kill("kill").await.unwrap();
+        // killing it again while it is being killed is fine
+        supervisor.kill("kill").await.unwrap();
+
+        let report = tokio::time::timeout(Duration::from_secs(5), exits.recv()).await;
+        let report = report.expect("game wasn't killed in time").unwrap();
+        assert_eq!(report.kind, ExitKind::Killed);
+        assert!(supervisor.running().await.is_empty());
+    }
+
+    #[test]
+    fn cancel() {
+        // with the only blocking thread taken, the launch always waits for the file system
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .max_blocking_threads(1)
+            .build()
+            .unwrap();
+
+        runtime.block_on(async {
+            let temp = TempDir::new().unwrap();
+            let supervisor = Supervisor::new();
+
+            let (release, blocked) = std::sync::mpsc::channel::<()>();
+            let blocker = tokio::task::spawn_blocking(move || blocked.recv());
+
+            // the launch is dropped after the instance is marked as starting
+            tokio::select! {
+                biased;
+                _ = supervisor.launch("cancel", options(temp.path(), "true")) => {
+                    panic!("launch should still be in progress");
+                }
+                () = std::future::ready(()) => {}
+            }
+            assert!(!supervisor.is_running("cancel").await);
+
+            release.send(()).unwrap();
+            blocker.await.unwrap().unwrap();
+
+            supervisor
+                .launch("cancel", options(temp.path(), "true"))
+                .await
+                .unwrap();
+            let report = supervisor.wait("cancel").await.unwrap();
+            assert_eq!(report.kind, ExitKind::Clean);
+        });
+    }
+}
@@ -14,7 +14,7 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 use tokio::fs;
@@ -45,7 +45,12 @@ where
     /// Creates a new directory registry and loads any existing entries from the base into memory.
     #[tracing::instrument(name = "DirectoryRegistry::new")]
     pub async fn new(base: &str, file: &'static str) -> Result<DirectoryRegistry<T>> {
-        let base = directories::DATA.join(base);
+        Self::at(directories::DATA.join(base), file).await
+    }
+
+    /// Creates a new directory registry at a custom location, instead of in the data directory.
+    #[tracing::instrument(name = "DirectoryRegistry::at")]
+    pub async fn at(base: PathBuf, file: &'static str) -> Result<DirectoryRegistry<T>> {
         fs::create_dir_all(&base).await?;
 
         tracing::trace!("Creating new directory registry at {}", base.display());
@@ -59,6 +64,31 @@ where
         Ok(registry)
     }
 
+    /// The base directory that the entries are stored in.
+    pub fn base(&self) -> &Path {
+        &self.base
+    }
+
+    /// The directory of an entry with a specific ID (which might not exist).
+    pub fn path(&self, id: impl AsRef<str>) -> PathBuf {
+        self.base.join(id.as_ref())
+    }
+
+    /// The number of entries in the registry.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the registry has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterates over the IDs and entries in the in-memory entries cache, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &T)> {
+        self.entries.iter().map(|(id, data)| (id.as_str(), data))
+    }
+
     /// Gets an entry from the in-memory entries cache.
     pub fn get(&self, id: impl AsRef<str>) -> Option<&T> {
         self.entries.get(id.as_ref())
@@ -12,3 +12,710 @@
 //
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Safe extraction and creation of archives. Supported formats are `.tar.gz` (Java builds for Linux
+//! and macOS), `.zip` (Java builds for Windows, exports, and backups), and `.jar` (native
+//! libraries, mods).
+//!
+//! Extraction never writes outside of the destination directory: entries with absolute paths or
+//! `..` components are rejected, and so are symbolic links that point outside of it. Unix
+//! permissions stored in the archive are preserved.
+
+use std::cell::Cell;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Cursor, Read};
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use flate2::read::GzDecoder;
+use thiserror::Error;
+use tokio::task;
+use zip::result::ZipError;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("zip error: {0}")]
+    Zip(#[from] ZipError),
+    #[error("unsafe path in archive: {0}")]
+    UnsafePath(String),
+    #[error("unsupported archive format: {0}")]
+    UnsupportedFormat(PathBuf),
+}
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+/// The supported archive formats.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    /// A gzip-compressed tarball (`.tar.gz` or `.tgz`).
+    TarGz,
+    /// A zip archive (`.zip`).
+    Zip,
+    /// A Java archive (`.jar`), which is a zip archive with a different extension.
+    Jar,
+}
+
+impl Format {
+    /// Detects the format of an archive from its file name.
+    pub fn detect(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".jar") {
+            Some(Self::Jar)
+        } else {
+            None
+        }
+    }
+}
+
+/// How far along an operation is, in bytes. For extraction, this is how much of the archive file
+/// has been read. For creation, this is how much of the source files has been written.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Progress {
+    pub done: u64,
+    pub total: u64,
+}
+
+/// A callback that is called as an operation makes progress.
+pub type ProgressCallback = Arc<dyn Fn(Progress) + Send + Sync>;
+
+/// A filter for entries, given their (relative) path. Entries for which it returns false are
+/// skipped.
+pub type Filter = Arc<dyn Fn(&Path) -> bool + Send + Sync>;
+
+/// Options for [extract].
+#[derive(Clone, Default)]
+pub struct ExtractOptions {
+    /// If all entries are inside of a single top-level directory, extract the contents of that
+    /// directory instead of the directory itself.
+    pub strip_top_level: bool,
+    /// Only extract entries that pass the filter. The path given to the filter is the one after
+    /// stripping the top-level directory.
+    pub filter: Option<Filter>,
+    /// Skip symbolic and hard links instead of recreating them. Archives from untrusted sources
+    /// that never need links (e.g. modpacks) should be extracted with this.
+    pub skip_links: bool,
+    /// Called after each entry is extracted.
+    pub progress: Option<ProgressCallback>,
+}
+
+/// Options for [create].
+#[derive(Clone, Default)]
+pub struct CreateOptions {
+    /// Only add files and directories that pass the filter. The path given to the filter is
+    /// relative to the source directory.
+    pub filter: Option<Filter>,
+    /// Called after each file is added.
+    pub progress: Option<ProgressCallback>,
+}
+
+// === helpers ===
+
+/// Turns the path of an entry into a safe relative path. Returns None for paths that are absolute,
+/// contain `..`, or are empty.
+fn sanitize(path: &Path) -> Option<PathBuf> {
+    let mut output = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => output.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+
+    (!output.as_os_str().is_empty()).then_some(output)
+}
+
+/// Whether a symbolic link at `link` (relative to the destination) that points to `target` stays
+/// inside of the destination.
+fn is_link_safe(link: &Path, target: &Path) -> bool {
+    let mut depth = link.components().count() as isize - 1;
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => depth -= 1,
+            Component::RootDir | Component::Prefix(_) => return false,
+        }
+
+        if depth < 0 {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Finds the single top-level directory that all paths are in, if there is one.
+fn top_level(paths: &[PathBuf]) -> Option<PathBuf> {
+    let first = paths.first()?.components().next()?;
+    let shared = paths.iter().all(|it| it.components().next() == Some(first));
+    let nested = paths.iter().any(|it| it.components().count() > 1);
+    (shared && nested).then(|| PathBuf::from(first.as_os_str()))
+}
+
+/// Where an entry should be extracted to, relative to the destination. Returns None for entries
+/// that should be skipped.
+fn relative(path: &Path, prefix: Option<&Path>, options: &ExtractOptions) -> Option<PathBuf> {
+    let path = match prefix {
+        Some(prefix) => path.strip_prefix(prefix).ok()?.to_path_buf(),
+        None => path.to_path_buf(),
+    };
+
+    let included = options.filter.as_ref().is_none_or(|it| it(&path));
+    (!path.as_os_str().is_empty() && included).then_some(path)
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: Option<u32>) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    match mode {
+        Some(mode) => fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o777)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: Option<u32>) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(not(unix))]
+fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+    tracing::warn!(
+        "Skipping symbolic link {} -> {}",
+        link.display(),
+        target.display()
+    );
+    Ok(())
+}
+
+/// Creates the parent directories of an entry in the destination, and returns where the entry
+/// goes. Fails if any of the parents is a symbolic link: links are only checked one by one, so a
+/// chain of them (e.g. `d -> .` and `e -> d/..`) could otherwise lead outside of the destination.
+fn prepare(destination: &Path, path: &Path) -> Result<PathBuf> {
+    let mut output = destination.to_path_buf();
+    let mut components = path.components().peekable();
+
+    while let Some(component) = components.next() {
+        output.push(component);
+        if components.peek().is_none() {
+            break;
+        }
+
+        match fs::symlink_metadata(&output) {
+            Ok(metadata) if metadata.is_symlink() => {
+                return Err(Error::UnsafePath(path.display().to_string()));
+            }
+            Ok(metadata) if metadata.is_dir() => {}
+            _ => fs::create_dir(&output)?,
+        }
+    }
+
+    Ok(output)
+}
+
+/// Creates a directory entry in the destination.
+fn write_dir(destination: &Path, path: &Path) -> Result<()> {
+    let output = prepare(destination, path)?;
+    if fs::symlink_metadata(&output).is_ok_and(|it| it.is_symlink()) {
+        return Err(Error::UnsafePath(path.display().to_string()));
+    }
+
+    fs::create_dir_all(output)?;
+    Ok(())
+}
+
+/// Writes a single entry into the destination.
+fn write_file(
+    destination: &Path,
+    path: &Path,
+    reader: &mut impl Read,
+    mode: Option<u32>,
+) -> Result<()> {
+    let output = prepare(destination, path)?;
+
+    // never write through whatever was there before (e.g. a symbolic link)
+    if fs::symlink_metadata(&output).is_ok() {
+        fs::remove_file(&output)?;
+    }
+
+    io::copy(reader, &mut File::create(&output)?)?;
+    set_mode(&output, mode)?;
+    Ok(())
+}
+
+/// Creates a symbolic link in the destination, making sure it doesn't point outside of it.
+fn write_link(destination: &Path, path: &Path, target: &Path) -> Result<()> {
+    if !is_link_safe(path, target) {
+        return Err(Error::UnsafePath(format!(
+            "{} -> {}",
+            path.display(),
+            target.display()
+        )));
+    }
+
+    let output = prepare(destination, path)?;
+    if fs::symlink_metadata(&output).is_ok() {
+        fs::remove_file(&output)?;
+    }
+
+    symlink(target, &output)?;
+    Ok(())
+}
+
+/// A reader that counts how many bytes have been read through it.
+struct Counting<R> {
+    inner: R,
+    count: Rc<Cell<u64>>,
+}
+
+impl<R: Read> Read for Counting<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.count.set(self.count.get() + read as u64);
+        Ok(read)
+    }
+}
+
+// === extraction ===
+
+fn extract_zip(archive: &Path, destination: &Path, options: &ExtractOptions) -> Result<()> {
+    let file = File::open(archive)?;
+    let total = file.metadata()?.len();
+    let mut zip = ZipArchive::new(BufReader::new(file))?;
+
+    let mut paths = Vec::with_capacity(zip.len());
+    for name in zip.file_names() {
+        paths.push(sanitize(Path::new(name)).ok_or_else(|| Error::UnsafePath(name.to_string()))?);
+    }
+
+    let prefix = options.strip_top_level.then(|| top_level(&paths)).flatten();
+    let mut done = 0;
+
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index)?;
+        done += entry.compressed_size();
+
+        let path = sanitize(Path::new(entry.name()));
+        let path = path.ok_or_else(|| Error::UnsafePath(entry.name().to_string()))?;
+
+        if let Some(path) = relative(&path, prefix.as_deref(), options) {
+            if entry.is_dir() {
+                write_dir(destination, &path)?;
+            } else if entry.is_symlink() && options.skip_links {
+                tracing::debug!("Skipping symbolic link {}", path.display());
+            } else if entry.is_symlink() {
+                let mut target = String::new();
+                entry.read_to_string(&mut target)?;
+                write_link(destination, &path, Path::new(&target))?;
+            } else {
+                let mode = entry.unix_mode();
+                write_file(destination, &path, &mut entry, mode)?;
+            }
+        }
+
+        if let Some(progress) = &options.progress {
+            progress(Progress { done, total });
+        }
+    }
+
+    Ok(())
+}
+
+fn extract_tar_gz(archive: &Path, destination: &Path, options: &ExtractOptions) -> Result<()> {
+    let total = fs::metadata(archive)?.len();
+    let count = Rc::new(Cell::new(0));
+    let open = || -> Result<_> {
+        let inner = BufReader::new(File::open(archive)?);
+        let reader = Counting {
+            inner,
+            count: count.clone(),
+        };
+        Ok(tar::Archive::new(GzDecoder::new(reader)))
+    };
+
+    // finding the top-level directory takes a separate pass over the archive
+    let prefix = match options.strip_top_level {
+        true => {
+            let mut paths = Vec::new();
+            for entry in open()?.entries()? {
+                paths.extend(sanitize(&entry?.path()?));
+            }
+            top_level(&paths)
+        }
+        false => None,
+    };
+
+    count.set(0);
+    for entry in open()?.entries()? {
+        let mut entry = entry?;
+        let raw = entry.path()?.to_path_buf();
+        let path = sanitize(&raw).ok_or_else(|| Error::UnsafePath(raw.display().to_string()))?;
+
+        if let Some(path) = relative(&path, prefix.as_deref(), options) {
+            let kind = entry.header().entry_type();
+            let mode = entry.header().mode().ok();
+
+            if kind.is_dir() {
+                write_dir(destination, &path)?;
+            } else if (kind.is_symlink() || kind.is_hard_link()) && options.skip_links {
+                tracing::debug!("Skipping link {}", path.display());
+            } else if kind.is_symlink() {
+                let target = entry.link_name()?.unwrap_or_default().to_path_buf();
+                write_link(destination, &path, &target)?;
+            } else if kind.is_hard_link() {
+                // hard links point to another entry of the archive, which was extracted before
+                let target = entry.link_name()?.unwrap_or_default().to_path_buf();
+                let target = sanitize(&target)
+                    .and_then(|it| relative(&it, prefix.as_deref(), options))
+                    .ok_or_else(|| Error::UnsafePath(target.display().to_string()))?;
+                let source = prepare(destination, &target)?;
+                if fs::symlink_metadata(&source)?.is_symlink() {
+                    return Err(Error::UnsafePath(target.display().to_string()));
+                }
+                let mut source = File::open(source)?;
+                write_file(destination, &path, &mut source, mode)?;
+            } else if kind.is_file() || kind.is_contiguous() {
+                write_file(destination, &path, &mut entry, mode)?;
+            } else {
+                tracing::debug!("Skipping special entry {}", path.display());
+            }
+        }
+
+        if let Some(progress) = &options.progress {
+            progress(Progress {
+                done: count.get(),
+                total,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Extracts an archive into a destination directory (which is created if it doesn't exist). The
+/// format is detected from the file name (see [Format::detect]).
+pub async fn extract(archive: &Path, destination: &Path, options: ExtractOptions) -> Result<()> {
+    let format = Format::detect(archive).ok_or_else(|| Error::UnsupportedFormat(archive.into()))?;
+    extract_as(archive, destination, format, options).await
+}
+
+/// Same as [extract], but with an explicit format.
+#[tracing::instrument(name = "archive::extract", skip(options))]
+pub async fn extract_as(
+    archive: &Path,
+    destination: &Path,
+    format: Format,
+    options: ExtractOptions,
+) -> Result<()> {
+    let archive = archive.to_path_buf();
+    let destination = destination.to_path_buf();
+
+    task::spawn_blocking(move || {
+        fs::create_dir_all(&destination)?;
+        match format {
+            Format::TarGz => extract_tar_gz(&archive, &destination, &options),
+            Format::Zip | Format::Jar => extract_zip(&archive, &destination, &options),
+        }
+    })
+    .await
+    .expect("blocking thread panicked")
+}
+
+/// Reads one entry of a zip archive (or a JAR) that is already in memory, e.g. the metadata of a
+/// mod or a JAR nested in another one. Returns None if the archive has no entry with that name.
+/// This function is blocking!
+pub fn read_from(data: &[u8], name: &str) -> Result<Option<Vec<u8>>> {
+    let mut zip = ZipArchive::new(Cursor::new(data))?;
+    let entry = match zip.by_name(name) {
+        Ok(entry) => entry,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+
+    // The size comes from the header, so it only bounds the read and the buffer grows as the
+    // data actually arrives, instead of trusting it to allocate up front
+    let size = entry.size();
+    let mut buffer = Vec::new();
+    entry.take(size).read_to_end(&mut buffer)?;
+    Ok(Some(buffer))
+}
+
+// === creation ===
+
+/// Lists everything in a directory recursively (without following symbolic links), in a stable
+/// order, as paths relative to the root.
+fn walk(
+    root: &Path,
+    directory: &Path,
+    filter: Option<&Filter>,
+    output: &mut Vec<PathBuf>,
+) -> Result<()> {
+    let mut entries = fs::read_dir(root.join(directory))?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|it| it.file_name());
+
+    for entry in entries {
+        let path = directory.join(entry.file_name());
+        if filter.is_some_and(|it| !it(&path)) {
+            continue;
+        }
+
+        output.push(path.clone());
+        if entry.file_type()?.is_dir() {
+            walk(root, &path, filter, output)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The name of a path inside of a zip archive, which always uses forward slashes.
+fn zip_name(path: &Path) -> String {
+    let parts: Vec<_> = path
+        .components()
+        .map(|it| it.as_os_str().to_string_lossy())
+        .collect();
+    parts.join("/")
+}
+
+fn create_zip(source: &Path, archive: &Path, options: &CreateOptions) -> Result<()> {
+    let mut paths = Vec::new();
+    walk(source, Path::new(""), options.filter.as_ref(), &mut paths)?;
+
+    let metadata = paths
+        .iter()
+        .map(|it| fs::symlink_metadata(source.join(it)))
+        .collect::<io::Result<Vec<_>>>()?;
+    let total = metadata
+        .iter()
+        .filter(|it| it.is_file())
+        .map(|it| it.len())
+        .sum();
+
+    if let Some(parent) = archive.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut zip = ZipWriter::new(File::create(archive)?);
+    let mut done = 0;
+
+    for (path, metadata) in paths.iter().zip(metadata) {
+        let name = zip_name(path);
+        let mut file_options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .large_file(metadata.len() >= u32::MAX as u64);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file_options = file_options.unix_permissions(metadata.permissions().mode() & 0o777);
+        }
+
+        if metadata.is_symlink() {
+            let target = fs::read_link(source.join(path))?;
+            zip.add_symlink(name, target.to_string_lossy(), file_options)?;
+        } else if metadata.is_dir() {
+            zip.add_directory(name, file_options)?;
+        } else {
+            zip.start_file(name, file_options)?;
+            io::copy(&mut File::open(source.join(path))?, &mut zip)?;
+            done += metadata.len();
+
+            if let Some(progress) = &options.progress {
+                progress(Progress { done, total });
+            }
+        }
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+/// Creates a zip archive (e.g. for exports and backups) out of the contents of a source directory.
+/// Symbolic links are stored as links, and not followed.
+#[tracing::instrument(name = "archive::create", skip(options))]
+pub async fn create(source: &Path, archive: &Path, options: CreateOptions) -> Result<()> {
+    let source = source.to_path_buf();
+    let archive = archive.to_path_buf();
+
+    task::spawn_blocking(move || create_zip(&source, &archive, &options))
+        .await
+        .expect("blocking thread panicked")
+}
+
+// === test ===
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn zip_slip() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let archive = root.join("evil.zip");
+
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        zip.start_file("../evil.txt", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"evil").unwrap();
+        zip.finish().unwrap();
+
+        let result = extract(&archive, &root.join("out"), ExtractOptions::default()).await;
+        assert!(matches!(result, Err(Error::UnsafePath(_))));
+        assert!(!root.join("evil.txt").exists());
+
+        assert!(is_link_safe(
+            Path::new("bin/java"),
+            Path::new("../lib/libjli.so")
+        ));
+        assert!(!is_link_safe(
+            Path::new("bin/java"),
+            Path::new("../../etc/passwd")
+        ));
+        assert!(!is_link_safe(Path::new("java"), Path::new("/usr/bin/java")));
+    }
+
+    #[tokio::test]
+    async fn link_chain() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let archive = root.join("evil.zip");
+
+        // each link stays inside on its own, but `e` resolves to the parent of the destination
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        zip.add_symlink("d", ".", SimpleFileOptions::default())
+            .unwrap();
+        zip.add_symlink("e", "d/..", SimpleFileOptions::default())
+            .unwrap();
+        zip.start_file("e/escaped.txt", SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"evil").unwrap();
+        zip.finish().unwrap();
+
+        let result = extract(&archive, &root.join("out"), ExtractOptions::default()).await;
+        assert!(matches!(result, Err(Error::UnsafePath(_))));
+        assert!(!root.join("escaped.txt").exists());
+
+        let options = ExtractOptions {
+            skip_links: true,
+            ..Default::default()
+        };
+        let destination = root.join("skipped");
+        extract(&archive, &destination, options).await.unwrap();
+        assert!(fs::symlink_metadata(destination.join("d")).is_err());
+        assert!(destination.join("e/escaped.txt").is_file());
+    }
+
+    #[tokio::test]
+    async fn tar_gz() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let archive = root.join("jdk.tar.gz");
+
+        let encoder = flate2::write::GzEncoder::new(
+            File::create(&archive).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut builder = tar::Builder::new(encoder);
+        for (name, mode) in [("jdk-21/bin/java", 0o755), ("jdk-21/release", 0o644)] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(4);
+            header.set_mode(mode);
+            builder
+                .append_data(&mut header, name, &b"data"[..])
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let destination = root.join("out");
+        let options = ExtractOptions {
+            strip_top_level: true,
+            ..Default::default()
+        };
+        extract(&archive, &destination, options).await.unwrap();
+
+        assert!(destination.join("bin/java").is_file());
+        assert!(destination.join("release").is_file());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(destination.join("bin/java"))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o755);
+        }
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        let source = root.join("instance");
+        fs::create_dir_all(source.join("mods")).unwrap();
+        fs::create_dir_all(source.join("logs")).unwrap();
+        fs::write(source.join("options.txt"), "fov:0.5").unwrap();
+        fs::write(source.join("mods/sodium.jar"), "mod").unwrap();
+        fs::write(source.join("logs/latest.log"), "log").unwrap();
+
+        let progress = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let options = CreateOptions {
+            filter: Some(Arc::new(|path: &Path| !path.starts_with("logs"))),
+            progress: Some({
+                let progress = progress.clone();
+                Arc::new(move |it| progress.lock().unwrap().push(it))
+            }),
+        };
+
+        let archive = root.join("export.zip");
+        create(&source, &archive, options).await.unwrap();
+        assert_eq!(
+            progress.lock().unwrap().last(),
+            Some(&Progress {
+                done: 10,
+                total: 10
+            })
+        );
+
+        let destination = root.join("out");
+        extract(&archive, &destination, ExtractOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(destination.join("options.txt")).unwrap(),
+            "fov:0.5"
+        );
+        assert_eq!(
+            fs::read_to_string(destination.join("mods/sodium.jar")).unwrap(),
+            "mod"
+        );
+        let data = fs::read(&archive).unwrap();
+        assert_eq!(
+            read_from(&data, "mods/sodium.jar").unwrap().as_deref(),
+            Some(b"mod".as_slice())
+        );
+        assert_eq!(read_from(&data, "logs/latest.log").unwrap(), None);
+        assert!(!destination.join("logs").exists());
+    }
+}
@@ -19,6 +19,7 @@
 //! This module is full of random code that doesn't distinctly belong anywhere else or warrant its
 //! own separate module, yet is still used in various places throughout the code. It includes:
 //!
+//! - [archive] - Safe extraction and creation of archives.
 //! - [directories] - Abstractions for common directories across platforms.
 //! - [log] - Functions for setting up logging and panic hooks.
 //! - [platforms] - Constants for the current platform.